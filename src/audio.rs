@@ -10,6 +10,7 @@ pub struct AudioManager {
     footstep_sound: Option<Sound>,
     jump_sound_special: Option<Sound>,
     footstep_sound_special: Option<Sound>,
+    hurt_sound: Option<Sound>,
     sounds_enabled: bool,
     load_errors: Vec<String>,
 }
@@ -26,12 +27,12 @@ impl AudioManager {
             footstep_sound: None,
             jump_sound_special: None,
             footstep_sound_special: None,
+            hurt_sound: None,
             sounds_enabled: true,
             load_errors: Vec::new(),
         }
     }
 
-    #[allow(dead_code)]
     pub fn is_enabled(&self) -> bool {
         self.sounds_enabled
     }
@@ -126,6 +127,7 @@ impl AudioManager {
 
         let jump_special_bytes = Self::generate_beep_bytes(660.0, 0.15, 44100);
         let footstep_special_bytes = Self::generate_beep_bytes(350.0, 0.1, 44100);
+        let hurt_bytes = Self::generate_beep_bytes(280.0, 0.2, 44100);
 
         match Self::load_sound_safe(&jump_bytes, "jump").await {
             Ok(sound) => self.jump_sound = Some(sound),
@@ -198,6 +200,14 @@ impl AudioManager {
                 self.load_errors.push(e);
             }
         }
+
+        match Self::load_sound_safe(&hurt_bytes, "hurt").await {
+            Ok(sound) => self.hurt_sound = Some(sound),
+            Err(e) => {
+                eprintln!("{}", e);
+                self.load_errors.push(e);
+            }
+        }
     }
 
     fn play_sound(sound: &Option<Sound>, volume: f32) {
@@ -240,6 +250,12 @@ impl AudioManager {
         }
     }
 
+    pub fn play_hurt(&self) {
+        if self.sounds_enabled {
+            Self::play_sound(&self.hurt_sound, 0.8);
+        }
+    }
+
     pub fn play_level_complete(&self) {
         if self.sounds_enabled {
             Self::play_sound(&self.level_complete_sound, 0.85);
@@ -1,21 +1,114 @@
 use crate::constants::*;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldBounds {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl WorldBounds {
+    pub fn new(left: f32, right: f32, top: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    /// Anything whose top edge drops below this line has fallen out of the level.
+    pub fn fall_death_y(&self) -> f32 {
+        self.bottom
+    }
+}
+
+pub fn level_world_bounds(level: usize) -> WorldBounds {
+    match level {
+        2 => WorldBounds::new(0.0, WORLD_WIDTH, -400.0, FALL_DEATH_Y),
+        _ => WorldBounds::new(0.0, WORLD_WIDTH, 0.0, FALL_DEATH_Y),
+    }
+}
+
+/// Zoom needed to keep both points, plus a margin, inside `view`. Never zooms in
+/// past 1.0; callers decide what to do when the result drops below their limit.
+pub fn framing_zoom(a: (f32, f32), b: (f32, f32), view: (f32, f32), margin: f32) -> f32 {
+    let needed_w = (a.0 - b.0).abs() + PLAYER_WIDTH + margin * 2.0;
+    let needed_h = (a.1 - b.1).abs() + PLAYER_HEIGHT + margin * 2.0;
+    (view.0 / needed_w).min(view.1 / needed_h).min(1.0)
+}
+
 pub struct Camera {
     pub x: f32,
     pub y: f32,
+    look_ahead: f32,
 }
 
 impl Camera {
     pub fn new() -> Self {
-        Self { x: 0.0, y: 0.0 }
+        Self {
+            x: 0.0,
+            y: 0.0,
+            look_ahead: 0.0,
+        }
+    }
+
+    /// Jumps straight to the framing `update` would settle on, without easing.
+    pub fn snap(&mut self, target: (f32, f32), view: (f32, f32), bounds: &WorldBounds) {
+        self.look_ahead = 0.0;
+        self.x = Self::clamp_x(target.0 - view.0 / 2.0, view.0, bounds);
+        self.y = Self::clamp_y(target.1 - view.1 / 2.0, view.1, bounds);
+    }
+
+    /// Follows `target` (a world-space centre point). Horizontal tracking is
+    /// immediate with a look-ahead that eases towards the facing direction;
+    /// vertical tracking only moves once the target leaves the deadzone and
+    /// then eases in, so small hops don't bob the screen.
+    pub fn update(
+        &mut self,
+        target: (f32, f32),
+        facing_right: bool,
+        view: (f32, f32),
+        bounds: &WorldBounds,
+        shake_offset: f32,
+        dt: f32,
+    ) {
+        let (view_w, view_h) = view;
+
+        let look_ahead_target = if facing_right {
+            CAMERA_LOOK_AHEAD
+        } else {
+            -CAMERA_LOOK_AHEAD
+        };
+        self.look_ahead +=
+            (look_ahead_target - self.look_ahead) * Self::ease(CAMERA_LOOK_AHEAD_SPEED, dt);
+        let target_x = target.0 - view_w / 2.0 + self.look_ahead;
+        self.x = Self::clamp_x(target_x + shake_offset, view_w, bounds);
+
+        let deadzone_top = self.y + view_h * (0.5 - CAMERA_DEADZONE_Y);
+        let deadzone_bottom = self.y + view_h * (0.5 + CAMERA_DEADZONE_Y);
+        let mut target_y = self.y;
+        if target.1 < deadzone_top {
+            target_y -= deadzone_top - target.1;
+        } else if target.1 > deadzone_bottom {
+            target_y += target.1 - deadzone_bottom;
+        }
+        let eased_y = self.y + (target_y - self.y) * Self::ease(CAMERA_FOLLOW_SPEED_Y, dt);
+        self.y = Self::clamp_y(eased_y, view_h, bounds);
     }
 
-    pub fn update(&mut self, player_x: f32, screen_width: f32, shake_offset: f32) {
-        let target_x = player_x - screen_width / 2.0;
+    fn ease(speed: f32, dt: f32) -> f32 {
+        1.0 - (-speed * dt).exp()
+    }
 
-        let min_x = 0.0;
-        let max_x = WORLD_WIDTH - screen_width;
+    fn clamp_x(x: f32, view_w: f32, bounds: &WorldBounds) -> f32 {
+        x.clamp(bounds.left, (bounds.right - view_w).max(bounds.left))
+    }
 
-        self.x = (target_x + shake_offset).clamp(min_x, max_x);
+    fn clamp_y(y: f32, view_h: f32, bounds: &WorldBounds) -> f32 {
+        // Levels shorter than the view stay anchored to their floor.
+        let max_y = bounds.bottom - view_h;
+        y.clamp(bounds.top.min(max_y), max_y)
     }
 }
@@ -98,6 +98,15 @@ pub fn create_level_coins(level: usize) -> Vec<Coin> {
             coins.push(Coin::new(2200.0, 320.0));
             coins.push(Coin::new(2400.0, 270.0));
             coins.push(Coin::new(2600.0, 220.0));
+            coins.push(Coin::new(2830.0, 140.0));
+            coins.push(Coin::new(2970.0, 60.0));
+            coins.push(Coin::new(2830.0, -20.0));
+            coins.push(Coin::new(2970.0, -100.0));
+            coins.push(Coin::new(2830.0, -180.0));
+            coins.push(Coin::new(3060.0, -260.0));
+            coins.push(Coin::new(3320.0, -180.0));
+            coins.push(Coin::new(3570.0, -80.0));
+            coins.push(Coin::new(3820.0, 30.0));
         }
         3 => {
             coins.push(Coin::new(150.0, 420.0));
@@ -161,3 +170,16 @@ pub fn create_level_coins(level: usize) -> Vec<Coin> {
 
     coins
 }
+
+/// Bonus coins sitting on the ledges from `create_level_coop_ledges`.
+pub fn create_level_coop_coins(level: usize) -> Vec<Coin> {
+    let positions: &[(f32, f32)] = match level {
+        1 => &[(2940.0, 270.0), (3000.0, 270.0)],
+        2 => &[(910.0, -40.0), (970.0, -40.0)],
+        3 => &[(3040.0, 270.0), (3100.0, 270.0)],
+        4 => &[(3090.0, 270.0), (3150.0, 270.0)],
+        5 => &[(960.0, 40.0), (1020.0, 40.0)],
+        _ => &[],
+    };
+    positions.iter().map(|&(x, y)| Coin::new(x, y)).collect()
+}
@@ -25,6 +25,35 @@ pub const WORLD_WIDTH: f32 = 4200.0;
 pub const LEVEL_COMPLETE_X: f32 = 4000.0;
 pub const FALL_DEATH_Y: f32 = 600.0;
 
+pub const CAMERA_LOOK_AHEAD: f32 = 80.0;
+pub const CAMERA_LOOK_AHEAD_SPEED: f32 = 3.0;
+pub const CAMERA_DEADZONE_Y: f32 = 0.15;
+pub const CAMERA_FOLLOW_SPEED_Y: f32 = 6.0;
+pub const COOP_MIN_ZOOM: f32 = 0.6;
+pub const COOP_SPLIT_MERGE_ZOOM: f32 = 0.7;
+pub const COOP_ZOOM_SPEED: f32 = 4.0;
+pub const COOP_FRAME_MARGIN: f32 = 120.0;
+/// Space left between a pulled-forward player and the spot they're set down beside.
+pub const COOP_REGROUP_GAP: f32 = 8.0;
+pub const REVIVE_BUBBLE_RADIUS: f32 = 40.0;
+pub const PARTNER_HEAD_TOLERANCE: f32 = 16.0;
+pub const PARTNER_MIN_OVERLAP: f32 = 0.4;
+pub const PARTNER_BOOST_MULTIPLIER: f32 = 1.35;
+pub const PARTNER_GRAB_RANGE: f32 = 80.0;
+pub const PARTNER_THROW_SPEED_X: f32 = 320.0;
+pub const PARTNER_THROW_SPEED_Y: f32 = -420.0;
+pub const PARTNER_THROW_STUN: f32 = 0.35;
+pub const REVIVE_BUBBLE_SPEED: f32 = 90.0;
+pub const REVIVE_BUBBLE_BOB: f32 = 6.0;
+pub const COOP_DROP_OUT_HOLD: f32 = 1.5;
+pub const PRESSURE_PLATE_WIDTH: f32 = 60.0;
+pub const PRESSURE_PLATE_HEIGHT: f32 = 8.0;
+pub const LEVER_WIDTH: f32 = 16.0;
+pub const LEVER_HEIGHT: f32 = 36.0;
+pub const TIMED_SWITCH_SIZE: f32 = 28.0;
+pub const DOOR_WIDTH: f32 = 20.0;
+pub const BRIDGE_HEIGHT: f32 = 20.0;
+
 pub const SCREEN_WIDTH: u32 = 800;
 pub const SCREEN_HEIGHT: u32 = 600;
 
@@ -69,6 +98,7 @@ pub const FOOTSTEP_INTERVAL: f32 = 0.25;
 pub const MIN_VELOCITY_FOR_FOOTSTEP: f32 = 10.0;
 pub const SAVE_CHECK_INTERVAL: f32 = 2.0;
 pub const MAX_SAVE_SLOTS: usize = 3;
+pub const MAX_PASSPHRASE_LEN: usize = 64;
 pub const MAX_NAME_LENGTH: usize = 20;
 pub const MIN_NAME_LENGTH: usize = 3;
 pub const ENEMY_ANIMATION_SPEED: f32 = 0.12;
@@ -87,7 +117,7 @@ pub const ESTIMATED_PLATFORMS_PER_LEVEL: usize = 20;
 pub const ESTIMATED_COINS_PER_LEVEL: usize = 30;
 pub const ESTIMATED_CHECKPOINTS_PER_LEVEL: usize = 5;
 
-pub const DIFFICULTY_EASY: f32 = 1.0;
+pub const DIFFICULTY_EASY: f32 = 1.25;
 pub const DIFFICULTY_NORMAL: f32 = 1.0;
 pub const DIFFICULTY_HARD: f32 = 0.7;
 pub const DIFFICULTY_INSANE: f32 = 0.5;
@@ -113,3 +143,47 @@ pub const FONT_SIZE_LARGE: f32 = 32.0;
 pub const FONT_SIZE_EXTRA_LARGE: f32 = 48.0;
 
 pub const ASSIST_MODE_SLOW_MOTION: f32 = 0.5;
+
+pub const VERSUS_ROUND_INTRO: f32 = 2.0;
+pub const VERSUS_PICKUP_DELAY: f32 = 0.5;
+pub const KING_OF_THE_HILL_GOAL: f32 = 30.0;
+pub const HILL_ZONE_HEIGHT: f32 = 90.0;
+pub const COIN_RUSH_GOAL: f32 = 15.0;
+pub const COIN_RUSH_SPAWN_INTERVAL: f32 = 1.2;
+pub const COIN_RUSH_MAX_COINS: usize = 6;
+pub const CAPTURE_THE_CRAB_GOAL: f32 = 30.0;
+pub const CRAB_SIZE: f32 = 28.0;
+pub const ITEM_BOX_INTERVAL: f32 = 6.0;
+pub const ITEM_BOX_MAX: usize = 2;
+pub const ITEM_BOX_SIZE: f32 = 26.0;
+pub const SPEED_BOOST_DURATION: f32 = 4.0;
+pub const SPEED_BOOST_MULTIPLIER: f32 = 1.6;
+pub const SHELL_SIZE: f32 = 18.0;
+pub const SHELL_SPEED: f32 = 320.0;
+pub const SHELL_TURN_RATE: f32 = 2.5;
+pub const SHELL_LIFETIME: f32 = 4.0;
+pub const FREEZE_TRAP_WIDTH: f32 = 40.0;
+pub const FREEZE_TRAP_LIFETIME: f32 = 12.0;
+pub const FREEZE_DURATION: f32 = 1.5;
+pub const SHIELD_BREAK_GRACE: f32 = 0.5;
+pub const NET_DEFAULT_PORT: u16 = 7777;
+/// Lobbies on this many ports from the default one up show in LAN lists.
+pub const NET_LAN_PORTS: u16 = 8;
+pub const NET_DEFAULT_INPUT_DELAY: u32 = 2;
+pub const NET_MAX_INPUT_DELAY: u32 = 6;
+/// Most online ticks run in one frame when catching up after a hitch.
+pub const NET_MAX_CATCH_UP: u32 = 4;
+pub const STREAM_DEFAULT_PORT: u16 = 7790;
+/// Lines shown per page of the Versus stats screen.
+pub const VERSUS_STATS_LINES: usize = 10;
+/// Bracket rounds shown side by side.
+pub const TOURNAMENT_COLUMNS: usize = 4;
+
+pub const PLAYER_MAX_HEALTH: u32 = 3;
+pub const INVULNERABILITY_DURATION: f32 = 1.5;
+pub const INVULNERABILITY_BLINK_RATE: f32 = 12.0;
+pub const KNOCKBACK_SPEED_X: f32 = 260.0;
+pub const KNOCKBACK_SPEED_Y: f32 = -280.0;
+pub const KNOCKBACK_STUN: f32 = 0.3;
+pub const HUD_HEART_SIZE: f32 = 10.0;
+pub const HUD_HEART_SPACING: f32 = 28.0;
@@ -0,0 +1,102 @@
+use crate::constants::*;
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CoopLives {
+    Shared,
+    Separate,
+}
+
+impl CoopLives {
+    pub fn toggle(self) -> Self {
+        match self {
+            CoopLives::Shared => CoopLives::Separate,
+            CoopLives::Separate => CoopLives::Shared,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CoopLives::Shared => "SHARED",
+            CoopLives::Separate => "SEPARATE",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct CoopRules {
+    pub lives: CoopLives,
+    /// When set, a player who dies while their partner is still up floats
+    /// in a bubble instead of spending a life and respawning at the checkpoint.
+    pub revive_bubble: bool,
+    /// Players collide with each other: stand on heads, boosted jumps, grab and throw.
+    pub partner_physics: bool,
+}
+
+impl Default for CoopRules {
+    fn default() -> Self {
+        Self {
+            lives: CoopLives::Shared,
+            revive_bubble: true,
+            partner_physics: true,
+        }
+    }
+}
+
+pub struct ReviveBubble {
+    pub x: f32,
+    pub y: f32,
+    bob_timer: f32,
+}
+
+impl ReviveBubble {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            bob_timer: 0.0,
+        }
+    }
+
+    /// Drifts towards `target` (the partner's centre) at a fixed speed.
+    pub fn update(&mut self, target: (f32, f32), dt: f32) {
+        self.bob_timer += dt;
+        let dx = target.0 - self.x;
+        let dy = target.1 - self.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance > 1.0 {
+            let step = (REVIVE_BUBBLE_SPEED * dt).min(distance);
+            self.x += dx / distance * step;
+            self.y += dy / distance * step;
+        }
+    }
+
+    pub fn touches(&self, rect: (f32, f32, f32, f32)) -> bool {
+        let (rx, ry, rw, rh) = rect;
+        let nearest_x = self.x.clamp(rx, rx + rw);
+        let nearest_y = self.y.clamp(ry, ry + rh);
+        let dx = self.x - nearest_x;
+        let dy = self.y - nearest_y;
+        dx * dx + dy * dy <= REVIVE_BUBBLE_RADIUS * REVIVE_BUBBLE_RADIUS
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32, label: &str, color: Color) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y + (self.bob_timer * 3.0).sin() * REVIVE_BUBBLE_BOB;
+        draw_circle(
+            screen_x,
+            screen_y,
+            REVIVE_BUBBLE_RADIUS,
+            Color::new(color.r, color.g, color.b, 0.2),
+        );
+        draw_circle_lines(screen_x, screen_y, REVIVE_BUBBLE_RADIUS, 2.0, color);
+        let label_width = measure_text(label, None, 24, 1.0).width;
+        draw_text(
+            label,
+            screen_x - label_width / 2.0,
+            screen_y + 8.0,
+            24.0,
+            color,
+        );
+    }
+}
@@ -1,18 +1,18 @@
 use super::*;
 
 impl Game {
-    fn draw_level_world(&self) {
-        let screen_left = self.camera.x - COLLISION_MARGIN;
-        let screen_right = self.camera.x + screen_width() + COLLISION_MARGIN;
-        let screen_top = self.camera.y - COLLISION_MARGIN;
-        let screen_bottom = self.camera.y + screen_height() + COLLISION_MARGIN;
+    fn draw_level_world(&self, camera: &Camera, view: (f32, f32)) {
+        let screen_left = camera.x - COLLISION_MARGIN;
+        let screen_right = camera.x + view.0 + COLLISION_MARGIN;
+        let screen_top = camera.y - COLLISION_MARGIN;
+        let screen_bottom = camera.y + view.1 + COLLISION_MARGIN;
         for platform in &self.platforms {
             if platform.x + platform.width >= screen_left
                 && platform.x <= screen_right
                 && platform.y + platform.height >= screen_top
                 && platform.y <= screen_bottom
             {
-                platform.draw(self.camera.x, self.camera.y);
+                platform.draw(camera.x, camera.y);
             }
         }
         for checkpoint in &self.checkpoints {
@@ -21,7 +21,25 @@ impl Game {
                 && checkpoint.y >= screen_top
                 && checkpoint.y <= screen_bottom
             {
-                checkpoint.draw(self.camera.x, self.camera.y);
+                checkpoint.draw(camera.x, camera.y);
+            }
+        }
+        for gate in &self.gates {
+            if gate.x + gate.width >= screen_left
+                && gate.x <= screen_right
+                && gate.y + gate.height >= screen_top
+                && gate.y <= screen_bottom
+            {
+                gate.draw(camera.x, camera.y);
+            }
+        }
+        for trigger in &self.triggers {
+            if trigger.x >= screen_left
+                && trigger.x <= screen_right
+                && trigger.y >= screen_top
+                && trigger.y <= screen_bottom
+            {
+                trigger.draw(camera.x, camera.y);
             }
         }
         for coin in &self.coins {
@@ -31,7 +49,7 @@ impl Game {
                 && coin.y >= screen_top
                 && coin.y <= screen_bottom
             {
-                coin.draw(self.camera.x, self.camera.y);
+                coin.draw(camera.x, camera.y);
             }
         }
         for enemy in &self.enemies {
@@ -41,15 +59,17 @@ impl Game {
                 && enemy.y >= screen_top
                 && enemy.y <= screen_bottom
             {
-                enemy.draw(self.camera.x, self.camera.y);
+                enemy.draw(camera.x, camera.y);
             }
         }
-        self.player.draw(self.camera.x, self.camera.y);
+        if self.revive_bubble_p1.is_none() && !self.coop_out_p1 {
+            self.player.draw(camera.x, camera.y);
+        }
         for particle in &self.particles {
-            particle.draw(self.camera.x, self.camera.y, self.colorblind_mode);
+            particle.draw(camera.x, camera.y, self.colorblind_mode);
         }
         for bounce in &self.coin_bounces {
-            bounce.draw(self.camera.x, self.camera.y, self.colorblind_mode);
+            bounce.draw(camera.x, camera.y, self.colorblind_mode);
         }
     }
     fn draw_level_hud(&self, include_time_label: bool) {
@@ -110,6 +130,9 @@ impl Game {
             }
         };
         draw_text(&lives_text, 10.0, 130.0, 28.0 * font_scale, lives_color);
+        if self.health_enabled() {
+            self.draw_health_hearts(&self.player, 20.0, 155.0);
+        }
         let time_width = measure_text(&time_text, None, (40.0 * font_scale) as u16, 1.0).width;
         draw_text(
             &time_text,
@@ -119,6 +142,866 @@ impl Game {
             time_color,
         );
     }
+    /// Sets up a world-space camera over `view` (in world units) drawn into
+    /// `viewport`, or the whole window when `None`. The rect is given with a
+    /// negative height so y grows downwards like the default screen camera.
+    fn set_world_camera(view: (f32, f32), viewport: Option<(i32, i32, i32, i32)>) {
+        let mut camera = Camera2D::from_display_rect(Rect::new(0.0, view.1, view.0, -view.1));
+        camera.viewport = viewport;
+        set_camera(&camera);
+    }
+    fn draw_coop_world(&self, camera: &Camera, view: (f32, f32)) {
+        self.draw_level_world(camera, view);
+        if let Some(ref p2) = self.player2 {
+            if self.revive_bubble_p2.is_none() && !self.coop_out_p2 {
+                p2.draw(camera.x, camera.y);
+            }
+        }
+        let (p1_color, p2_color) = if self.colorblind_mode {
+            (BLACK, DARKGRAY)
+        } else {
+            (BLUE, RED)
+        };
+        if let Some(ref bubble) = self.revive_bubble_p1 {
+            bubble.draw(camera.x, camera.y, "P1", p1_color);
+        }
+        if let Some(ref bubble) = self.revive_bubble_p2 {
+            bubble.draw(camera.x, camera.y, "P2", p2_color);
+        }
+    }
+    fn draw_coop_scene(&self) {
+        let (sw, sh) = (screen_width(), screen_height());
+        if self.coop_split_active {
+            let half_view = (sw, sh / 2.0);
+            // GL viewports are measured from the bottom of the window.
+            Self::set_world_camera(
+                half_view,
+                Some((0, (sh / 2.0) as i32, sw as i32, (sh / 2.0) as i32)),
+            );
+            self.draw_coop_world(&self.camera, half_view);
+            Self::set_world_camera(half_view, Some((0, 0, sw as i32, (sh / 2.0) as i32)));
+            self.draw_coop_world(&self.camera_p2, half_view);
+            set_default_camera();
+            draw_line(0.0, sh / 2.0, sw, sh / 2.0, 3.0, BLACK);
+        } else {
+            let view = (sw / self.coop_zoom, sh / self.coop_zoom);
+            Self::set_world_camera(view, None);
+            self.draw_coop_world(&self.camera, view);
+            set_default_camera();
+        }
+    }
+    fn coop_tag_label(&self, is_p1: bool) -> String {
+        let (name, bubbled, out, lives) = if is_p1 {
+            (
+                "P1",
+                self.revive_bubble_p1.is_some(),
+                self.coop_out_p1,
+                self.lives,
+            )
+        } else {
+            (
+                "P2",
+                self.revive_bubble_p2.is_some(),
+                self.coop_out_p2,
+                self.lives_p2,
+            )
+        };
+        if out {
+            format!("{} OUT", name)
+        } else if bubbled {
+            format!("{} BUBBLE", name)
+        } else if self.coop_rules.lives == CoopLives::Separate {
+            format!("{} x{}", name, lives)
+        } else {
+            name.to_string()
+        }
+    }
+    fn coop_tag_width(&self, label: &str, show_hearts: bool) -> f32 {
+        let label_width =
+            measure_text(label, None, (24.0 * self.font_size_scale) as u16, 1.0).width;
+        if show_hearts {
+            label_width + 20.0 + HUD_HEART_SPACING * PLAYER_MAX_HEALTH as f32
+        } else {
+            label_width
+        }
+    }
+    fn draw_coop_player_tag(&self, player: &Player, is_p1: bool, x: f32, y: f32) {
+        let color = match (is_p1, self.colorblind_mode) {
+            (true, false) => BLUE,
+            (true, true) => BLACK,
+            (false, false) => RED,
+            (false, true) => DARKGRAY,
+        };
+        let label = self.coop_tag_label(is_p1);
+        draw_text(&label, x, y, 24.0 * self.font_size_scale, color);
+        if self.health_enabled() && self.coop_player_active(is_p1) {
+            let label_width = self.coop_tag_width(&label, false);
+            self.draw_health_hearts(player, x + label_width + 20.0, y - 8.0);
+        }
+    }
+    fn draw_coop_hud(&self) {
+        let font_scale = self.font_size_scale;
+        let (sw, sh) = (screen_width(), screen_height());
+        let mut shared = format!(
+            "Level: {} | Coins: {}/{} | Score: {}",
+            self.current_level, self.coins_collected, self.total_coins, self.score
+        );
+        if self.coop_rules.lives == CoopLives::Shared {
+            shared.push_str(&format!(" | Lives: {}", self.lives));
+        }
+        draw_text(&shared, 10.0, 30.0, 24.0 * font_scale, BLACK);
+        let time_text = format!("Time: {}s", self.time_remaining as u32);
+        let time_color = if self.time_remaining < TIME_WARNING_RED {
+            if self.colorblind_mode {
+                DARKGRAY
+            } else {
+                RED
+            }
+        } else {
+            BLACK
+        };
+        let time_width = measure_text(&time_text, None, (24.0 * font_scale) as u16, 1.0).width;
+        draw_text(
+            &time_text,
+            sw - time_width - 10.0,
+            30.0,
+            24.0 * font_scale,
+            time_color,
+        );
+        self.draw_coop_player_tag(&self.player, true, 10.0, 60.0);
+        if let Some(ref p2) = self.player2 {
+            if self.coop_split_active {
+                // Each tag sits in the corner of its own half.
+                self.draw_coop_player_tag(p2, false, 10.0, sh / 2.0 + 30.0);
+            } else {
+                let tag_width = self.coop_tag_width(
+                    &self.coop_tag_label(false),
+                    self.health_enabled() && self.coop_player_active(false),
+                );
+                self.draw_coop_player_tag(p2, false, sw - tag_width - 10.0, 60.0);
+            }
+        }
+    }
+    /// Centred list of menu rows with the usual `>` marker on the selected one.
+    fn draw_option_rows(&self, rows: &[String], selection: usize, start_y: f32, spacing: f32) {
+        let option_size = 30.0;
+        for (i, row) in rows.iter().enumerate() {
+            let y = start_y + spacing * i as f32;
+            let width = measure_text(row, None, option_size as u16, 1.0).width;
+            let color = if i == selection { BLACK } else { GRAY };
+            if i == selection {
+                draw_text(
+                    ">",
+                    screen_width() / 2.0 - width / 2.0 - 30.0,
+                    y,
+                    option_size,
+                    BLACK,
+                );
+            }
+            draw_text(
+                row,
+                screen_width() / 2.0 - width / 2.0,
+                y,
+                option_size,
+                color,
+            );
+        }
+    }
+    fn draw_centered_title(&self, title: &str, y: f32) {
+        let title_size = 48.0;
+        let title_width = measure_text(title, None, title_size as u16, 1.0).width;
+        draw_text(
+            title,
+            screen_width() / 2.0 - title_width / 2.0,
+            y,
+            title_size,
+            BLACK,
+        );
+    }
+    fn draw_centered_hint(&self, text: &str) {
+        let inst_size = 16.0;
+        let inst_width = measure_text(text, None, inst_size as u16, 1.0).width;
+        draw_text(
+            text,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 40.0,
+            inst_size,
+            GRAY,
+        );
+    }
+    /// Scaled-down arena map fitted into the given box, with spawns marked.
+    fn draw_arena_preview(&self, arena: &VersusLayout, x: f32, y: f32, w: f32, h: f32) {
+        draw_rectangle(x, y, w, h, Color::new(0.9, 0.9, 0.9, 1.0));
+        draw_rectangle_lines(x, y, w, h, 2.0, BLACK);
+        let bounds = arena.bounds;
+        let scale = (w / (bounds.right - bounds.left)).min(h / (bounds.bottom - bounds.top));
+        let origin_x = x + (w - (bounds.right - bounds.left) * scale) / 2.0;
+        let origin_y = y + (h - (bounds.bottom - bounds.top) * scale) / 2.0;
+        let to_screen = |px: f32, py: f32| {
+            (
+                origin_x + (px - bounds.left) * scale,
+                origin_y + (py - bounds.top) * scale,
+            )
+        };
+        for platform in &arena.platforms {
+            let left = platform.x.max(bounds.left);
+            let right = (platform.x + platform.width).min(bounds.right);
+            if right <= left {
+                continue;
+            }
+            let (sx, sy) = to_screen(left, platform.y.min(bounds.bottom));
+            let height = (platform.height * scale)
+                .max(2.0)
+                .min(origin_y + (bounds.bottom - bounds.top) * scale - sy);
+            draw_rectangle(sx, sy, (right - left) * scale, height, DARKGRAY);
+        }
+        for hazard in &arena.hazards {
+            let (sx, sy) = to_screen(hazard.x, hazard.y);
+            draw_rectangle(
+                sx,
+                sy,
+                hazard.width * scale,
+                (hazard.height * scale).max(2.0),
+                RED,
+            );
+        }
+        let seats = arena.spawns.iter().take(MAX_VERSUS_PLAYERS);
+        for (i, &(spawn_x, spawn_y)) in seats.enumerate() {
+            let (sx, sy) = to_screen(spawn_x + PLAYER_WIDTH / 2.0, spawn_y + PLAYER_HEIGHT / 2.0);
+            draw_circle(sx, sy, (PLAYER_WIDTH * scale / 2.0).max(3.0), slot_color(i));
+        }
+        if arena.wrap {
+            let (left_x, mid_y) = to_screen(bounds.left, (bounds.top + bounds.bottom) / 2.0);
+            let (right_x, _) = to_screen(bounds.right, bounds.top);
+            draw_text("<", left_x + 4.0, mid_y, 24.0, BLUE);
+            draw_text(">", right_x - 16.0, mid_y, 24.0, BLUE);
+        }
+    }
+    fn draw_versus_field(&self) {
+        let camera_x = self.camera.x;
+        let camera_y = self.camera.y;
+        for platform in &self.versus_platforms {
+            platform.draw(camera_x, camera_y);
+        }
+        for hazard in &self.versus_hazards {
+            hazard.draw(camera_x, camera_y);
+        }
+        self.draw_versus_objectives(camera_x, camera_y);
+        let time = get_time() as f32;
+        for item_box in &self.versus_item_boxes {
+            item_box.draw(camera_x, camera_y, time);
+        }
+        for trap in &self.versus_traps {
+            trap.draw(camera_x, camera_y);
+        }
+        // In a wraparound arena a player crossing the edge shows on both sides.
+        let wrap_offsets: &[f32] = if self.versus_wrap {
+            let width = self.versus_bounds.right - self.versus_bounds.left;
+            &[0.0, width, -width]
+        } else {
+            &[0.0]
+        };
+        for &offset in wrap_offsets {
+            for fighter in &self.versus_fighters {
+                fighter.draw(camera_x + offset, camera_y);
+            }
+        }
+        if self.versus_rules.mode == VersusMode::CaptureTheCrab {
+            self.versus_crab.draw(camera_x, camera_y);
+        }
+        for shell in &self.versus_shells {
+            shell.draw(camera_x, camera_y);
+        }
+        // Scoreboard: odd seats down the left, even seats down the right.
+        let score_size = if self.versus_fighters.len() > 2 {
+            20.0
+        } else {
+            24.0
+        };
+        let mode = self.versus_rules.mode;
+        for fighter in &self.versus_fighters {
+            let mut text = match mode {
+                VersusMode::Deathmatch => format!(
+                    "{}: {} kills | {} pts",
+                    fighter.name(),
+                    fighter.kills,
+                    fighter.points
+                ),
+                _ => format!(
+                    "{}: {} | {} kills",
+                    fighter.name(),
+                    mode.format_objective(fighter.objective),
+                    fighter.kills
+                ),
+            };
+            if fighter.out {
+                text.push_str(" | OUT");
+            } else if self.versus_rules.stocks.is_some() {
+                text.push_str(&format!(" | {} stock", fighter.stocks));
+            }
+            if self.versus_rules.items {
+                let item = fighter.item.map_or("-", |item| item.label());
+                text.push_str(&format!(" | [{}]", item));
+            }
+            let y = 30.0 + 38.0 * (fighter.slot / 2) as f32;
+            let align = |text: &str, size: f32| {
+                if fighter.slot % 2 == 0 {
+                    20.0
+                } else {
+                    let width = measure_text(text, None, size as u16, 1.0).width;
+                    screen_width() - width - 20.0
+                }
+            };
+            draw_text(
+                &text,
+                align(&text, score_size),
+                y,
+                score_size,
+                fighter.color(),
+            );
+            // Handicaps sit under the score so everyone can see them.
+            let handicap = &self.versus_rules.handicaps[fighter.slot];
+            if !handicap.is_neutral() {
+                let tag = handicap.tag();
+                draw_text(&tag, align(&tag, 14.0), y + 14.0, 14.0, DARKGRAY);
+            }
+        }
+        let time_text = if self.versus_rules.time_limit.is_some() {
+            format!("{}", self.versus_time_remaining as u32)
+        } else {
+            "--".to_string()
+        };
+        let time_width = measure_text(&time_text, None, 28u16, 1.0).width;
+        let time_color = if self.versus_rules.time_limit.is_none() {
+            BLACK
+        } else if self.versus_time_remaining < 60.0 {
+            RED
+        } else if self.versus_time_remaining < 120.0 {
+            ORANGE
+        } else {
+            BLACK
+        };
+        draw_text(
+            &time_text,
+            screen_width() / 2.0 - time_width / 2.0,
+            30.0,
+            28.0,
+            time_color,
+        );
+        let mut round_parts = Vec::new();
+        if let Some(goal) = mode.goal() {
+            round_parts.push(format!("{}: {}", mode.label(), mode.format_objective(goal)));
+        }
+        if self.versus_rules.best_of > 1 {
+            round_parts.push(format!(
+                "Round {}/{} | {}",
+                self.versus_round,
+                self.versus_rules.best_of,
+                self.versus_rounds_text()
+            ));
+        }
+        if self.versus_rules.teams == VersusTeams::TwoVsTwo {
+            round_parts.push(match mode {
+                VersusMode::Deathmatch => format!(
+                    "KOs {} - {}",
+                    self.versus_team_tally(0).0,
+                    self.versus_team_tally(1).0
+                ),
+                _ => format!(
+                    "{} - {}",
+                    mode.format_objective(self.versus_team_objective(0)),
+                    mode.format_objective(self.versus_team_objective(1))
+                ),
+            });
+        }
+        if !round_parts.is_empty() {
+            let round_text = round_parts.join(" | ");
+            let round_width = measure_text(&round_text, None, 20u16, 1.0).width;
+            draw_text(
+                &round_text,
+                screen_width() / 2.0 - round_width / 2.0,
+                56.0,
+                20.0,
+                GRAY,
+            );
+        }
+    }
+    /// The hill zone, tinted for whoever holds it alone, and loose coins.
+    /// The crab is drawn after the fighters so it sits on top of its carrier.
+    fn draw_versus_objectives(&self, camera_x: f32, camera_y: f32) {
+        match self.versus_rules.mode {
+            VersusMode::KingOfTheHill => {
+                let (x, y, w, h) = self.versus_hill;
+                let color = self.versus_hill_holder().map_or(GRAY, slot_color);
+                draw_rectangle(
+                    x - camera_x,
+                    y - camera_y,
+                    w,
+                    h,
+                    Color::new(color.r, color.g, color.b, 0.25),
+                );
+                draw_rectangle_lines(x - camera_x, y - camera_y, w, h, 2.0, color);
+                let label = "HILL";
+                let label_width = measure_text(label, None, 18u16, 1.0).width;
+                draw_text(
+                    label,
+                    x - camera_x + w / 2.0 - label_width / 2.0,
+                    y - camera_y + 18.0,
+                    18.0,
+                    color,
+                );
+            }
+            VersusMode::CoinRush => {
+                for coin in &self.versus_coins {
+                    coin.draw_as_coin(self.versus_coin_rotation, camera_x, camera_y);
+                }
+            }
+            VersusMode::Deathmatch | VersusMode::CaptureTheCrab => {}
+        }
+    }
+    /// Rounds won per team, e.g. "2 - 1" or "1 - 0 - 1".
+    fn versus_rounds_text(&self) -> String {
+        self.versus_team_rounds
+            .iter()
+            .map(|rounds| rounds.to_string())
+            .collect::<Vec<_>>()
+            .join(" - ")
+    }
+    /// "ROUND N" card shown while `versus_round_intro` counts down, with the
+    /// result of the round that just ended.
+    fn draw_versus_round_intro(&self) {
+        draw_rectangle(
+            0.0,
+            0.0,
+            screen_width(),
+            screen_height(),
+            Color::new(0.0, 0.0, 0.0, 0.4),
+        );
+        let result = match self.versus_round_result {
+            Some(team) => format!(
+                "{} TAKES ROUND {}",
+                self.versus_team_name(team),
+                self.versus_round - 1
+            ),
+            None => format!("ROUND {} DRAWN", self.versus_round - 1),
+        };
+        let title = format!("ROUND {}", self.versus_round);
+        for (text, size, y) in [(result.as_str(), 28.0, -50.0), (title.as_str(), 56.0, 10.0)] {
+            let width = measure_text(text, None, size as u16, 1.0).width;
+            draw_text(
+                text,
+                screen_width() / 2.0 - width / 2.0,
+                screen_height() / 2.0 + y,
+                size,
+                WHITE,
+            );
+        }
+    }
+    fn draw_health_hearts(&self, player: &Player, x: f32, y: f32) {
+        let full_color = if self.colorblind_mode { DARKGRAY } else { RED };
+        for i in 0..PLAYER_MAX_HEALTH {
+            let color = if i < player.health {
+                full_color
+            } else {
+                LIGHTGRAY
+            };
+            draw_heart(
+                x + i as f32 * HUD_HEART_SPACING,
+                y,
+                HUD_HEART_SIZE * self.font_size_scale,
+                color,
+            );
+        }
+    }
+
+    /// Final standings, shared by the end screen and spectators.
+    fn draw_versus_results(&self) {
+        let title = "GAME OVER";
+        let title_size = 48.0;
+        let title_width = measure_text(title, None, title_size as u16, 1.0).width;
+        draw_text(
+            title,
+            screen_width() / 2.0 - title_width / 2.0,
+            150.0,
+            title_size,
+            BLACK,
+        );
+        let (winner_text, winner_color) = match self.versus_match_winner() {
+            Some(team) => (
+                format!("{} WINS!", self.versus_team_name(team)),
+                slot_color(team),
+            ),
+            None => ("DRAW!".to_string(), GRAY),
+        };
+        let winner_size = 36.0;
+        let winner_width = measure_text(&winner_text, None, winner_size as u16, 1.0).width;
+        draw_text(
+            &winner_text,
+            screen_width() / 2.0 - winner_width / 2.0,
+            220.0,
+            winner_size,
+            winner_color,
+        );
+        if self.versus_rules.best_of > 1 {
+            let rounds_text = format!("Rounds {}", self.versus_rounds_text());
+            let rounds_width = measure_text(&rounds_text, None, 24u16, 1.0).width;
+            draw_text(
+                &rounds_text,
+                screen_width() / 2.0 - rounds_width / 2.0,
+                260.0,
+                24.0,
+                BLACK,
+            );
+        }
+        let mut standings: Vec<_> = self.versus_fighters.iter().collect();
+        let mode = self.versus_rules.mode;
+        standings.sort_by(|a, b| {
+            b.objective_total
+                .total_cmp(&a.objective_total)
+                .then((b.kills, b.points).cmp(&(a.kills, a.points)))
+        });
+        let score_size = if standings.len() > 2 { 24.0 } else { 28.0 };
+        let score_spacing = score_size + 8.0;
+        for (i, fighter) in standings.iter().enumerate() {
+            let cpu = if fighter.cpu.is_some() { " (CPU)" } else { "" };
+            let mut text = format!(
+                "{}. Player {}{}: {} kills | {} points",
+                i + 1,
+                fighter.slot + 1,
+                cpu,
+                fighter.kills,
+                fighter.points
+            );
+            if mode != VersusMode::Deathmatch {
+                text.push_str(&format!(
+                    " | {}",
+                    mode.describe_total(fighter.objective_total)
+                ));
+            }
+            let width = measure_text(&text, None, score_size as u16, 1.0).width;
+            draw_text(
+                &text,
+                screen_width() / 2.0 - width / 2.0,
+                300.0 + score_spacing * i as f32,
+                score_size,
+                fighter.color(),
+            );
+        }
+        let rules_y = 300.0 + score_spacing * standings.len() as f32 + 14.0;
+        let rules_text = self.versus_rules.summary();
+        let rules_width = measure_text(&rules_text, None, 20u16, 1.0).width;
+        draw_text(
+            &rules_text,
+            screen_width() / 2.0 - rules_width / 2.0,
+            rules_y,
+            20.0,
+            GRAY,
+        );
+    }
+
+    fn draw_versus_stats(&self) {
+        self.draw_centered_title("VERSUS STATS", 100.0);
+        let page = ["RECORDS", "HEAD TO HEAD", "RECENT MATCHES"][self.versus_stats_page];
+        let page_label = format!("< {} >", page);
+        let page_width = measure_text(&page_label, None, 28u16, 1.0).width;
+        draw_text(
+            &page_label,
+            screen_width() / 2.0 - page_width / 2.0,
+            150.0,
+            28.0,
+            DARKGRAY,
+        );
+        let history = &self.versus_history;
+        let lines: Vec<String> = match self.versus_stats_page {
+            0 => history
+                .player_records()
+                .iter()
+                .map(|r| {
+                    format!(
+                        "{}: {} of {} won ({:.0}%) | best streak {} | most points {}",
+                        r.name,
+                        r.wins,
+                        r.played,
+                        r.win_rate() * 100.0,
+                        r.best_streak,
+                        r.most_points
+                    )
+                })
+                .collect(),
+            1 => history
+                .head_to_head()
+                .iter()
+                .map(|h| {
+                    let mut line = format!(
+                        "{} {} - {} {} ({:.0}%)",
+                        h.names.0,
+                        h.wins.0,
+                        h.wins.1,
+                        h.names.1,
+                        h.win_rate() * 100.0
+                    );
+                    if h.draws > 0 {
+                        line.push_str(&format!(" | {} drawn", h.draws));
+                    }
+                    line
+                })
+                .collect(),
+            _ => history
+                .matches
+                .iter()
+                .rev()
+                .map(|m| {
+                    let winner = match m.winner {
+                        Some(team) => m
+                            .players
+                            .iter()
+                            .filter(|p| p.team == team)
+                            .map(|p| p.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" & "),
+                        None => "Draw".to_string(),
+                    };
+                    format!(
+                        "{} | {} | {} | {}",
+                        format_date(m.timestamp),
+                        m.arena,
+                        m.scoreline(),
+                        winner
+                    )
+                })
+                .collect(),
+        };
+        if lines.is_empty() {
+            let empty = "No Versus matches played yet";
+            let empty_width = measure_text(empty, None, 22u16, 1.0).width;
+            draw_text(
+                empty,
+                screen_width() / 2.0 - empty_width / 2.0,
+                300.0,
+                22.0,
+                GRAY,
+            );
+        }
+        for (i, line) in lines.iter().take(VERSUS_STATS_LINES).enumerate() {
+            let width = measure_text(line, None, 20u16, 1.0).width;
+            draw_text(
+                line,
+                screen_width() / 2.0 - width / 2.0,
+                200.0 + i as f32 * 30.0,
+                20.0,
+                BLACK,
+            );
+        }
+        self.draw_centered_hint("LEFT/RIGHT: Page | ESC: Back");
+    }
+
+    fn draw_versus_handicaps(&self) {
+        self.draw_centered_title("HANDICAPS", 100.0);
+        let slot = self.versus_handicap_player;
+        let handicap = &self.versus_rules.handicaps[slot];
+        let cpu = if self.versus_rules.is_cpu(slot) {
+            " (CPU)"
+        } else {
+            ""
+        };
+        let mut rows = vec![format!("PLAYER: < P{}{} >", slot + 1, cpu)];
+        rows.extend(handicap.row_labels());
+        rows.push("RESET ALL".to_string());
+        rows.push("BACK".to_string());
+        self.draw_option_rows(&rows, self.versus_handicap_selection, 160.0, 38.0);
+        let tags: Vec<(usize, String)> = (0..self.versus_rules.players)
+            .filter(|&seat| !self.versus_rules.handicaps[seat].is_neutral())
+            .map(|seat| {
+                let tag = self.versus_rules.handicaps[seat].tag();
+                (seat, format!("P{}: {}", seat + 1, tag))
+            })
+            .collect();
+        for (i, (seat, tag)) in tags.iter().enumerate() {
+            let width = measure_text(tag, None, 18u16, 1.0).width;
+            draw_text(
+                tag,
+                screen_width() / 2.0 - width / 2.0,
+                470.0 + i as f32 * 22.0,
+                18.0,
+                slot_color(*seat),
+            );
+        }
+        self.draw_centered_hint("ARROWS: Change | ENTER: Select | ESC: Back");
+    }
+
+    fn draw_tournament_setup(&self) {
+        self.draw_centered_title("TOURNAMENT", 100.0);
+        let setup = &self.tournament_setup;
+        let resume = match &self.tournament {
+            Some(saved) => format!(
+                "RESUME: {} PLAYERS, ROUND {}",
+                saved.names.len(),
+                saved.rounds.len()
+            ),
+            None => "RESUME: NOTHING SAVED".to_string(),
+        };
+        let cursor = if setup.selection == 0 { "_" } else { "" };
+        let rows = [
+            format!("ADD PLAYER: {}{}", setup.input, cursor),
+            format!("FORMAT: {}", setup.kind.label()),
+            format!("START ({} PLAYERS)", setup.names.len()),
+            resume,
+            "BACK".to_string(),
+        ];
+        self.draw_option_rows(&rows, setup.selection, 160.0, 42.0);
+        // Entrants in seed order, four to a line.
+        for (line, names) in setup.names.chunks(4).enumerate() {
+            let text = names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| format!("{}. {}", line * 4 + i + 1, name))
+                .collect::<Vec<_>>()
+                .join("   ");
+            let width = measure_text(&text, None, 20u16, 1.0).width;
+            draw_text(
+                &text,
+                screen_width() / 2.0 - width / 2.0,
+                370.0 + line as f32 * 26.0,
+                20.0,
+                DARKGRAY,
+            );
+        }
+        let description = match setup.selection {
+            0 => "Type a name and press ENTER; BACKSPACE on an empty name removes the last",
+            1 => "Out after one loss, or after two with a lower bracket",
+            2 => "Seeded in the order entered; starting replaces any saved tournament",
+            3 => "Carry on with the tournament saved last time",
+            _ => "",
+        };
+        let desc_width = measure_text(description, None, 18u16, 1.0).width;
+        draw_text(
+            description,
+            screen_width() / 2.0 - desc_width / 2.0,
+            490.0,
+            18.0,
+            DARKGRAY,
+        );
+        let error = setup.error.as_deref().unwrap_or("");
+        let error_width = measure_text(error, None, 22u16, 1.0).width;
+        draw_text(
+            error,
+            screen_width() / 2.0 - error_width / 2.0,
+            525.0,
+            22.0,
+            RED,
+        );
+        self.draw_centered_hint("UP/DOWN: Move | LEFT/RIGHT: Change | ENTER: Select | ESC: Back");
+    }
+
+    /// The latest rounds side by side, a column each, with the next match
+    /// or the champion underneath.
+    fn draw_tournament_bracket(&self) {
+        let Some(tournament) = &self.tournament else {
+            return;
+        };
+        self.draw_centered_title(tournament.kind.label(), 80.0);
+        let shown = tournament.rounds.len().min(TOURNAMENT_COLUMNS);
+        let first = tournament.rounds.len() - shown;
+        let column_width = screen_width() / TOURNAMENT_COLUMNS as f32;
+        let next = tournament.next_match();
+        for (column, round) in tournament.rounds[first..].iter().enumerate() {
+            let index = first + column;
+            let x = column_width * column as f32 + 16.0;
+            draw_text(&format!("ROUND {}", index + 1), x, 130.0, 22.0, BLACK);
+            let mut y = 160.0;
+            for (i, pairing) in round.pairings.iter().enumerate() {
+                for player in pairing.players {
+                    let color = match pairing.winner {
+                        Some(winner) if winner == player => BLACK,
+                        Some(_) => LIGHTGRAY,
+                        None if next == Some((index, i)) => DARKGREEN,
+                        None => GRAY,
+                    };
+                    draw_text(&tournament.names[player], x, y, 18.0, color);
+                    y += 16.0;
+                }
+                y += 8.0;
+            }
+            for &player in &round.byes {
+                let text = format!("{} (bye)", tournament.names[player]);
+                draw_text(&text, x, y, 18.0, GRAY);
+                y += 16.0;
+            }
+        }
+        let status = match (tournament.champion(), tournament.next_pairing()) {
+            (Some(champion), _) => format!("{} WINS THE TOURNAMENT!", tournament.names[champion]),
+            (None, Some(pairing)) => format!(
+                "NEXT: {} (P1) vs {} (P2)",
+                tournament.names[pairing.players[0]], tournament.names[pairing.players[1]]
+            ),
+            (None, None) => String::new(),
+        };
+        let status_width = measure_text(&status, None, 28u16, 1.0).width;
+        draw_text(
+            &status,
+            screen_width() / 2.0 - status_width / 2.0,
+            510.0,
+            28.0,
+            BLACK,
+        );
+        let out: Vec<&str> = (0..tournament.names.len())
+            .filter(|&p| !tournament.alive(p))
+            .map(|p| tournament.names[p].as_str())
+            .collect();
+        if !out.is_empty() && tournament.champion().is_none() {
+            let text = format!("Out: {}", out.join(", "));
+            let width = measure_text(&text, None, 16u16, 1.0).width;
+            draw_text(&text, screen_width() / 2.0 - width / 2.0, 535.0, 16.0, GRAY);
+        }
+        self.draw_centered_hint(if tournament.champion().is_some() {
+            "ENTER: Finish | ESC: Back"
+        } else {
+            "ENTER: Play match | ESC: Back (progress is saved)"
+        });
+    }
+
+    /// What a spectator sees: the match as broadcast, or a placeholder
+    /// until it starts.
+    fn draw_spectate(&self) {
+        let Some(spectating) = &self.spectating else {
+            return;
+        };
+        if !spectating.watching {
+            self.draw_centered_title("Waiting for the match...", 280.0);
+        } else if spectating.ended {
+            self.draw_versus_results();
+        } else {
+            self.draw_versus_field();
+            if self.versus_round_intro > 0.0 {
+                self.draw_versus_round_intro();
+            }
+        }
+        let mut hints = vec![format!("WATCHING {}", spectating.label)];
+        if let Some(notice) = &spectating.notice {
+            hints.push(notice.clone());
+        }
+        if spectating.is_recording() {
+            hints.push(
+                if spectating.paused() {
+                    "SPACE: Resume"
+                } else {
+                    "SPACE: Pause"
+                }
+                .to_string(),
+            );
+        }
+        hints.push("ESC: Leave".to_string());
+        let instructions = hints.join(" | ");
+        let inst_width = measure_text(&instructions, None, 16u16, 1.0).width;
+        draw_text(
+            &instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 30.0,
+            16.0,
+            GRAY,
+        );
+    }
+
     pub fn draw(&self) {
         clear_background(WHITE);
         match self.state {
@@ -452,10 +1335,9 @@ impl Game {
                     title_size,
                     BLACK,
                 );
-                let saves = SaveData::list_all_saves();
                 let start_y = 200.0;
                 let slot_spacing = 80.0;
-                for (slot_idx, (_, save_data_opt)) in saves.iter().enumerate() {
+                for (slot_idx, slot) in self.save_slots.iter().enumerate() {
                     let y = start_y + (slot_idx as f32 * slot_spacing);
                     let slot_num = slot_idx + 1;
                     let color = if slot_idx == self.continue_selection {
@@ -466,7 +1348,7 @@ impl Game {
                     if slot_idx == self.continue_selection {
                         draw_text(">", 100.0, y, 32.0, BLACK);
                     }
-                    if let Some(save_data) = save_data_opt {
+                    if let Some((save_data, source)) = &slot.save {
                         let minutes = (save_data.time_taken / 60.0) as u32;
                         let seconds = (save_data.time_taken % 60.0) as u32;
                         let slot_info = format!(
@@ -484,12 +1366,60 @@ impl Game {
                             }
                         );
                         draw_text(&slot_info, 150.0, y, 24.0, color);
+                        if let SaveSource::Backup(n) = source {
+                            let recovered =
+                                format!("Profile file was damaged, restored from backup {}", n);
+                            draw_text(&recovered, 150.0, y + 24.0, 18.0, ORANGE);
+                        }
+                    } else if slot.protected {
+                        let locked_text = format!("Slot {}: Locked with a passphrase", slot_num);
+                        draw_text(&locked_text, 150.0, y, 24.0, color);
                     } else {
                         let empty_text = format!("Slot {}: Empty Slot", slot_num);
                         draw_text(&empty_text, 150.0, y, 24.0, color);
                     }
                 }
-                if self.continue_mode == ContinueMode::DeleteConfirm {
+                if matches!(
+                    self.continue_mode,
+                    ContinueMode::Unlock
+                        | ContinueMode::Lock
+                        | ContinueMode::ConfirmLock
+                        | ContinueMode::RemoveLock
+                ) {
+                    let prompt = match self.continue_mode {
+                        ContinueMode::Unlock => "Passphrase:",
+                        ContinueMode::Lock => "New passphrase:",
+                        ContinueMode::ConfirmLock => "New passphrase again:",
+                        _ => "Current passphrase, to remove it:",
+                    };
+                    let prompt_width = measure_text(prompt, None, 28, 1.0).width;
+                    draw_text(
+                        prompt,
+                        screen_width() / 2.0 - prompt_width / 2.0,
+                        screen_height() / 2.0 + 100.0,
+                        28.0,
+                        BLACK,
+                    );
+                    let masked = format!("{}_", "*".repeat(self.passphrase_input.chars().count()));
+                    let masked_width = measure_text(&masked, None, 28, 1.0).width;
+                    draw_text(
+                        &masked,
+                        screen_width() / 2.0 - masked_width / 2.0,
+                        screen_height() / 2.0 + 140.0,
+                        28.0,
+                        BLACK,
+                    );
+                    let instructions = "ENTER: Confirm | ESC: Cancel";
+                    let inst_width =
+                        measure_text(instructions, None, MENU_INSTRUCTION_SIZE as u16, 1.0).width;
+                    draw_text(
+                        instructions,
+                        screen_width() / 2.0 - inst_width / 2.0,
+                        screen_height() - 40.0,
+                        MENU_INSTRUCTION_SIZE,
+                        GRAY,
+                    );
+                } else if self.continue_mode == ContinueMode::DeleteConfirm {
                     let confirm_text = "Are you sure you want to delete this save? (Y/N)";
                     let confirm_width = measure_text(confirm_text, None, 28, 1.0).width;
                     draw_text(
@@ -500,7 +1430,7 @@ impl Game {
                         RED,
                     );
                 } else {
-                    let instructions = "ENTER: Load | DELETE: Erase | ESC: Back";
+                    let instructions = "ENTER: Load | P: Passphrase | DELETE: Erase | ESC: Back";
                     let inst_width =
                         measure_text(instructions, None, MENU_INSTRUCTION_SIZE as u16, 1.0).width;
                     draw_text(
@@ -544,6 +1474,309 @@ impl Game {
                     DARKGRAY,
                 );
             }
+            GameState::CoopSetup => {
+                self.draw_centered_title("CO-OP RULES", 120.0);
+                let rows = [
+                    format!("LIVES: {}", self.coop_rules.lives.label()),
+                    format!(
+                        "REVIVE: {}",
+                        if self.coop_rules.revive_bubble {
+                            "BUBBLE"
+                        } else {
+                            "CHECKPOINT"
+                        }
+                    ),
+                    format!(
+                        "PARTNER PHYSICS: {}",
+                        if self.coop_rules.partner_physics {
+                            "ON"
+                        } else {
+                            "OFF"
+                        }
+                    ),
+                    "START".to_string(),
+                ];
+                self.draw_option_rows(&rows, self.coop_setup_selection, 200.0, 55.0);
+                let description = match self.coop_setup_selection {
+                    0 => match self.coop_rules.lives {
+                        CoopLives::Shared => "Both players draw from one pool of lives",
+                        CoopLives::Separate => "Each player keeps their own lives",
+                    },
+                    1 if self.coop_rules.revive_bubble => {
+                        "Fallen players float to their partner and pop back in on touch"
+                    }
+                    1 => "Fallen players respawn at the last checkpoint",
+                    2 if self.coop_rules.partner_physics => {
+                        "Stand on heads for boosted jumps, ACTION to grab and throw"
+                    }
+                    2 => "Players pass through each other",
+                    _ => "Game over only when both players are down",
+                };
+                let desc_width = measure_text(description, None, 20u16, 1.0).width;
+                draw_text(
+                    description,
+                    screen_width() / 2.0 - desc_width / 2.0,
+                    420.0,
+                    20.0,
+                    DARKGRAY,
+                );
+                self.draw_centered_hint("ARROWS: Change | ENTER: Select | ESC: Back");
+            }
+            GameState::VersusSetup => {
+                self.draw_centered_title("VERSUS RULES", 100.0);
+                let mut rows = self.versus_rules.row_labels();
+                rows.push("START".to_string());
+                rows.push("PLAY ONLINE".to_string());
+                rows.push("BROADCAST & WATCH".to_string());
+                rows.push("STATS".to_string());
+                rows.push("TOURNAMENT".to_string());
+                rows.push("HANDICAPS".to_string());
+                self.draw_option_rows(&rows, self.versus_setup_selection, 124.0, 22.0);
+                let description = match (self.versus_setup_selection, self.versus_rules.mode) {
+                    (0, VersusMode::Deathmatch) => "Stomp the others; knockouts decide the round",
+                    (0, VersusMode::KingOfTheHill) => {
+                        "Stand alone in the hill zone; first to 30 seconds wins"
+                    }
+                    (0, VersusMode::CoinRush) => {
+                        "Grab the coins that drop in; a stomp knocks half loose. First to 15"
+                    }
+                    (0, VersusMode::CaptureTheCrab) => {
+                        "Carry the crab and don't get stomped; first to 30 seconds wins"
+                    }
+                    (1, _) => "P1 WASD, P2 arrows, P3 IJKL, P4 numpad 4/6/8",
+                    (2, _) => "Everyone for themselves, or P1 & P3 against P2 & P4",
+                    (3, _) => "CPU opponents take the last player seats",
+                    (4, _) => "How well the CPU players fight",
+                    (5, _) => "Round ends when the clock runs out; whoever leads takes it",
+                    (6, _) => "First player or team to this many stomps takes the round",
+                    (7, _) => "Lives per round; run out and you sit the round out",
+                    (8, _) => "Number of rounds; the majority wins the match",
+                    (9, _) => "Seconds before a stomped player comes back",
+                    (10, _) => "Stomp immunity right after spawning",
+                    (11, _) => "Item boxes: shield, speed boost, homing shell, freeze trap",
+                    (13, _) => "Host or join a match over the network with these rules",
+                    (14, _) => "Stream your matches to spectators, record them, or watch one",
+                    (15, _) => "Win rates, head-to-head records and recent matches",
+                    (16, _) => "A bracket of 4 to 16 players, one-on-one with these rules",
+                    (17, _) => "Per-player speed, jump, respawn and scoring adjustments",
+                    _ => "",
+                };
+                let desc_width = measure_text(description, None, 20u16, 1.0).width;
+                draw_text(
+                    description,
+                    screen_width() / 2.0 - desc_width / 2.0,
+                    520.0,
+                    20.0,
+                    DARKGRAY,
+                );
+                self.draw_centered_hint("ARROWS: Change | ENTER: Select | ESC: Back");
+            }
+            GameState::VersusArenaSelect => {
+                self.draw_centered_title("ARENA", 100.0);
+                let count = self.versus_arenas.len();
+                let arena = self.versus_arenas.get(self.versus_arena_selection);
+                let name = arena.map_or("RANDOM", |a| a.name.as_str());
+                let label = format!("< {} >", name);
+                let label_width = measure_text(&label, None, 30u16, 1.0).width;
+                draw_text(
+                    &label,
+                    screen_width() / 2.0 - label_width / 2.0,
+                    160.0,
+                    30.0,
+                    BLACK,
+                );
+                let (box_w, box_h) = (480.0, 280.0);
+                let box_x = screen_width() / 2.0 - box_w / 2.0;
+                let box_y = 190.0;
+                match arena {
+                    Some(arena) => self.draw_arena_preview(arena, box_x, box_y, box_w, box_h),
+                    None => {
+                        draw_rectangle(box_x, box_y, box_w, box_h, Color::new(0.9, 0.9, 0.9, 1.0));
+                        draw_rectangle_lines(box_x, box_y, box_w, box_h, 2.0, BLACK);
+                        let mark_width = measure_text("?", None, 96u16, 1.0).width;
+                        draw_text(
+                            "?",
+                            screen_width() / 2.0 - mark_width / 2.0,
+                            box_y + box_h / 2.0 + 32.0,
+                            96.0,
+                            GRAY,
+                        );
+                    }
+                }
+                let details = match arena {
+                    Some(arena) => {
+                        let mut parts =
+                            vec![format!("{}/{}", self.versus_arena_selection + 1, count)];
+                        if arena.wrap {
+                            parts.push("Wraparound".to_string());
+                        }
+                        if !arena.hazards.is_empty() {
+                            parts.push("Hazards".to_string());
+                        }
+                        parts.join(" | ")
+                    }
+                    None => format!("Any of {} arenas", count),
+                };
+                let details_width = measure_text(&details, None, 20u16, 1.0).width;
+                draw_text(
+                    &details,
+                    screen_width() / 2.0 - details_width / 2.0,
+                    500.0,
+                    20.0,
+                    DARKGRAY,
+                );
+                self.draw_centered_hint("ARROWS: Choose | ENTER: Fight | ESC: Back");
+            }
+            GameState::NetLobby => {
+                self.draw_centered_title("PLAY ONLINE", 100.0);
+                let lobby = &self.net_lobby;
+                let host = lobby.role == NetRole::Host;
+                let cursor = if lobby.selection == 1 && !lobby.connecting() {
+                    "_"
+                } else {
+                    ""
+                };
+                let lan = lobby.selected_lan_lobby();
+                let third_row = match (host, lan) {
+                    (true, _) => format!(
+                        "ARENA: {}",
+                        self.versus_arenas
+                            .get(self.versus_arena_selection)
+                            .map_or("RANDOM", |a| a.name.as_str())
+                    ),
+                    (false, Some(lan)) if lobby.lan_count() > 1 => {
+                        format!("LAN: < {} - {} MS >", lan.name, lan.ping_ms)
+                    }
+                    (false, Some(lan)) => format!("LAN: {} - {} MS", lan.name, lan.ping_ms),
+                    (false, None) => "LAN: SEARCHING...".to_string(),
+                };
+                let lan_details = match lan {
+                    Some(lan) => lan.details.clone(),
+                    None => format!(
+                        "Looking for hosts on ports {}-{}",
+                        NET_DEFAULT_PORT,
+                        NET_DEFAULT_PORT + NET_LAN_PORTS - 1
+                    ),
+                };
+                let rows = [
+                    format!("ROLE: {}", if host { "HOST" } else { "JOIN" }),
+                    if host {
+                        format!("PORT: {}{}", lobby.port, cursor)
+                    } else {
+                        format!("ADDRESS: {}{}", lobby.address, cursor)
+                    },
+                    third_row,
+                    format!("INPUT DELAY: {} FRAMES", lobby.input_delay),
+                    match (lobby.connecting(), host) {
+                        (true, _) => "CANCEL".to_string(),
+                        (false, true) => "HOST MATCH".to_string(),
+                        (false, false) => "CONNECT".to_string(),
+                    },
+                    "BACK".to_string(),
+                ];
+                self.draw_option_rows(&rows, lobby.selection, 180.0, 45.0);
+                let description = match lobby.selection {
+                    0 => "Host waits for another player; join connects to a host",
+                    1 if host => "UDP port to listen on; the other player needs it reachable",
+                    1 => "Host's address as host:port, or pick a LAN game below",
+                    2 if host => "Both machines need this arena; the rules come from setup",
+                    2 => lan_details.as_str(),
+                    3 => "Hides this much lag without rollback; higher feels heavier",
+                    4 => "You play P1 with your own keys; CPU seats run on both sides",
+                    _ => "",
+                };
+                let desc_width = measure_text(description, None, 20u16, 1.0).width;
+                draw_text(
+                    description,
+                    screen_width() / 2.0 - desc_width / 2.0,
+                    470.0,
+                    20.0,
+                    DARKGRAY,
+                );
+                let status = match &lobby.status {
+                    Some(_) if lobby.guest_seen() => "Player found, starting...",
+                    Some(status) => status.as_str(),
+                    None => "",
+                };
+                let status_width = measure_text(status, None, 22u16, 1.0).width;
+                draw_text(
+                    status,
+                    screen_width() / 2.0 - status_width / 2.0,
+                    510.0,
+                    22.0,
+                    if lobby.connecting() { DARKGREEN } else { RED },
+                );
+                self.draw_centered_hint(
+                    "UP/DOWN: Move | LEFT/RIGHT: Change | ENTER: Select | ESC: Back",
+                );
+            }
+            GameState::BroadcastSetup => {
+                self.draw_centered_title("BROADCAST & WATCH", 100.0);
+                let broadcast = &self.broadcast;
+                let live = broadcast.live();
+                let cursor = |row: usize| if broadcast.selection == row { "_" } else { "" };
+                let recording = match broadcast.recordings.get(broadcast.recording_selection) {
+                    Some(path) if broadcast.recordings.len() > 1 => {
+                        format!("< {} >", recording_name(path))
+                    }
+                    Some(path) => recording_name(path),
+                    None => "NONE YET".to_string(),
+                };
+                let rows = [
+                    if live {
+                        format!("BROADCAST: ON ({} WATCHING)", broadcast.spectator_count())
+                    } else {
+                        "BROADCAST: OFF".to_string()
+                    },
+                    format!(
+                        "PORT: {}{}",
+                        broadcast.port,
+                        if live { "" } else { cursor(1) }
+                    ),
+                    format!(
+                        "RECORD MATCHES: {}",
+                        if broadcast.record { "ON" } else { "OFF" }
+                    ),
+                    format!("WATCH LIVE: {}{}", broadcast.watch_address, cursor(3)),
+                    format!("WATCH RECORDING: {}", recording),
+                    "BACK".to_string(),
+                ];
+                self.draw_option_rows(&rows, broadcast.selection, 180.0, 45.0);
+                let description = match broadcast.selection {
+                    0 => "Spectators can tune in to every Versus match you play",
+                    1 if live => "Turn the broadcast off to change the port",
+                    1 => "TCP port spectators connect to",
+                    2 => "Saves every Versus match beside your save files",
+                    3 => "Broadcaster's address as host:port; ENTER to tune in",
+                    4 => "Recorded matches, newest first; ENTER to play back",
+                    _ => "",
+                };
+                let desc_width = measure_text(description, None, 20u16, 1.0).width;
+                draw_text(
+                    description,
+                    screen_width() / 2.0 - desc_width / 2.0,
+                    470.0,
+                    20.0,
+                    DARKGRAY,
+                );
+                let status = broadcast.status.as_deref().unwrap_or("");
+                let status_width = measure_text(status, None, 22u16, 1.0).width;
+                draw_text(
+                    status,
+                    screen_width() / 2.0 - status_width / 2.0,
+                    510.0,
+                    22.0,
+                    RED,
+                );
+                self.draw_centered_hint(
+                    "UP/DOWN: Move | LEFT/RIGHT: Change | ENTER: Select | ESC: Back",
+                );
+            }
+            GameState::Spectate => self.draw_spectate(),
+            GameState::VersusStats => self.draw_versus_stats(),
+            GameState::VersusHandicaps => self.draw_versus_handicaps(),
+            GameState::TournamentSetup => self.draw_tournament_setup(),
+            GameState::TournamentBracket => self.draw_tournament_bracket(),
             GameState::Settings => {
                 let title = "SETTINGS";
                 let title_size = 48.0;
@@ -556,8 +1789,8 @@ impl Game {
                     BLACK,
                 );
                 let option_size = 30.0;
-                let start_y = 180.0;
-                let spacing = 60.0;
+                let start_y = 140.0;
+                let spacing = 38.0;
                 let safe_resolution_index = self
                     .resolution_index
                     .min(self.available_resolutions.len().saturating_sub(1));
@@ -807,18 +2040,109 @@ impl Game {
                     option_size,
                     assist_color,
                 );
+                let health_text = if self.health_mode == HealthMode::Auto {
+                    format!(
+                        "HEALTH: AUTO ({})",
+                        if self.health_enabled() { "ON" } else { "OFF" }
+                    )
+                } else {
+                    format!("HEALTH: {}", self.health_mode.label())
+                };
+                let health_color = if self.settings_selection == 7 {
+                    BLACK
+                } else {
+                    GRAY
+                };
+                let health_width = measure_text(&health_text, None, option_size as u16, 1.0).width;
+                if self.settings_selection == 7 {
+                    draw_text(
+                        "<",
+                        screen_width() / 2.0 - health_width / 2.0 - 30.0,
+                        start_y + spacing * 7.0,
+                        option_size,
+                        BLACK,
+                    );
+                    draw_text(
+                        ">",
+                        screen_width() / 2.0 + health_width / 2.0 + 10.0,
+                        start_y + spacing * 7.0,
+                        option_size,
+                        BLACK,
+                    );
+                }
+                draw_text(
+                    &health_text,
+                    screen_width() / 2.0 - health_width / 2.0,
+                    start_y + spacing * 7.0,
+                    option_size,
+                    health_color,
+                );
+                let coop_camera_text = format!("CO-OP CAMERA: {}", self.coop_camera_mode.label());
+                let coop_camera_color = if self.settings_selection == 8 {
+                    BLACK
+                } else {
+                    GRAY
+                };
+                let coop_camera_width =
+                    measure_text(&coop_camera_text, None, option_size as u16, 1.0).width;
+                if self.settings_selection == 8 {
+                    draw_text(
+                        "<",
+                        screen_width() / 2.0 - coop_camera_width / 2.0 - 30.0,
+                        start_y + spacing * 8.0,
+                        option_size,
+                        BLACK,
+                    );
+                    draw_text(
+                        ">",
+                        screen_width() / 2.0 + coop_camera_width / 2.0 + 10.0,
+                        start_y + spacing * 8.0,
+                        option_size,
+                        BLACK,
+                    );
+                }
+                draw_text(
+                    &coop_camera_text,
+                    screen_width() / 2.0 - coop_camera_width / 2.0,
+                    start_y + spacing * 8.0,
+                    option_size,
+                    coop_camera_color,
+                );
+                let key_text = "NEW SAVE KEY";
+                let key_color = if self.settings_selection == 9 {
+                    BLACK
+                } else {
+                    GRAY
+                };
+                let key_width = measure_text(key_text, None, option_size as u16, 1.0).width;
+                if self.settings_selection == 9 {
+                    draw_text(
+                        ">",
+                        screen_width() / 2.0 - key_width / 2.0 - 30.0,
+                        start_y + spacing * 9.0,
+                        option_size,
+                        BLACK,
+                    );
+                }
+                draw_text(
+                    key_text,
+                    screen_width() / 2.0 - key_width / 2.0,
+                    start_y + spacing * 9.0,
+                    option_size,
+                    key_color,
+                );
                 let back_text = "BACK";
-                let back_color = if self.settings_selection == 7 {
+                let back_color = if self.settings_selection == 10 {
                     BLACK
                 } else {
                     GRAY
                 };
                 let back_width = measure_text(back_text, None, option_size as u16, 1.0).width;
-                if self.settings_selection == 7 {
+                if self.settings_selection == 10 {
                     draw_text(
                         ">",
                         screen_width() / 2.0 - back_width / 2.0 - 30.0,
-                        start_y + spacing * 7.0,
+                        start_y + spacing * 10.0,
                         option_size,
                         BLACK,
                     );
@@ -826,10 +2150,31 @@ impl Game {
                 draw_text(
                     back_text,
                     screen_width() / 2.0 - back_width / 2.0,
-                    start_y + spacing * 7.0,
+                    start_y + spacing * 10.0,
                     option_size,
                     back_color,
                 );
+                if self.confirm_key_rotation {
+                    let confirm_text = "Re-encrypt every save with a new key? (Y/N)";
+                    let confirm_width = measure_text(confirm_text, None, 28, 1.0).width;
+                    draw_text(
+                        confirm_text,
+                        screen_width() / 2.0 - confirm_width / 2.0,
+                        screen_height() - 65.0,
+                        28.0,
+                        RED,
+                    );
+                } else if let Some(notice) = &self.settings_notice {
+                    let notice_size = 18.0;
+                    let notice_width = measure_text(notice, None, notice_size as u16, 1.0).width;
+                    draw_text(
+                        notice,
+                        screen_width() / 2.0 - notice_width / 2.0,
+                        screen_height() - 65.0,
+                        notice_size,
+                        DARKGREEN,
+                    );
+                }
                 let instructions =
                     "Use ARROWS to navigate and adjust, ENTER to confirm, ESC to go back";
                 let inst_size = 16.0;
@@ -861,6 +2206,8 @@ impl Game {
                             ControlAction::Left => "LEFT",
                             ControlAction::Right => "RIGHT",
                             ControlAction::Jump => "JUMP",
+                            ControlAction::Action => "ACTION",
+                            ControlAction::Item => "ITEM",
                         }
                     );
                     let waiting_width = measure_text(&waiting_text, None, 32, 1.0).width;
@@ -903,6 +2250,8 @@ impl Game {
                         ("LEFT", controls.left, controls.left_gamepad),
                         ("RIGHT", controls.right, controls.right_gamepad),
                         ("JUMP", controls.jump, controls.jump_gamepad),
+                        ("ACTION", controls.action, controls.action_gamepad),
+                        ("ITEM", controls.item, controls.item_gamepad),
                     ];
                     for (i, (action_name, key, gamepad)) in actions.iter().enumerate() {
                         let y = start_y + 80.0 + (i as f32 * spacing);
@@ -1068,61 +2417,43 @@ impl Game {
                 );
             }
             GameState::Playing => {
-                self.draw_level_world();
+                self.draw_level_world(&self.camera, (screen_width(), screen_height()));
                 self.draw_level_hud(false);
+                if let (true, Some(key)) = (self.can_player2_drop_in(), self.player2_controls.jump)
+                {
+                    self.draw_centered_hint(&format!("P2: press {:?} to join", key));
+                }
                 self.draw_level_start_fade();
             }
             GameState::Versus => {
-                let camera_x = self.camera.x;
-                let camera_y = self.camera.y;
-                for platform in &self.versus_platforms {
-                    platform.draw(camera_x, camera_y);
-                }
-                if self.respawn_timer_p1 <= 0.0 {
-                    self.player.draw_vs(camera_x, camera_y, true);
+                self.draw_versus_field();
+                if self.versus_round_intro > 0.0 {
+                    self.draw_versus_round_intro();
                 }
-                if let Some(ref p2) = self.player2 {
-                    if self.respawn_timer_p2 <= 0.0 {
-                        p2.draw_vs(camera_x, camera_y, false);
+                let hints: Vec<String> = match &self.netplay {
+                    Some(net) => vec![
+                        format!("ONLINE: you are P{}", net.seat + 1),
+                        format!("Delay {}f", net.input_delay()),
+                        format!("Rollbacks {}", net.rollbacks()),
+                        "ESC: Leave".to_string(),
+                    ],
+                    None => {
+                        let mut hints: Vec<String> = self
+                            .versus_fighters
+                            .iter()
+                            .map(|f| f.controls_hint())
+                            .collect();
+                        if let Some(names) = self.tournament_match_names() {
+                            hints.insert(0, format!("{} vs {}", names[0], names[1]));
+                        }
+                        hints.push("P/ESC: Pause".to_string());
+                        hints
                     }
-                }
-                let p1_score_text = format!(
-                    "P1: {} kills | {} pts",
-                    self.player1_score, self.player1_points
-                );
-                let p2_score_text = format!(
-                    "P2: {} kills | {} pts",
-                    self.player2_score, self.player2_points
-                );
-                draw_text(&p1_score_text, 20.0, 30.0, 24.0, BLACK);
-                let p2_width = measure_text(&p2_score_text, None, 24u16, 1.0).width;
-                draw_text(
-                    &p2_score_text,
-                    screen_width() - p2_width - 20.0,
-                    30.0,
-                    24.0,
-                    DARKGRAY,
-                );
-                let time_text = format!("{}", self.versus_time_remaining as u32);
-                let time_width = measure_text(&time_text, None, 28u16, 1.0).width;
-                let time_color = if self.versus_time_remaining < 60.0 {
-                    RED
-                } else if self.versus_time_remaining < 120.0 {
-                    ORANGE
-                } else {
-                    BLACK
                 };
+                let instructions = hints.join(" | ");
+                let inst_width = measure_text(&instructions, None, 16u16, 1.0).width;
                 draw_text(
-                    &time_text,
-                    screen_width() / 2.0 - time_width / 2.0,
-                    30.0,
-                    28.0,
-                    time_color,
-                );
-                let instructions = "P1: WASD | P2: Arrow Keys | ESC: Menu";
-                let inst_width = measure_text(instructions, None, 16u16, 1.0).width;
-                draw_text(
-                    instructions,
+                    &instructions,
                     screen_width() / 2.0 - inst_width / 2.0,
                     screen_height() - 30.0,
                     16.0,
@@ -1131,19 +2462,13 @@ impl Game {
                 self.draw_level_start_fade();
             }
             GameState::Coop => {
-                self.draw_level_world();
-                if let Some(ref p2) = self.player2 {
-                    p2.draw(self.camera.x, self.camera.y);
-                }
-                self.draw_level_hud(true);
-                let p1_text = "P1";
-                let p2_text = "P2";
-                draw_text(p1_text, 10.0, 10.0, 20.0, BLUE);
-                if let Some(ref _p2) = self.player2 {
-                    let p2_width = measure_text(p2_text, None, 20u16, 1.0).width;
-                    draw_text(p2_text, screen_width() - p2_width - 10.0, 10.0, 20.0, RED);
-                }
-                let instructions = "P1: WASD | P2: Arrow Keys | P: Pause | ESC: Menu";
+                self.draw_coop_scene();
+                self.draw_coop_hud();
+                let instructions = if self.coop_rules.partner_physics {
+                    "P1: WASD, S grab | P2: Arrows, DOWN grab (hold to leave) | P/ESC: Pause"
+                } else {
+                    "P1: WASD | P2: Arrow Keys, hold DOWN to leave | P/ESC: Pause"
+                };
                 let inst_width = measure_text(instructions, None, 16u16, 1.0).width;
                 draw_text(
                     instructions,
@@ -1152,70 +2477,34 @@ impl Game {
                     16.0,
                     GRAY,
                 );
+                if self.p2_leave_timer > 0.0 {
+                    let progress = (self.p2_leave_timer / COOP_DROP_OUT_HOLD).min(1.0);
+                    let label = "P2 LEAVING...";
+                    let label_width = measure_text(label, None, 20u16, 1.0).width;
+                    let bar_width = 160.0;
+                    let bar_x = screen_width() / 2.0 - bar_width / 2.0;
+                    let bar_y = screen_height() - 70.0;
+                    draw_text(
+                        label,
+                        screen_width() / 2.0 - label_width / 2.0,
+                        bar_y - 6.0,
+                        20.0,
+                        DARKGRAY,
+                    );
+                    draw_rectangle(bar_x, bar_y, bar_width * progress, 8.0, DARKGRAY);
+                    draw_rectangle_lines(bar_x, bar_y, bar_width, 8.0, 1.0, GRAY);
+                }
                 self.draw_level_start_fade();
             }
             GameState::VersusEnd => {
-                let title = "GAME OVER";
-                let title_size = 48.0;
-                let title_width = measure_text(title, None, title_size as u16, 1.0).width;
-                draw_text(
-                    title,
-                    screen_width() / 2.0 - title_width / 2.0,
-                    150.0,
-                    title_size,
-                    BLACK,
-                );
-                let winner_text = if self.player1_points > self.player2_points {
-                    "PLAYER 1 WINS!"
-                } else if self.player2_points > self.player1_points {
-                    "PLAYER 2 WINS!"
+                self.draw_versus_results();
+                let back_text = if self.netplay.is_some() {
+                    "ENTER, SPACE or ESC: Leave"
+                } else if self.tournament_playing {
+                    "ENTER, SPACE or ESC: Back to the bracket"
                 } else {
-                    "DRAW!"
+                    "R: Rematch | ENTER, SPACE or ESC: Menu"
                 };
-                let winner_size = 36.0;
-                let winner_width = measure_text(winner_text, None, winner_size as u16, 1.0).width;
-                let winner_color = if self.player1_points > self.player2_points {
-                    BLACK
-                } else if self.player2_points > self.player1_points {
-                    DARKGRAY
-                } else {
-                    GRAY
-                };
-                draw_text(
-                    winner_text,
-                    screen_width() / 2.0 - winner_width / 2.0,
-                    220.0,
-                    winner_size,
-                    winner_color,
-                );
-                let score_size = 28.0;
-                let p1_final_text = format!(
-                    "Player 1: {} kills | {} points",
-                    self.player1_score, self.player1_points
-                );
-                let p2_final_text = format!(
-                    "Player 2: {} kills | {} points",
-                    self.player2_score, self.player2_points
-                );
-                let p1_final_width =
-                    measure_text(&p1_final_text, None, score_size as u16, 1.0).width;
-                let p2_final_width =
-                    measure_text(&p2_final_text, None, score_size as u16, 1.0).width;
-                draw_text(
-                    &p1_final_text,
-                    screen_width() / 2.0 - p1_final_width / 2.0,
-                    300.0,
-                    score_size,
-                    BLACK,
-                );
-                draw_text(
-                    &p2_final_text,
-                    screen_width() / 2.0 - p2_final_width / 2.0,
-                    340.0,
-                    score_size,
-                    DARKGRAY,
-                );
-                let back_text = "Press ENTER, SPACE or ESC to return to menu";
                 let back_size = 18.0;
                 let back_width = measure_text(back_text, None, back_size as u16, 1.0).width;
                 draw_text(
@@ -1336,8 +2625,17 @@ impl Game {
                 );
             }
             GameState::Pause => {
-                self.draw_level_world();
-                self.draw_level_hud(true);
+                match self.pause_origin {
+                    GameState::Coop => {
+                        self.draw_coop_scene();
+                        self.draw_coop_hud();
+                    }
+                    GameState::Versus => self.draw_versus_field(),
+                    _ => {
+                        self.draw_level_world(&self.camera, (screen_width(), screen_height()));
+                        self.draw_level_hud(true);
+                    }
+                }
                 draw_rectangle(
                     0.0,
                     0.0,
@@ -1355,42 +2653,65 @@ impl Game {
                     title_size,
                     WHITE,
                 );
-                let menu_options = vec!["RESUME", "SETTINGS", "CREDITS", "MAIN MENU"];
-                let start_y = screen_height() / 2.0 - 40.0;
-                for (i, option) in menu_options.iter().enumerate() {
-                    let option_width =
-                        measure_text(option, None, MENU_OPTION_SIZE as u16, 1.0).width;
-                    let x = screen_width() / 2.0 - option_width / 2.0;
-                    let y = start_y + (i as f32 * MENU_OPTION_SPACING);
-                    let scale = self.pause_animation.get_scale(i);
-                    let color = if i == self.pause_selection {
-                        WHITE
-                    } else {
-                        LIGHTGRAY
-                    };
-                    if i == self.pause_selection {
-                        draw_text(">", x - MENU_INDICATOR_OFFSET, y, MENU_OPTION_SIZE, WHITE);
+                let versus = self.pause_origin == GameState::Versus;
+                if let Some(option) = self.pause_confirm {
+                    let question = format!("{}?", option.label(versus));
+                    let warning = "Progress in this run will be lost.";
+                    for (text, size, y, color) in [
+                        (question.as_str(), MENU_OPTION_SIZE, -40.0, WHITE),
+                        (warning, 24.0, 10.0, LIGHTGRAY),
+                        ("ENTER: Yes | ESC: No", 24.0, 70.0, WHITE),
+                    ] {
+                        let width = measure_text(text, None, size as u16, 1.0).width;
+                        draw_text(
+                            text,
+                            screen_width() / 2.0 - width / 2.0,
+                            screen_height() / 2.0 + y,
+                            size,
+                            color,
+                        );
                     }
-                    let scaled_size = MENU_OPTION_SIZE * scale;
-                    let scaled_width = measure_text(option, None, scaled_size as u16, 1.0).width;
+                } else {
+                    let options = self.pause_options();
+                    let option_spacing = 50.0;
+                    let start_y = screen_height() / 2.0 - 100.0;
+                    for (i, option) in options.iter().enumerate() {
+                        let option = option.label(versus);
+                        let option_width =
+                            measure_text(option, None, MENU_OPTION_SIZE as u16, 1.0).width;
+                        let x = screen_width() / 2.0 - option_width / 2.0;
+                        let y = start_y + (i as f32 * option_spacing);
+                        let scale = self.pause_animation.get_scale(i);
+                        let color = if i == self.pause_selection {
+                            WHITE
+                        } else {
+                            LIGHTGRAY
+                        };
+                        if i == self.pause_selection {
+                            draw_text(">", x - MENU_INDICATOR_OFFSET, y, MENU_OPTION_SIZE, WHITE);
+                        }
+                        let scaled_size = MENU_OPTION_SIZE * scale;
+                        let scaled_width =
+                            measure_text(option, None, scaled_size as u16, 1.0).width;
+                        draw_text(
+                            option,
+                            x + (option_width - scaled_width) / 2.0,
+                            y - (scaled_size - MENU_OPTION_SIZE) / 2.0,
+                            scaled_size,
+                            color,
+                        );
+                    }
+                    let instructions = "ARROWS/WASD: Navigate | ENTER: Select | P/ESC: Resume";
+                    let inst_width =
+                        measure_text(instructions, None, MENU_INSTRUCTION_SIZE as u16, 1.0).width;
                     draw_text(
-                        option,
-                        x + (option_width - scaled_width) / 2.0,
-                        y - (scaled_size - MENU_OPTION_SIZE) / 2.0,
-                        scaled_size,
-                        color,
+                        instructions,
+                        screen_width() / 2.0 - inst_width / 2.0,
+                        screen_height() - 40.0,
+                        MENU_INSTRUCTION_SIZE,
+                        LIGHTGRAY,
                     );
                 }
-                let instructions = "ARROWS/WASD: Navigate | ENTER: Select | P/ESC: Resume";
-                let inst_width =
-                    measure_text(instructions, None, MENU_INSTRUCTION_SIZE as u16, 1.0).width;
-                draw_text(
-                    instructions,
-                    screen_width() / 2.0 - inst_width / 2.0,
-                    screen_height() - 40.0,
-                    MENU_INSTRUCTION_SIZE,
-                    LIGHTGRAY,
-                );
             }
             GameState::Respawn => {
                 let time_remaining = self.respawn_timer;
@@ -1565,6 +2886,34 @@ impl Game {
                     continue_size,
                     GRAY,
                 );
+                if self.player2.is_some() {
+                    let rows = [
+                        (
+                            format!(
+                                "P1: {} pts | {} coins",
+                                self.coop_score_p1, self.coop_coins_p1
+                            ),
+                            if self.colorblind_mode { BLACK } else { BLUE },
+                        ),
+                        (
+                            format!(
+                                "P2: {} pts | {} coins",
+                                self.coop_score_p2, self.coop_coins_p2
+                            ),
+                            if self.colorblind_mode { DARKGRAY } else { RED },
+                        ),
+                    ];
+                    for (i, (text, color)) in rows.iter().enumerate() {
+                        let width = measure_text(text, None, 24u16, 1.0).width;
+                        draw_text(
+                            text,
+                            screen_width() / 2.0 - width / 2.0,
+                            screen_height() / 2.0 + 110.0 + i as f32 * 32.0,
+                            24.0,
+                            *color,
+                        );
+                    }
+                }
             }
         }
         self.draw_transition();
@@ -1,13 +1,29 @@
-use crate::coin::create_level_coins;
+use crate::camera::{framing_zoom, WorldBounds};
+use crate::coin::{create_level_coins, create_level_coop_coins};
 use crate::constants::*;
 use crate::enemy::Enemy;
-use crate::platform::Platform;
+use crate::platform::{create_level_coop_ledges, Platform};
 use crate::player::Player;
-use crate::save::SaveData;
+use crate::save::{PassphraseKey, SaveData, SaveSource};
 use crate::systems::{CoinBounce, Particle};
+use crate::trigger::{
+    create_level_coop_gates, create_level_coop_triggers, restore_latched_states, update_gates, Gate,
+};
+use ::rand::rngs::StdRng;
+use ::rand::SeedableRng;
 use macroquad::prelude::*;
+use std::path::Path;
 
-use super::{ControlAction, Game, GameState, PlayerControls};
+use super::coop::{CoopLives, ReviveBubble};
+use super::versus_ai::VersusAi;
+use super::versus_fighter::{extra_slot_controls, VersusFighter, VersusInput};
+use super::versus_layout::{VersusLayout, ARENA_DIR};
+use super::versus_modes::{last_team_standing, team_tally, time_up_winner, Crab};
+use super::versus_rules::VersusTeams;
+use super::{
+    ContinueMode, ControlAction, CoopCameraMode, Game, GameState, HealthMode, PauseOption,
+    PlayerControls, SaveSlot,
+};
 
 impl Game {
     pub fn get_common_resolutions() -> Vec<(u32, u32)> {
@@ -71,15 +87,45 @@ impl Game {
             versus_played: self.versus_played,
         };
         let path = SaveData::get_save_path(slot)?;
-        save_data.save_to_file(&path)
+        match &self.save_passphrase {
+            Some((locked, key)) if *locked == slot => save_data.save_protected(&path, slot, key),
+            _ => save_data.save_to_file(&path, slot),
+        }
     }
 
     pub fn load_game(&mut self, slot: usize) -> Result<(), String> {
-        let save_data = SaveData::load_slot(slot)?;
+        let (save_data, source) = SaveData::load_slot(slot)?;
+        self.save_passphrase = None;
+        self.apply_save(save_data);
+        self.report_save_source(slot, source);
+        Ok(())
+    }
+
+    /// Loads a passphrase-locked slot, keeping the key for later saves.
+    pub fn unlock_game(&mut self, slot: usize, passphrase: &str) -> Result<(), String> {
+        let (save_data, key, source) = SaveData::load_locked_slot(slot, passphrase)?;
+        self.save_passphrase = Some((slot, key));
+        self.apply_save(save_data);
+        self.report_save_source(slot, source);
+        Ok(())
+    }
+
+    fn report_save_source(&mut self, slot: usize, source: SaveSource) {
+        if let SaveSource::Backup(n) = source {
+            self.show_error(format!(
+                "Slot {} was damaged, continuing from backup {}",
+                slot + 1,
+                n
+            ));
+        }
+    }
+
+    fn apply_save(&mut self, save_data: SaveData) {
         self.current_level = save_data.current_level;
         self.unlocked_levels = save_data.unlocked_levels;
         self.lives = save_data.lives;
         self.score = save_data.score;
+        self.level_start_score = save_data.score;
         let default_total = create_level_coins(self.current_level).len() as u32;
         self.total_coins = if save_data.total_coins == 0 {
             default_total
@@ -97,7 +143,94 @@ impl Game {
         self.use_p2_skin = self.player_name.to_lowercase() == "guicybercode";
         self.tutorial_completed = save_data.tutorial_completed;
         self.versus_played = save_data.versus_played;
-        Ok(())
+    }
+
+    /// Drops into the level a save was just loaded from.
+    pub fn resume_loaded_game(&mut self) {
+        self.load_level(
+            self.current_level,
+            self.last_checkpoint_pos.is_some(),
+            Some(self.time_remaining),
+            Some(self.coins_collected),
+        );
+        self.start_transition(GameState::Playing);
+    }
+
+    pub fn open_passphrase_prompt(&mut self, mode: ContinueMode) {
+        // Drop whatever was typed on the slot list.
+        while get_char_pressed().is_some() {}
+        self.passphrase_input.clear();
+        self.lock_passphrase.clear();
+        self.continue_mode = mode;
+    }
+
+    /// Typing into the ContinueMenu passphrase prompt. A wrong passphrase
+    /// keeps the prompt open for another try.
+    pub fn update_passphrase_prompt(&mut self) {
+        while let Some(ch) = get_char_pressed() {
+            if !ch.is_control() && self.passphrase_input.chars().count() < MAX_PASSPHRASE_LEN {
+                self.passphrase_input.push(ch);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.passphrase_input.pop();
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.passphrase_input.clear();
+            self.lock_passphrase.clear();
+            self.continue_mode = ContinueMode::View;
+            return;
+        }
+        if !is_key_pressed(KeyCode::Enter) {
+            return;
+        }
+        let slot = self.continue_selection;
+        let passphrase = std::mem::take(&mut self.passphrase_input);
+        let mode = self.continue_mode;
+        if mode == ContinueMode::Lock {
+            // Taken twice, so a typo can't lock the slot away for good.
+            self.lock_passphrase = passphrase;
+            self.continue_mode = ContinueMode::ConfirmLock;
+            return;
+        }
+        let result = match mode {
+            ContinueMode::Unlock => self.unlock_game(slot, &passphrase),
+            ContinueMode::ConfirmLock => {
+                if passphrase != std::mem::take(&mut self.lock_passphrase) {
+                    Err("Passphrases don't match".to_string())
+                } else {
+                    PassphraseKey::new(&passphrase).and_then(|key| {
+                        let path = SaveData::get_save_path(slot)?;
+                        SaveData::load_slot(slot)?
+                            .0
+                            .save_protected(&path, slot, &key)
+                    })
+                }
+            }
+            ContinueMode::RemoveLock => SaveData::get_save_path(slot).and_then(|path| {
+                let (save_data, _, _) = SaveData::load_locked_slot(slot, &passphrase)?;
+                save_data.save_to_file(&path, slot)
+            }),
+            _ => Ok(()),
+        };
+        match result {
+            Ok(()) => {
+                self.audio.play_menu_select();
+                self.continue_mode = ContinueMode::View;
+                if mode == ContinueMode::Unlock {
+                    self.resume_loaded_game();
+                } else {
+                    self.refresh_save_slots();
+                }
+            }
+            Err(e) => {
+                // Start a failed lock over from the first entry.
+                if mode == ContinueMode::ConfirmLock {
+                    self.continue_mode = ContinueMode::Lock;
+                }
+                self.show_error(e);
+            }
+        }
     }
 
     pub fn apply_skin(&self, player: &mut Player) {
@@ -112,6 +245,16 @@ impl Game {
         self.player_name.to_lowercase() == "guicybercode"
     }
 
+    /// Hit points are always on or off when forced; in AUTO they follow
+    /// assist mode and the easy difficulty.
+    pub fn health_enabled(&self) -> bool {
+        match self.health_mode {
+            HealthMode::On => true,
+            HealthMode::Off => false,
+            HealthMode::Auto => self.assist_mode || self.difficulty_multiplier == DIFFICULTY_EASY,
+        }
+    }
+
     pub fn show_error(&mut self, message: String) {
         self.error_message = Some(message);
         self.error_timer = 5.0;
@@ -151,29 +294,340 @@ impl Game {
         }
     }
 
-    pub fn handle_player_death_versus_p1(&mut self) {
-        self.audio.play_death();
-        if self.lives > 0 {
-            self.lives -= 1;
+    pub fn new_player(&self, x: f32, y: f32) -> Player {
+        let mut player = Player::new(
+            x,
+            y,
+            self.player_sprite_texture_p1.clone(),
+            self.player_sprite_texture_p2.clone(),
+        );
+        player.on_ground = true;
+        player.vel_y = 0.0;
+        player
+    }
+
+    /// Loads `level` for two players and resets everything the co-op rules track.
+    pub fn start_coop_level(
+        &mut self,
+        level: usize,
+        use_checkpoint: bool,
+        restored_time: Option<f32>,
+        restored_coins: Option<u32>,
+    ) {
+        self.load_level(level, use_checkpoint, restored_time, restored_coins);
+        if self.coop_rules.partner_physics {
+            self.platforms.extend(create_level_coop_ledges(level));
+            let bonus_coins = create_level_coop_coins(level);
+            self.total_coins += bonus_coins.len() as u32;
+            self.coins.extend(bonus_coins);
         }
-        if self.lives == 0 {
-            self.game_over_fade_timer = GAME_OVER_FADE_TIMER;
-            self.state = GameState::GameOver;
+        self.triggers = create_level_coop_triggers(level);
+        self.gates = create_level_coop_gates(level);
+        if use_checkpoint {
+            restore_latched_states(&mut self.triggers, &self.checkpoint_trigger_states);
+        }
+        self.refresh_gates();
+        self.player2 = Some(self.new_player(self.player.x + 100.0, self.player.y));
+        self.coop_ground = [
+            (self.player.x, self.player.y),
+            (self.player.x + 100.0, self.player.y),
+        ];
+        self.reset_coop_session();
+    }
+
+    fn reset_coop_session(&mut self) {
+        self.lives_p2 = self.lives;
+        self.respawn_timer_p1 = 0.0;
+        self.respawn_timer_p2 = 0.0;
+        self.revive_bubble_p1 = None;
+        self.revive_bubble_p2 = None;
+        self.coop_out_p1 = false;
+        self.coop_out_p2 = false;
+        self.coop_carrying = None;
+        self.coop_score_p1 = 0;
+        self.coop_score_p2 = 0;
+        self.coop_coins_p1 = 0;
+        self.coop_coins_p2 = 0;
+        self.p2_leave_timer = 0.0;
+    }
+
+    /// Player 2 can drop in with their jump key as long as P1 isn't using it too.
+    pub fn can_player2_drop_in(&self) -> bool {
+        let key = self.player2_controls.jump;
+        let p1 = &self.player1_controls;
+        key.is_some() && ![p1.left, p1.right, p1.jump, p1.action].contains(&key)
+    }
+
+    /// Moves a single-player run into co-op without reloading the level; P2
+    /// spawns at the last checkpoint, or the level start without one. The
+    /// level keeps its solo layout; co-op ledges and puzzles only come with
+    /// co-op started from Level Select.
+    pub fn drop_in_player2(&mut self) {
+        let (spawn_x, spawn_y) = match self.last_checkpoint_pos {
+            Some(checkpoint) => (checkpoint.0 + 100.0, checkpoint.1 - PLAYER_HEIGHT),
+            None => (150.0, GROUND_Y - PLAYER_HEIGHT),
+        };
+        self.player2 = Some(self.new_player(spawn_x, spawn_y));
+        self.reset_coop_session();
+        self.coop_score_p1 = self.score;
+        self.coop_coins_p1 = self.coins_collected;
+        self.camera_p2.snap(
+            (spawn_x + PLAYER_WIDTH / 2.0, spawn_y + PLAYER_HEIGHT / 2.0),
+            (screen_width(), screen_height()),
+            &self.world_bounds,
+        );
+        self.state = GameState::Coop;
+    }
+
+    /// Drops player 2 and carries the run on in `Playing`. Puzzle triggers go
+    /// with them so player 1 isn't left behind a door they can't open alone.
+    pub fn drop_out_player2(&mut self) {
+        self.player2 = None;
+        self.revive_bubble_p2 = None;
+        self.respawn_timer_p2 = 0.0;
+        self.coop_carrying = None;
+        self.coop_zoom = 1.0;
+        self.coop_split_active = false;
+        self.p2_leave_timer = 0.0;
+        self.triggers.clear();
+        self.gates.clear();
+        self.gate_platforms.clear();
+        self.state = GameState::Playing;
+    }
+
+    /// Whether a co-op player is on the field: not respawning, bubbled or out of lives.
+    pub fn coop_player_active(&self, is_p1: bool) -> bool {
+        if is_p1 {
+            self.respawn_timer_p1 <= 0.0 && self.revive_bubble_p1.is_none() && !self.coop_out_p1
         } else {
-            self.respawn_timer_p1 = RESPAWN_TIMER;
+            self.player2.is_some()
+                && self.respawn_timer_p2 <= 0.0
+                && self.revive_bubble_p2.is_none()
+                && !self.coop_out_p2
         }
     }
 
-    pub fn handle_player_death_versus_p2(&mut self) {
+    pub fn handle_coop_player_death(&mut self, is_p1: bool) {
         self.audio.play_death();
-        if self.lives > 0 {
-            self.lives -= 1;
+        if self.coop_rules.revive_bubble && self.coop_player_active(!is_p1) {
+            let body = if is_p1 {
+                Some(&self.player)
+            } else {
+                self.player2.as_ref()
+            };
+            if let Some(body) = body {
+                // Keep bubbles from players who fell out of the world reachable.
+                let max_y = self.world_bounds.fall_death_y() - REVIVE_BUBBLE_RADIUS * 2.0;
+                let bubble = ReviveBubble::new(
+                    body.x + body.width / 2.0,
+                    (body.y + body.height / 2.0).min(max_y),
+                );
+                if is_p1 {
+                    self.revive_bubble_p1 = Some(bubble);
+                } else {
+                    self.revive_bubble_p2 = Some(bubble);
+                }
+                return;
+            }
         }
-        if self.lives == 0 {
+        self.spend_coop_life(is_p1);
+    }
+
+    fn spend_coop_life(&mut self, is_p1: bool) {
+        let pool = if is_p1 || self.coop_rules.lives == CoopLives::Shared {
+            &mut self.lives
+        } else {
+            &mut self.lives_p2
+        };
+        *pool = pool.saturating_sub(1);
+        let out_of_lives = *pool == 0;
+        match (is_p1, out_of_lives) {
+            (true, true) => self.coop_out_p1 = true,
+            (true, false) => self.respawn_timer_p1 = RESPAWN_TIMER,
+            (false, true) => self.coop_out_p2 = true,
+            (false, false) => self.respawn_timer_p2 = RESPAWN_TIMER,
+        }
+        if self.coop_out_p1 && (self.player2.is_none() || self.coop_out_p2) {
             self.game_over_fade_timer = GAME_OVER_FADE_TIMER;
             self.state = GameState::GameOver;
+        }
+    }
+
+    /// Floats each bubble towards its partner and pops it on contact. A bubble
+    /// whose partner is out of lives can never be reached, so it falls back to
+    /// spending a life instead.
+    pub fn update_revive_bubbles(&mut self, dt: f32) {
+        for is_p1 in [true, false] {
+            let partner_rect = if is_p1 {
+                self.player2.as_ref().map(|p2| p2.get_rect())
+            } else {
+                Some(self.player.get_rect())
+            };
+            let partner_out = if is_p1 {
+                self.coop_out_p2
+            } else {
+                self.coop_out_p1
+            };
+            let partner_active = self.coop_player_active(!is_p1);
+            let bubble = if is_p1 {
+                &mut self.revive_bubble_p1
+            } else {
+                &mut self.revive_bubble_p2
+            };
+            let Some(ref mut b) = bubble else {
+                continue;
+            };
+            if partner_out {
+                *bubble = None;
+                self.spend_coop_life(is_p1);
+                continue;
+            }
+            let Some((px, py, pw, ph)) = partner_rect else {
+                continue;
+            };
+            if !partner_active {
+                continue;
+            }
+            b.update((px + pw / 2.0, py + ph / 2.0), dt);
+            if b.touches((px, py, pw, ph)) {
+                let (bx, by) = (b.x, b.y);
+                *bubble = None;
+                self.revive_coop_player(is_p1, bx, by);
+            }
+        }
+    }
+
+    /// Grab, carry and throw between co-op partners. The action key picks up
+    /// a partner standing alongside and throws them on the next press; the
+    /// carried player can wriggle free with a jump.
+    pub fn update_partner_carry(&mut self, action_tapped: (bool, bool), jump_tapped: (bool, bool)) {
+        let both_active = self.coop_player_active(true) && self.coop_player_active(false);
+        let Some(ref mut p2) = self.player2 else {
+            self.coop_carrying = None;
+            return;
+        };
+        if !self.coop_rules.partner_physics || !both_active {
+            self.coop_carrying = None;
+            return;
+        }
+        match self.coop_carrying {
+            None => {
+                if action_tapped.0 && Self::can_grab_partner(&self.player, p2) {
+                    self.coop_carrying = Some(true);
+                } else if action_tapped.1 && Self::can_grab_partner(p2, &self.player) {
+                    self.coop_carrying = Some(false);
+                }
+            }
+            Some(p1_carries) => {
+                let (carrier, held, throw, wriggle) = if p1_carries {
+                    (&mut self.player, p2, action_tapped.0, jump_tapped.1)
+                } else {
+                    (p2, &mut self.player, action_tapped.1, jump_tapped.0)
+                };
+                held.x = carrier.x;
+                held.y = carrier.y - held.height;
+                held.vel_x = carrier.vel_x;
+                held.vel_y = 0.0;
+                held.on_ground = false;
+                held.facing_right = carrier.facing_right;
+                if throw {
+                    let direction = if carrier.facing_right { 1.0 } else { -1.0 };
+                    held.vel_x = PARTNER_THROW_SPEED_X * direction;
+                    held.vel_y = PARTNER_THROW_SPEED_Y;
+                    held.knockback_timer = PARTNER_THROW_STUN;
+                    self.coop_carrying = None;
+                    self.audio.play_jump(false);
+                } else if wriggle {
+                    held.vel_y = JUMP_FORCE * 0.6;
+                    self.coop_carrying = None;
+                }
+            }
+        }
+    }
+
+    fn can_grab_partner(carrier: &Player, partner: &Player) -> bool {
+        let dx = (carrier.x + carrier.width / 2.0) - (partner.x + partner.width / 2.0);
+        let dy = (carrier.y + carrier.height) - (partner.y + partner.height);
+        carrier.on_ground
+            && partner.on_ground
+            && !carrier.standing_on_partner
+            && !partner.standing_on_partner
+            && dx.abs() <= PARTNER_GRAB_RANGE
+            && dy.abs() <= PARTNER_HEAD_TOLERANCE
+    }
+
+    /// Steps plates, levers and switches, then rebuilds the solid gate list.
+    /// `action_tapped` is per player (P1, P2); returns which of those presses
+    /// went to a lever or switch so they aren't reused for grabbing.
+    pub fn update_level_triggers(&mut self, dt: f32, action_tapped: (bool, bool)) -> (bool, bool) {
+        if self.triggers.is_empty() {
+            return (false, false);
+        }
+        let p1_rect = if self.coop_player_active(true) {
+            Some(self.player.get_rect())
         } else {
-            self.respawn_timer_p2 = RESPAWN_TIMER;
+            None
+        };
+        let p2_rect = if self.coop_player_active(false) {
+            self.player2.as_ref().map(|p2| p2.get_rect())
+        } else {
+            None
+        };
+        let players: Vec<_> = p1_rect.into_iter().chain(p2_rect).collect();
+        let enemies: Vec<_> = self
+            .enemies
+            .iter()
+            .filter(|e| e.alive)
+            .map(|e| (e.x, e.y, e.width, e.height))
+            .collect();
+        let mut used = (false, false);
+        for trigger in &mut self.triggers {
+            trigger.update(dt, &players, &enemies);
+            if let (true, Some(rect)) = (action_tapped.0, p1_rect) {
+                used.0 |= trigger.interact(rect);
+            }
+            if let (true, Some(rect)) = (action_tapped.1, p2_rect) {
+                used.1 |= trigger.interact(rect);
+            }
+        }
+        self.refresh_gates();
+        used
+    }
+
+    fn refresh_gates(&mut self) {
+        update_gates(&mut self.gates, &self.triggers);
+        self.gate_platforms = self
+            .gates
+            .iter()
+            .filter(|gate| gate.is_solid())
+            .map(Gate::as_platform)
+            .collect();
+    }
+
+    fn revive_coop_player(&mut self, is_p1: bool, center_x: f32, center_y: f32) {
+        let mut player = self.new_player(
+            center_x - PLAYER_WIDTH / 2.0,
+            center_y - PLAYER_HEIGHT / 2.0,
+        );
+        player.on_ground = false;
+        player.vel_y = JUMP_FORCE * 0.5;
+        player.invulnerable_timer = INVULNERABILITY_DURATION;
+        if is_p1 {
+            self.apply_skin(&mut player);
+            self.player = player;
+        } else {
+            self.player2 = Some(player);
+        }
+        self.audio.play_coin();
+        for _ in 0..PARTICLE_COUNT {
+            let angle = rand::gen_range(0.0, std::f32::consts::PI * 2.0);
+            let speed = rand::gen_range(60.0, 120.0);
+            self.particles.push(Particle::new(
+                center_x,
+                center_y,
+                angle.cos() * speed,
+                angle.sin() * speed,
+            ));
         }
     }
 
@@ -185,6 +639,83 @@ impl Game {
         }
     }
 
+    /// Puts the current gameplay mode behind the pause overlay. The paused
+    /// mode isn't updated until it resumes, so its timers hold where they are.
+    pub fn open_pause(&mut self) {
+        self.pause_origin = self.state;
+        self.state = GameState::Pause;
+        self.pause_selection = 0;
+        self.pause_confirm = None;
+        self.came_from_pause = false;
+    }
+
+    pub fn pause_options(&self) -> Vec<PauseOption> {
+        let mut options = vec![PauseOption::Resume, PauseOption::RestartLevel];
+        if self.pause_origin != GameState::Versus && self.last_checkpoint_pos.is_some() {
+            options.push(PauseOption::RestartCheckpoint);
+        }
+        options.extend([
+            PauseOption::Settings,
+            PauseOption::Credits,
+            PauseOption::MainMenu,
+        ]);
+        options
+    }
+
+    pub fn apply_pause_option(&mut self, option: PauseOption) {
+        match option {
+            PauseOption::Resume => {
+                self.state = self.pause_origin;
+            }
+            PauseOption::RestartLevel => match self.pause_origin {
+                GameState::Versus => {
+                    self.load_versus_map(::rand::random());
+                    self.state = GameState::Versus;
+                }
+                GameState::Coop => {
+                    self.last_checkpoint_pos = None;
+                    self.score = self.level_start_score;
+                    self.start_coop_level(self.current_level, false, None, None);
+                    self.state = GameState::Coop;
+                }
+                _ => {
+                    self.last_checkpoint_pos = None;
+                    self.score = self.level_start_score;
+                    self.load_level(self.current_level, false, None, None);
+                    self.state = GameState::Playing;
+                }
+            },
+            // Same as a respawn, minus the lost life: score, clock and coins carry over.
+            PauseOption::RestartCheckpoint => {
+                let time = Some(self.time_remaining);
+                let coins = Some(self.coins_collected);
+                if self.pause_origin == GameState::Coop {
+                    self.start_coop_level(self.current_level, true, time, coins);
+                    self.state = GameState::Coop;
+                } else {
+                    self.load_level(self.current_level, true, time, coins);
+                    self.state = GameState::Playing;
+                }
+            }
+            PauseOption::Settings => {
+                self.came_from_pause = true;
+                self.state = GameState::Settings;
+                self.settings_selection = 0;
+            }
+            PauseOption::Credits => {
+                self.came_from_pause = true;
+                self.state = GameState::Credits;
+            }
+            PauseOption::MainMenu => {
+                // An abandoned tournament match stays unplayed.
+                self.leave_tournament_match();
+                self.player2 = None;
+                self.transition_to_menu();
+                self.menu_selection = 0;
+            }
+        }
+    }
+
     pub fn transition_to_menu(&mut self) {
         if !self.splash_shown {
             self.splash_timer = 0.0;
@@ -199,9 +730,36 @@ impl Game {
             ControlAction::Left => controls.left.map(|k| is_key_down(k)).unwrap_or(false),
             ControlAction::Right => controls.right.map(|k| is_key_down(k)).unwrap_or(false),
             ControlAction::Jump => controls.jump.map(|k| is_key_down(k)).unwrap_or(false),
+            ControlAction::Action => controls.action.map(is_key_down).unwrap_or(false),
+            ControlAction::Item => controls.item.map(is_key_down).unwrap_or(false),
         }
     }
 
+    pub fn read_versus_input(&self, controls: &PlayerControls) -> VersusInput {
+        VersusInput {
+            left: self.is_control_pressed(controls, ControlAction::Left),
+            right: self.is_control_pressed(controls, ControlAction::Right),
+            jump: self.is_control_pressed(controls, ControlAction::Jump),
+            item: self.is_control_just_pressed(controls, ControlAction::Item),
+        }
+    }
+
+    /// Like `is_control_pressed`, but only true on the frame the key goes down.
+    pub fn is_control_just_pressed(
+        &self,
+        controls: &PlayerControls,
+        action: ControlAction,
+    ) -> bool {
+        let key = match action {
+            ControlAction::Left => controls.left,
+            ControlAction::Right => controls.right,
+            ControlAction::Jump => controls.jump,
+            ControlAction::Action => controls.action,
+            ControlAction::Item => controls.item,
+        };
+        key.map(is_key_pressed).unwrap_or(false)
+    }
+
     pub fn update_transition(&mut self, dt: f32) {
         if let Some(target) = self.transition.update(dt) {
             self.state = target;
@@ -227,14 +785,23 @@ impl Game {
         }
     }
 
+    /// Rereads every slot for the ContinueMenu.
+    pub fn refresh_save_slots(&mut self) {
+        self.save_slots = SaveData::list_all_saves()
+            .into_iter()
+            .map(|(slot, save)| SaveSlot {
+                save,
+                protected: SaveData::is_slot_protected(slot),
+            })
+            .collect();
+    }
+
     pub fn check_for_new_saves(&mut self) {
-        let saves = SaveData::list_all_saves();
+        self.refresh_save_slots();
         let mut newest_timestamp = self.last_save_timestamp;
-        for (_, save_opt) in saves {
-            if let Some(save) = save_opt {
-                if save.timestamp > newest_timestamp {
-                    newest_timestamp = save.timestamp;
-                }
+        for (save, _) in self.save_slots.iter().filter_map(|slot| slot.save.as_ref()) {
+            if save.timestamp > newest_timestamp {
+                newest_timestamp = save.timestamp;
             }
         }
         if newest_timestamp > self.last_save_timestamp {
@@ -310,13 +877,16 @@ impl Game {
         }
     }
 
-    pub fn calculate_versus_points(streak: u32) -> u32 {
-        if streak == 0 {
-            return 200;
-        }
-        let exp = streak.saturating_sub(1).min(10);
-        let multiplier = 1u32.checked_shl(exp).unwrap_or(u32::MAX);
-        200u32.saturating_mul(multiplier)
+    /// Points for a stomp on a streak of `streak`, scaled by the scorer's
+    /// handicap `multiplier`.
+    pub fn calculate_versus_points(streak: u32, multiplier: f32) -> u32 {
+        let base = if streak == 0 {
+            200
+        } else {
+            let exp = streak.saturating_sub(1).min(10);
+            200u32.saturating_mul(1u32.checked_shl(exp).unwrap_or(u32::MAX))
+        };
+        (base as f32 * multiplier).round() as u32
     }
 
     pub fn is_player_on_platform(
@@ -365,6 +935,30 @@ impl Game {
         player.handle_jump_custom(jump)
     }
 
+    /// Walls at the arena's sides, or in a wraparound arena, carries a
+    /// player who crosses one side over to the other.
+    pub fn keep_in_versus_arena(player: &mut Player, bounds: &WorldBounds, wrap: bool) {
+        if wrap {
+            let width = bounds.right - bounds.left;
+            let center_x = player.x + player.width / 2.0;
+            if center_x < bounds.left {
+                player.x += width;
+            } else if center_x > bounds.right {
+                player.x -= width;
+            }
+        } else if player.x < bounds.left {
+            player.x = bounds.left;
+            player.vel_x = 0.0;
+        } else if player.x + player.width > bounds.right {
+            player.x = bounds.right - player.width;
+            player.vel_x = 0.0;
+        }
+    }
+
+    pub fn touches_versus_hazard(&self, rect: (f32, f32, f32, f32)) -> bool {
+        self.versus_hazards.iter().any(|h| h.overlaps(rect))
+    }
+
     pub fn load_level(
         &mut self,
         level: usize,
@@ -372,7 +966,7 @@ impl Game {
         restored_time: Option<f32>,
         restored_coins: Option<u32>,
     ) {
-        use crate::camera::Camera;
+        use crate::camera::{level_world_bounds, Camera};
         use crate::checkpoint::create_level_checkpoints;
         use crate::enemy::create_level_enemies;
         use crate::platform::create_level_platforms;
@@ -435,11 +1029,30 @@ impl Game {
         player.vel_y = 0.0;
         self.apply_skin(&mut player);
         self.player = player;
+        self.player2 = None;
         self.enemies = enemies;
         self.platforms = platforms;
         self.coins = coins;
         self.checkpoints = checkpoints;
+        self.triggers.clear();
+        self.gates.clear();
+        self.gate_platforms.clear();
+        if !use_checkpoint {
+            self.checkpoint_trigger_states.clear();
+            self.level_start_score = self.score;
+        }
+        self.world_bounds = level_world_bounds(level);
         self.camera = Camera::new();
+        self.coop_zoom = 1.0;
+        self.coop_split_active = false;
+        self.camera.snap(
+            (
+                self.player.x + self.player.width / 2.0,
+                self.player.y + self.player.height / 2.0,
+            ),
+            (screen_width(), screen_height()),
+            &self.world_bounds,
+        );
         self.coins_collected = restored_coins.unwrap_or(0).min(total_coins);
         self.total_coins = total_coins;
         self.current_level = level;
@@ -458,59 +1071,375 @@ impl Game {
         self.level_start_fade_timer = LEVEL_START_FADE_TIMER;
     }
 
-    pub fn load_versus_map(&mut self) {
-        use crate::camera::Camera;
-        use crate::game::versus_layout::VersusLayout;
+    /// Frames both co-op players by zooming out; once that would pass
+    /// `COOP_MIN_ZOOM` the configured `CoopCameraMode` takes over.
+    pub fn update_coop_camera(&mut self, dt: f32) {
+        let players = [Some(&self.player), self.player2.as_ref()];
+        for (ground, player) in self.coop_ground.iter_mut().zip(players) {
+            if let Some(player) = player.filter(|p| p.on_ground && !p.standing_on_partner) {
+                *ground = (player.x, player.y);
+            }
+        }
+        let view = (screen_width(), screen_height());
+        let shake = self.camera_shake.get_offset();
+        let p1_center = (
+            self.player.x + self.player.width / 2.0,
+            self.player.y + self.player.height / 2.0,
+        );
+        let p2_center = self
+            .player2
+            .as_ref()
+            .map(|p2| (p2.x + p2.width / 2.0, p2.y + p2.height / 2.0));
+        let p2_facing = self.player2.as_ref().is_some_and(|p2| p2.facing_right);
 
-        let layout = VersusLayout::balanced_default();
-        let map_width = layout
-            .platforms
-            .iter()
-            .map(|p| p.x + p.width)
-            .fold(SCREEN_WIDTH as f32, f32::max);
-        let center_x = map_width / 2.0;
-        let spawn_offset = 140.0;
-        let spawn_y = GROUND_Y - PLAYER_HEIGHT;
+        // A player waiting to respawn shouldn't drag the view off the other one.
+        let framed = match p2_center {
+            Some(p2_center) if self.respawn_timer_p1 <= 0.0 && self.respawn_timer_p2 <= 0.0 => {
+                Some(p2_center)
+            }
+            _ => None,
+        };
+        let Some(p2_center) = framed else {
+            let (target, facing) = match p2_center {
+                Some(p2_center) if self.respawn_timer_p1 > 0.0 => (p2_center, p2_facing),
+                _ => (p1_center, self.player.facing_right),
+            };
+            self.coop_split_active = false;
+            self.coop_zoom = 1.0;
+            self.camera
+                .update(target, facing, view, &self.world_bounds, shake, dt);
+            return;
+        };
 
-        self.versus_platforms = layout.platforms;
-        self.level_start_fade_timer = LEVEL_START_FADE_TIMER;
-        let mut player = Player::new(
-            center_x - spawn_offset,
-            spawn_y,
-            self.player_sprite_texture_p1
-                .as_ref()
-                .map(|t| std::rc::Rc::clone(t)),
-            self.player_sprite_texture_p2
-                .as_ref()
-                .map(|t| std::rc::Rc::clone(t)),
+        let mut zoom = framing_zoom(p1_center, p2_center, view, COOP_FRAME_MARGIN);
+        let split_threshold = if self.coop_split_active {
+            COOP_SPLIT_MERGE_ZOOM
+        } else {
+            COOP_MIN_ZOOM
+        };
+        if zoom < split_threshold {
+            match self.coop_camera_mode {
+                CoopCameraMode::Split => {
+                    let half_view = (view.0, view.1 / 2.0);
+                    if !self.coop_split_active {
+                        self.camera_p2
+                            .snap(p2_center, half_view, &self.world_bounds);
+                        self.coop_split_active = true;
+                    }
+                    self.coop_zoom = 1.0;
+                    self.camera.update(
+                        p1_center,
+                        self.player.facing_right,
+                        half_view,
+                        &self.world_bounds,
+                        shake,
+                        dt,
+                    );
+                    self.camera_p2.update(
+                        p2_center,
+                        p2_facing,
+                        half_view,
+                        &self.world_bounds,
+                        shake,
+                        dt,
+                    );
+                    return;
+                }
+                CoopCameraMode::PullForward => {
+                    self.pull_lagging_player_forward();
+                    zoom = COOP_MIN_ZOOM;
+                }
+            }
+        }
+        if self.coop_split_active {
+            self.coop_split_active = false;
+            self.coop_zoom = COOP_MIN_ZOOM;
+        }
+
+        let zoom = zoom.max(COOP_MIN_ZOOM);
+        if zoom < self.coop_zoom {
+            self.coop_zoom = zoom;
+        } else {
+            self.coop_zoom += (zoom - self.coop_zoom) * (1.0 - (-COOP_ZOOM_SPEED * dt).exp());
+        }
+        // Re-read positions: pulling forward may have just moved a player.
+        let midpoint = match self.player2 {
+            Some(ref p2) => (
+                (self.player.x + p2.x) / 2.0 + PLAYER_WIDTH / 2.0,
+                (self.player.y + p2.y) / 2.0 + PLAYER_HEIGHT / 2.0,
+            ),
+            None => p1_center,
+        };
+        self.camera.update(
+            midpoint,
+            self.player.facing_right,
+            (view.0 / self.coop_zoom, view.1 / self.coop_zoom),
+            &self.world_bounds,
+            shake,
+            dt,
         );
-        self.apply_skin(&mut player);
-        self.player = player;
-        self.player.on_ground = true;
-        self.player.vel_y = 0.0;
-        self.player2 = Some(Player::new(
-            center_x + spawn_offset - PLAYER_WIDTH,
-            spawn_y,
-            self.player_sprite_texture_p1
-                .as_ref()
-                .map(|t| std::rc::Rc::clone(t)),
-            self.player_sprite_texture_p2
-                .as_ref()
-                .map(|t| std::rc::Rc::clone(t)),
-        ));
+    }
+
+    fn pull_lagging_player_forward(&mut self) {
         if let Some(ref mut p2) = self.player2 {
-            p2.on_ground = true;
-            p2.vel_y = 0.0;
-        }
-        self.player1_score = 0;
-        self.player2_score = 0;
-        self.player1_streak = 0;
-        self.player2_streak = 0;
-        self.player1_points = 0;
-        self.player2_points = 0;
-        self.respawn_timer_p1 = 0.0;
-        self.respawn_timer_p2 = 0.0;
-        self.versus_time_remaining = 600.0;
+            if self.player.x >= p2.x {
+                Self::regroup_player(p2, self.coop_ground[0]);
+            } else {
+                Self::regroup_player(&mut self.player, self.coop_ground[1]);
+            }
+        }
+    }
+
+    /// Sets `lagging` down just behind `ground`, the leader's last footing,
+    /// so they land beside the leader rather than inside them or mid-air.
+    fn regroup_player(lagging: &mut Player, ground: (f32, f32)) {
+        lagging.x = ground.0 - lagging.width - COOP_REGROUP_GAP;
+        lagging.y = ground.1;
+        lagging.vel_x = 0.0;
+        lagging.vel_y = 0.0;
+        lagging.on_ground = false;
+        lagging.invulnerable_timer = INVULNERABILITY_DURATION;
+    }
+
+    /// Sets up a fresh match with the current `versus_rules`.
+    /// Rereads the arena files so edits show up without a restart, then
+    /// opens the arena select screen.
+    pub fn open_arena_select(&mut self) {
+        self.tournament_playing = false;
+        let (arenas, errors) = VersusLayout::load_all(Path::new(ARENA_DIR));
+        self.versus_arenas = arenas;
+        if self.versus_arena_selection > self.versus_arenas.len() {
+            self.versus_arena_selection = 0;
+        }
+        if let Some(error) = errors.into_iter().next() {
+            self.show_error(format!("Arena skipped: {}", error));
+        }
+        self.state = GameState::VersusArenaSelect;
+    }
+
+    /// Plays the highlighted arena, rolling one when RANDOM is selected.
+    pub fn choose_versus_arena(&mut self) {
+        self.versus_arena = if self.versus_arena_selection >= self.versus_arenas.len() {
+            rand::gen_range(0, self.versus_arenas.len())
+        } else {
+            self.versus_arena_selection
+        };
+    }
+
+    /// Sets up a fresh match on the chosen arena. `seed` drives every
+    /// random roll in it.
+    pub fn load_versus_map(&mut self, seed: u64) {
+        self.versus_rng = StdRng::seed_from_u64(seed);
+        let layout = self
+            .versus_arenas
+            .get(self.versus_arena)
+            .cloned()
+            .unwrap_or_else(VersusLayout::balanced_default);
+        self.versus_spawns = layout.spawns;
+        self.versus_platforms = layout.platforms;
+        self.versus_bounds = layout.bounds;
+        self.versus_wrap = layout.wrap;
+        self.versus_hazards = layout.hazards;
+        self.versus_hill = layout.hill;
+        self.versus_crab = Crab::new(layout.crab_spawn);
+        self.level_start_fade_timer = LEVEL_START_FADE_TIMER;
+        let rules = self.versus_rules;
+        self.versus_fighters = (0..rules.players)
+            .map(|slot| {
+                let controls = match slot {
+                    0 => self.player1_controls.clone(),
+                    1 => self.player2_controls.clone(),
+                    _ => extra_slot_controls(slot),
+                };
+                let cpu = rules.is_cpu(slot).then(|| VersusAi::new(rules.cpu_level));
+                let player = self.new_player(0.0, 0.0);
+                let mut fighter =
+                    VersusFighter::new(player, slot, rules.team_of(slot), controls, cpu);
+                fighter.apply_head_start(&rules.handicaps[slot]);
+                fighter
+            })
+            .collect();
+        self.versus_team_rounds = vec![0; rules.team_count()];
+        self.versus_round = 1;
+        self.versus_round_result = None;
+        self.start_versus_round();
+        self.versus_round_intro = 0.0;
+        self.versus_recorded = false;
+        self.begin_versus_stream();
+    }
+
+    /// Puts every fighter back on their spawn with the per-round tallies,
+    /// stocks and clock reset. Match totals carry over.
+    fn start_versus_round(&mut self) {
+        use crate::camera::Camera;
+
+        let stocks = self.versus_rules.stocks.unwrap_or(0);
+        for index in 0..self.versus_fighters.len() {
+            self.spawn_versus_fighter(index);
+            let fighter = &mut self.versus_fighters[index];
+            fighter.round_kills = 0;
+            fighter.stocks = stocks;
+            fighter.respawn_timer = 0.0;
+            fighter.out = false;
+            fighter.objective = 0.0;
+        }
+        self.reset_versus_objectives();
+        self.reset_versus_items();
+        self.versus_time_remaining = self.versus_rules.time_limit.unwrap_or(0.0);
+        self.versus_round_intro = VERSUS_ROUND_INTRO;
         self.camera = Camera::new();
     }
+
+    /// Arenas with fewer spawns than fighters hand them out again in turn.
+    pub fn spawn_versus_fighter(&mut self, index: usize) {
+        let (x, y) = self.versus_spawns[index % self.versus_spawns.len()];
+        let mut player = self.new_player(x, y);
+        player.invulnerable_timer = self.versus_rules.spawn_invulnerability;
+        if self.versus_fighters[index].slot == 0 {
+            self.apply_skin(&mut player);
+        }
+        let fighter = &mut self.versus_fighters[index];
+        fighter.player = player;
+        fighter.shield = false;
+        fighter.speed_timer = 0.0;
+        fighter.frozen_timer = 0.0;
+    }
+
+    /// Closest fighter on another team who is on the field, for the CPU to chase.
+    pub fn nearest_versus_foe(&self, index: usize) -> Option<usize> {
+        let me = &self.versus_fighters[index];
+        self.versus_fighters
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.team != me.team && f.in_play())
+            .min_by(|(_, a), (_, b)| {
+                let dist =
+                    |f: &VersusFighter| (f.player.x - me.player.x).hypot(f.player.y - me.player.y);
+                dist(a).total_cmp(&dist(b))
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// First `(stomper, victim)` pair this frame. Teammates can't stomp
+    /// each other, and a spawn shield blocks the stomp.
+    pub fn find_versus_stomp(&self) -> Option<(usize, usize)> {
+        let fighters = &self.versus_fighters;
+        for (i, stomper) in fighters.iter().enumerate() {
+            for (j, victim) in fighters.iter().enumerate() {
+                if i == j
+                    || stomper.team == victim.team
+                    || !stomper.in_play()
+                    || !victim.in_play()
+                    || victim.player.is_invulnerable()
+                {
+                    continue;
+                }
+                if stomper
+                    .player
+                    .check_stomp(&victim.player, stomper.player.vel_y)
+                {
+                    return Some((i, j));
+                }
+            }
+        }
+        None
+    }
+
+    /// Who gets the credit when `victim` falls out or hits a hazard: their
+    /// opponent in a one-on-one, nobody once there are more players.
+    pub fn versus_fall_credit(&self, victim: usize) -> Option<usize> {
+        match self.versus_fighters.len() {
+            2 => Some(1 - victim),
+            _ => None,
+        }
+    }
+
+    /// Knocks out `victim`, crediting `scorer` if there is one. Returns true
+    /// when that decided the round.
+    pub fn award_versus_ko(&mut self, victim: usize, scorer: Option<usize>) -> bool {
+        let rules = &self.versus_rules;
+        let stocks_on = rules.stocks.is_some();
+        let fighter = &mut self.versus_fighters[victim];
+        fighter.take_ko(
+            rules.respawn_delay,
+            &rules.handicaps[fighter.slot],
+            stocks_on,
+        );
+        if let Some(scorer) = scorer {
+            let fighter = &mut self.versus_fighters[scorer];
+            fighter.score_ko(&rules.handicaps[fighter.slot]);
+        }
+        self.audio.play_enemy_death();
+
+        if let Some(target) = self.versus_rules.stomp_target {
+            let leader = (0..self.versus_team_rounds.len())
+                .find(|&team| self.versus_team_tally(team).0 >= target);
+            if leader.is_some() {
+                self.end_versus_round(leader);
+                return true;
+            }
+        }
+        if stocks_on {
+            let teams = self.versus_team_rounds.len();
+            if let Some(winner) = last_team_standing(&self.versus_fighters, teams) {
+                self.end_versus_round(winner);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// A team's knockouts this round, stocks left and match points.
+    pub fn versus_team_tally(&self, team: usize) -> (u32, u32, u32) {
+        team_tally(&self.versus_fighters, team)
+    }
+
+    pub fn versus_time_up_winner(&self) -> Option<usize> {
+        time_up_winner(&self.versus_fighters, self.versus_team_rounds.len())
+    }
+
+    /// Team ahead on rounds at the end of the match, `None` for a draw.
+    pub fn versus_match_winner(&self) -> Option<usize> {
+        let best = self.versus_team_rounds.iter().max()?;
+        let mut leaders = self
+            .versus_team_rounds
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| *r == best);
+        match (leaders.next(), leaders.next()) {
+            (Some((team, _)), None) => Some(team),
+            _ => None,
+        }
+    }
+
+    pub fn versus_team_name(&self, team: usize) -> String {
+        match self.versus_rules.teams {
+            VersusTeams::TwoVsTwo if team == 0 => "BLUE TEAM".to_string(),
+            VersusTeams::TwoVsTwo => "ORANGE TEAM".to_string(),
+            VersusTeams::FreeForAll => match self.tournament_match_names() {
+                Some(names) => names[team].to_uppercase(),
+                None => format!("PLAYER {}", team + 1),
+            },
+        }
+    }
+
+    pub fn end_versus_round(&mut self, winner: Option<usize>) {
+        if let Some(team) = winner {
+            self.versus_team_rounds[team] += 1;
+        }
+        self.versus_round_result = winner;
+        let needed = self.versus_rules.rounds_to_win();
+        if self
+            .versus_team_rounds
+            .iter()
+            .any(|&rounds| rounds >= needed)
+            || self.versus_round >= self.versus_rules.best_of
+        {
+            self.audio.play_level_complete();
+            self.state = GameState::VersusEnd;
+        } else {
+            self.versus_round += 1;
+            self.start_versus_round();
+        }
+    }
 }
@@ -1,5 +1,5 @@
 use crate::audio::AudioManager;
-use crate::camera::Camera;
+use crate::camera::{level_world_bounds, Camera, WorldBounds};
 use crate::checkpoint::Checkpoint;
 use crate::coin::Coin;
 use crate::constants::*;
@@ -7,15 +7,39 @@ use crate::enemy::Enemy;
 use crate::name_filter;
 use crate::platform::Platform;
 use crate::player::Player;
-use crate::save::SaveData;
-use crate::systems::{CameraShake, CoinBounce, MenuAnimation, Particle, Transition};
+use crate::save::{rotate_save_key, PassphraseKey, SaveData, SaveSource};
+use crate::systems::{draw_heart, CameraShake, CoinBounce, MenuAnimation, Particle, Transition};
+use crate::trigger::{Gate, Trigger};
+use crate::versus_history::{format_date, VersusHistory};
+use ::rand::rngs::StdRng;
+use ::rand::SeedableRng;
+use coop::{CoopLives, CoopRules, ReviveBubble};
 use macroquad::prelude::*;
+use tournament::{Tournament, TournamentSetup};
+use versus_fighter::{slot_color, VersusFighter, VersusInput, MAX_VERSUS_PLAYERS};
+use versus_items::{FreezeTrap, ItemBox, Shell};
+use versus_layout::{Hazard, VersusLayout};
+use versus_modes::{Crab, Loose};
+use versus_net::{NetLobby, NetMatch, NetRole};
+use versus_rules::{VersusMode, VersusRules, VersusTeams, VERSUS_RULE_ROWS};
+use versus_stream::{recording_name, Broadcast, Spectating};
 
+mod coop;
 mod draw;
 mod helpers;
 mod state;
+mod tournament;
 mod update;
+mod versus_ai;
+mod versus_fighter;
+mod versus_handicap;
+mod versus_items;
 mod versus_layout;
+mod versus_modes;
+mod versus_net;
+mod versus_rules;
+mod versus_stats;
+mod versus_stream;
 
 pub use state::*;
 pub struct Game {
@@ -24,7 +48,18 @@ pub struct Game {
     platforms: Vec<Platform>,
     coins: Vec<Coin>,
     checkpoints: Vec<Checkpoint>,
+    triggers: Vec<Trigger>,
+    gates: Vec<Gate>,
+    gate_platforms: Vec<Platform>,
+    checkpoint_trigger_states: Vec<bool>,
     camera: Camera,
+    camera_p2: Camera,
+    coop_zoom: f32,
+    coop_split_active: bool,
+    /// Where P1 and P2 last stood on solid ground, for pulling a lagging
+    /// partner forward.
+    coop_ground: [(f32, f32); 2],
+    world_bounds: WorldBounds,
     audio: AudioManager,
     state: GameState,
     coins_collected: u32,
@@ -37,31 +72,107 @@ pub struct Game {
     last_checkpoint_pos: Option<(f32, f32)>,
     time_remaining: f32,
     settings_selection: usize,
+    /// Result of the last settings action, shown until the selection moves.
+    settings_notice: Option<String>,
+    /// NEW SAVE KEY was chosen and is waiting for a Y/N.
+    confirm_key_rotation: bool,
     sound_enabled: bool,
     resolution_index: usize,
     available_resolutions: Vec<(u32, u32)>,
     score: u32,
+    /// Score when the current level was entered, restored by RestartLevel.
+    level_start_score: u32,
     lives: u32,
     respawn_timer: f32,
     game_over_fade_timer: f32,
     level_start_fade_timer: f32,
     footstep_timer: f32,
     player2: Option<Player>,
-    player1_score: u32,
-    player2_score: u32,
-    player1_streak: u32,
-    player2_streak: u32,
-    player1_points: u32,
-    player2_points: u32,
     versus_platforms: Vec<Platform>,
+    versus_spawns: Vec<(f32, f32)>,
+    versus_bounds: WorldBounds,
+    versus_wrap: bool,
+    versus_hazards: Vec<Hazard>,
+    versus_arenas: Vec<VersusLayout>,
+    /// Highlighted arena; one past the last arena is RANDOM.
+    versus_arena_selection: usize,
+    /// Arena the current match is played on, kept for rematches.
+    versus_arena: usize,
+    versus_rules: VersusRules,
+    versus_fighters: Vec<VersusFighter>,
+    /// Rounds won, indexed by team.
+    versus_team_rounds: Vec<u32>,
+    versus_setup_selection: usize,
+    versus_round: u32,
+    versus_round_intro: f32,
+    /// Team that took the round that just finished, `None` when it was drawn.
+    versus_round_result: Option<usize>,
+    /// King of the Hill zone as `(x, y, width, height)`.
+    versus_hill: (f32, f32, f32, f32),
+    /// Coin Rush coins lying in the arena.
+    versus_coins: Vec<Loose>,
+    versus_coin_timer: f32,
+    versus_coin_rotation: f32,
+    versus_crab: Crab,
+    versus_item_boxes: Vec<ItemBox>,
+    versus_item_timer: f32,
+    versus_shells: Vec<Shell>,
+    versus_traps: Vec<FreezeTrap>,
+    /// Source of every random roll inside a Versus tick, so an online
+    /// match plays out the same on both machines.
+    versus_rng: StdRng,
+    net_lobby: NetLobby,
+    /// Set while an online match is running.
+    netplay: Option<NetMatch>,
+    broadcast: Broadcast,
+    /// Set while watching someone else's match.
+    spectating: Option<Spectating>,
+    /// The finished match is already in the history.
+    versus_recorded: bool,
+    versus_history: VersusHistory,
+    versus_stats_page: usize,
+    versus_handicap_selection: usize,
+    /// Seat being edited on the handicap screen.
+    versus_handicap_player: usize,
+    /// The bracket in progress, if any.
+    tournament: Option<Tournament>,
+    tournament_setup: TournamentSetup,
+    /// The current Versus match is the bracket's next pairing.
+    tournament_playing: bool,
+    /// The player's own rules, put back once a tournament match is over.
+    rules_before_tournament: Option<VersusRules>,
     respawn_timer_p1: f32,
     respawn_timer_p2: f32,
+    coop_rules: CoopRules,
+    coop_setup_selection: usize,
+    lives_p2: u32,
+    revive_bubble_p1: Option<ReviveBubble>,
+    revive_bubble_p2: Option<ReviveBubble>,
+    coop_out_p1: bool,
+    coop_out_p2: bool,
+    /// `Some(true)` while P1 carries P2, `Some(false)` for the other way round.
+    coop_carrying: Option<bool>,
+    p2_leave_timer: f32,
+    coop_score_p1: u32,
+    coop_score_p2: u32,
+    coop_coins_p1: u32,
+    coop_coins_p2: u32,
     versus_time_remaining: f32,
     player_name: String,
     continue_selection: usize,
     continue_mode: ContinueMode,
+    /// Each slot's profile as the ContinueMenu lists it. Reread when a slot
+    /// may have changed rather than every frame.
+    save_slots: Vec<SaveSlot>,
     name_input: String,
     name_input_error: Option<String>,
+    passphrase_input: String,
+    /// First entry of a new passphrase, held until it's been typed again.
+    lock_passphrase: String,
+    /// Slot and key of the passphrase-locked profile being played, so
+    /// saving back to that slot keeps it locked. Other slots, and `None`,
+    /// save under the keyfile.
+    save_passphrase: Option<(usize, PassphraseKey)>,
     tutorial_page: usize,
     tutorial_completed: bool,
     versus_played: bool,
@@ -71,6 +182,8 @@ pub struct Game {
     level_info_cache: Vec<(String, usize, Color)>,
     pause_selection: usize,
     came_from_pause: bool,
+    pause_origin: GameState,
+    pause_confirm: Option<PauseOption>,
     use_p2_skin: bool,
     player_sprite_texture_p1: Option<std::rc::Rc<Texture2D>>,
     player_sprite_texture_p2: Option<std::rc::Rc<Texture2D>>,
@@ -89,6 +202,8 @@ pub struct Game {
     colorblind_mode: bool,
     font_size_scale: f32,
     assist_mode: bool,
+    health_mode: HealthMode,
+    coop_camera_mode: CoopCameraMode,
     camera_shake: CameraShake,
     particles: Vec<Particle>,
     coin_bounces: Vec<CoinBounce>,
@@ -156,7 +271,16 @@ impl Game {
             platforms: Vec::with_capacity(ESTIMATED_PLATFORMS_PER_LEVEL),
             coins: Vec::with_capacity(ESTIMATED_COINS_PER_LEVEL),
             checkpoints: Vec::with_capacity(ESTIMATED_CHECKPOINTS_PER_LEVEL),
+            triggers: Vec::new(),
+            gates: Vec::new(),
+            gate_platforms: Vec::new(),
+            checkpoint_trigger_states: Vec::new(),
             camera: Camera::new(),
+            camera_p2: Camera::new(),
+            coop_zoom: 1.0,
+            coop_split_active: false,
+            coop_ground: [(0.0, 0.0); 2],
+            world_bounds: level_world_bounds(1),
             audio,
             state: GameState::Splash,
             coins_collected: 0,
@@ -169,31 +293,82 @@ impl Game {
             last_checkpoint_pos: None,
             time_remaining: 0.0,
             settings_selection: 0,
+            settings_notice: None,
+            confirm_key_rotation: false,
             sound_enabled: true,
             resolution_index: 0,
             available_resolutions: Self::get_common_resolutions(),
             score: 0,
+            level_start_score: 0,
             lives: DEFAULT_LIVES,
             respawn_timer: 0.0,
             game_over_fade_timer: 0.0,
             level_start_fade_timer: LEVEL_START_FADE_TIMER,
             footstep_timer: 0.0,
             player2: None,
-            player1_score: 0,
-            player2_score: 0,
-            player1_streak: 0,
-            player2_streak: 0,
-            player1_points: 0,
-            player2_points: 0,
             versus_platforms: Vec::with_capacity(10),
+            versus_spawns: Vec::new(),
+            versus_bounds: WorldBounds::new(0.0, SCREEN_WIDTH as f32, 0.0, FALL_DEATH_Y),
+            versus_wrap: false,
+            versus_hazards: Vec::new(),
+            versus_arenas: vec![VersusLayout::balanced_default()],
+            versus_arena_selection: 0,
+            versus_arena: 0,
+            versus_rules: VersusRules::default(),
+            versus_fighters: Vec::new(),
+            versus_team_rounds: Vec::new(),
+            versus_setup_selection: 0,
+            versus_round: 1,
+            versus_round_intro: 0.0,
+            versus_round_result: None,
+            versus_hill: (0.0, 0.0, 0.0, 0.0),
+            versus_coins: Vec::new(),
+            versus_coin_timer: 0.0,
+            versus_coin_rotation: 0.0,
+            versus_crab: Crab::new((0.0, 0.0)),
+            versus_item_boxes: Vec::new(),
+            versus_item_timer: 0.0,
+            versus_shells: Vec::new(),
+            versus_traps: Vec::new(),
+            versus_rng: StdRng::seed_from_u64(0),
+            net_lobby: NetLobby::default(),
+            netplay: None,
+            broadcast: Broadcast::default(),
+            spectating: None,
+            versus_recorded: false,
+            versus_history: VersusHistory::default(),
+            versus_stats_page: 0,
+            versus_handicap_selection: 0,
+            versus_handicap_player: 0,
+            tournament: None,
+            tournament_setup: TournamentSetup::default(),
+            tournament_playing: false,
+            rules_before_tournament: None,
             respawn_timer_p1: 0.0,
             respawn_timer_p2: 0.0,
+            coop_rules: CoopRules::default(),
+            coop_setup_selection: 0,
+            lives_p2: DEFAULT_LIVES,
+            revive_bubble_p1: None,
+            revive_bubble_p2: None,
+            coop_out_p1: false,
+            coop_out_p2: false,
+            coop_carrying: None,
+            p2_leave_timer: 0.0,
+            coop_score_p1: 0,
+            coop_score_p2: 0,
+            coop_coins_p1: 0,
+            coop_coins_p2: 0,
             versus_time_remaining: 600.0,
             player_name: String::new(),
             continue_selection: 0,
             continue_mode: ContinueMode::View,
+            save_slots: Vec::new(),
             name_input: String::new(),
             name_input_error: None,
+            passphrase_input: String::new(),
+            lock_passphrase: String::new(),
+            save_passphrase: None,
             tutorial_page: 0,
             tutorial_completed: false,
             versus_played: false,
@@ -203,6 +378,8 @@ impl Game {
             level_info_cache: Self::init_level_info_cache(),
             pause_selection: 0,
             came_from_pause: false,
+            pause_origin: GameState::Playing,
+            pause_confirm: None,
             use_p2_skin: false,
             player_sprite_texture_p1,
             player_sprite_texture_p2,
@@ -214,17 +391,25 @@ impl Game {
                 left: Some(KeyCode::A),
                 right: Some(KeyCode::D),
                 jump: Some(KeyCode::W),
+                action: Some(KeyCode::S),
+                item: Some(KeyCode::E),
                 left_gamepad: None,
                 right_gamepad: None,
                 jump_gamepad: None,
+                action_gamepad: None,
+                item_gamepad: None,
             },
             player2_controls: PlayerControls {
                 left: Some(KeyCode::Left),
                 right: Some(KeyCode::Right),
                 jump: Some(KeyCode::Up),
+                action: Some(KeyCode::Down),
+                item: Some(KeyCode::RightShift),
                 left_gamepad: None,
                 right_gamepad: None,
                 jump_gamepad: None,
+                action_gamepad: None,
+                item_gamepad: None,
             },
             controls_selection: 0,
             controls_player: 1,
@@ -235,11 +420,13 @@ impl Game {
             colorblind_mode: false,
             font_size_scale: 1.0,
             assist_mode: false,
+            health_mode: HealthMode::Auto,
+            coop_camera_mode: CoopCameraMode::Split,
             camera_shake: CameraShake::new(),
             particles: Vec::with_capacity(PARTICLE_COUNT * 10),
             coin_bounces: Vec::new(),
             menu_animation: MenuAnimation::new(7),
-            pause_animation: MenuAnimation::new(4),
+            pause_animation: MenuAnimation::new(6),
             transition: Transition::new(),
         }
     }
@@ -1,3 +1,4 @@
+use crate::save::{SaveData, SaveSource};
 use macroquad::prelude::KeyCode;
 
 #[derive(Clone, Copy, PartialEq)]
@@ -12,8 +13,18 @@ pub enum GameState {
     Playing,
     GameOver,
     LevelComplete,
+    VersusSetup,
+    VersusArenaSelect,
     Versus,
     VersusEnd,
+    NetLobby,
+    BroadcastSetup,
+    Spectate,
+    VersusStats,
+    VersusHandicaps,
+    TournamentSetup,
+    TournamentBracket,
+    CoopSetup,
     Coop,
     Respawn,
     ContinueMenu,
@@ -27,6 +38,10 @@ pub enum ControlAction {
     Left,
     Right,
     Jump,
+    /// Context action: grab/throw a partner in co-op.
+    Action,
+    /// Uses the held Versus item.
+    Item,
 }
 
 #[derive(Clone)]
@@ -34,13 +49,122 @@ pub struct PlayerControls {
     pub left: Option<KeyCode>,
     pub right: Option<KeyCode>,
     pub jump: Option<KeyCode>,
+    pub action: Option<KeyCode>,
+    pub item: Option<KeyCode>,
     pub left_gamepad: Option<u8>,
     pub right_gamepad: Option<u8>,
     pub jump_gamepad: Option<u8>,
+    pub action_gamepad: Option<u8>,
+    pub item_gamepad: Option<u8>,
 }
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum ContinueMode {
     View,
     DeleteConfirm,
+    /// Asking for the passphrase of a locked slot before loading it.
+    Unlock,
+    /// Asking for a passphrase to lock the selected slot with.
+    Lock,
+    /// Asking for the new passphrase again; the slot is only locked if both
+    /// entries match.
+    ConfirmLock,
+    /// Asking for the current passphrase before going back to the keyfile.
+    RemoveLock,
+}
+
+/// One row of the ContinueMenu, as read when the list was last refreshed.
+pub struct SaveSlot {
+    /// `None` for an empty, locked or unreadable slot.
+    pub save: Option<(SaveData, SaveSource)>,
+    /// Locked with a passphrase.
+    pub protected: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HealthMode {
+    Auto,
+    On,
+    Off,
+}
+
+impl HealthMode {
+    pub fn next(self) -> Self {
+        match self {
+            HealthMode::Auto => HealthMode::On,
+            HealthMode::On => HealthMode::Off,
+            HealthMode::Off => HealthMode::Auto,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            HealthMode::Auto => HealthMode::Off,
+            HealthMode::On => HealthMode::Auto,
+            HealthMode::Off => HealthMode::On,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HealthMode::Auto => "AUTO",
+            HealthMode::On => "ON",
+            HealthMode::Off => "OFF",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CoopCameraMode {
+    Split,
+    PullForward,
+}
+
+impl CoopCameraMode {
+    pub fn toggle(self) -> Self {
+        match self {
+            CoopCameraMode::Split => CoopCameraMode::PullForward,
+            CoopCameraMode::PullForward => CoopCameraMode::Split,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CoopCameraMode::Split => "SPLIT",
+            CoopCameraMode::PullForward => "PULL FORWARD",
+        }
+    }
+}
+
+/// Entries on the pause overlay; which ones appear depends on the paused mode.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PauseOption {
+    Resume,
+    RestartLevel,
+    RestartCheckpoint,
+    Settings,
+    Credits,
+    MainMenu,
+}
+
+impl PauseOption {
+    pub fn label(self, versus: bool) -> &'static str {
+        match self {
+            PauseOption::Resume => "RESUME",
+            PauseOption::RestartLevel if versus => "RESTART MATCH",
+            PauseOption::RestartLevel => "RESTART LEVEL",
+            PauseOption::RestartCheckpoint => "RESTART FROM CHECKPOINT",
+            PauseOption::Settings => "SETTINGS",
+            PauseOption::Credits => "CREDITS",
+            PauseOption::MainMenu => "MAIN MENU",
+        }
+    }
+
+    /// Options that throw away the current run ask before going ahead.
+    pub fn needs_confirm(self) -> bool {
+        matches!(
+            self,
+            PauseOption::RestartLevel | PauseOption::RestartCheckpoint | PauseOption::MainMenu
+        )
+    }
 }
@@ -0,0 +1,545 @@
+use super::versus_layout::{VersusLayout, ARENA_DIR};
+use super::versus_rules::{VersusRules, VersusTeams};
+use super::{Game, GameState};
+use crate::name_filter;
+use crate::save::{get_save_dir, write_atomic};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TOURNAMENT_FILE: &str = "tournament.json";
+pub const MIN_ENTRANTS: usize = 4;
+pub const MAX_ENTRANTS: usize = 16;
+
+/// Rows on the tournament setup screen.
+pub const TOURNAMENT_ROWS: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BracketKind {
+    Single,
+    Double,
+}
+
+impl BracketKind {
+    /// Losses before a player is out.
+    fn lives(self) -> u32 {
+        match self {
+            BracketKind::Single => 1,
+            BracketKind::Double => 2,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BracketKind::Single => "SINGLE ELIMINATION",
+            BracketKind::Double => "DOUBLE ELIMINATION",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            BracketKind::Single => BracketKind::Double,
+            BracketKind::Double => BracketKind::Single,
+        }
+    }
+}
+
+/// Two entrants, by index into `Tournament::names`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Pairing {
+    pub players: [usize; 2],
+    pub winner: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct BracketRound {
+    pub pairings: Vec<Pairing>,
+    /// Entrants who sit this round out and go through.
+    pub byes: Vec<usize>,
+}
+
+/// A bracket played one Versus match at a time, saved beside the profiles
+/// after every result so it survives closing the game.
+///
+/// Rounds are drawn as the previous one finishes: entrants still in are
+/// paired with others on the same number of losses, top seed against
+/// bottom, with a bye for the odd one out. With one life that is a plain
+/// knockout; with two, the zero-loss players form the upper bracket and
+/// the one-loss players the lower, and once one of each is left they meet
+/// in the grand final. A lower-bracket win there leaves both on one loss,
+/// so the final is played again.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tournament {
+    /// Entrants, best seed first.
+    pub names: Vec<String>,
+    pub kind: BracketKind,
+    pub rules: VersusRules,
+    pub rounds: Vec<BracketRound>,
+}
+
+impl Tournament {
+    pub fn new(names: Vec<String>, kind: BracketKind, rules: VersusRules) -> Result<Self, String> {
+        if names.len() < MIN_ENTRANTS || names.len() > MAX_ENTRANTS {
+            return Err(format!(
+                "A tournament needs {} to {} players",
+                MIN_ENTRANTS, MAX_ENTRANTS
+            ));
+        }
+        for (i, name) in names.iter().enumerate() {
+            check_entrant(&names[..i], name)?;
+        }
+        let rules = VersusRules {
+            players: 2,
+            cpu_players: 0,
+            teams: VersusTeams::FreeForAll,
+            // Seats change hands every match, so per-seat handicaps don't carry.
+            handicaps: VersusRules::default().handicaps,
+            ..rules
+        };
+        let mut tournament = Self {
+            names,
+            kind,
+            rules,
+            rounds: Vec::new(),
+        };
+        tournament.draw_next_round();
+        Ok(tournament)
+    }
+
+    pub fn losses(&self, player: usize) -> u32 {
+        self.rounds
+            .iter()
+            .flat_map(|round| &round.pairings)
+            .filter(|p| p.players.contains(&player) && p.winner.is_some_and(|w| w != player))
+            .count() as u32
+    }
+
+    fn byes(&self, player: usize) -> usize {
+        self.rounds
+            .iter()
+            .filter(|round| round.byes.contains(&player))
+            .count()
+    }
+
+    pub fn alive(&self, player: usize) -> bool {
+        self.losses(player) < self.kind.lives()
+    }
+
+    pub fn champion(&self) -> Option<usize> {
+        let mut alive = (0..self.names.len()).filter(|&p| self.alive(p));
+        match (alive.next(), alive.next()) {
+            (Some(champion), None) => Some(champion),
+            _ => None,
+        }
+    }
+
+    /// The next pairing to play, as (round, pairing).
+    pub fn next_match(&self) -> Option<(usize, usize)> {
+        let round = self.rounds.len().checked_sub(1)?;
+        let index = self.rounds[round]
+            .pairings
+            .iter()
+            .position(|p| p.winner.is_none())?;
+        Some((round, index))
+    }
+
+    pub fn next_pairing(&self) -> Option<&Pairing> {
+        let (round, index) = self.next_match()?;
+        Some(&self.rounds[round].pairings[index])
+    }
+
+    /// Settles the next pairing in favour of its `side` (0 or 1) and draws
+    /// the following round once every pairing in this one is settled.
+    pub fn report(&mut self, side: usize) {
+        let Some((round, index)) = self.next_match() else {
+            return;
+        };
+        let pairing = &mut self.rounds[round].pairings[index];
+        pairing.winner = Some(pairing.players[side]);
+        if self.next_match().is_none() {
+            self.draw_next_round();
+        }
+    }
+
+    fn draw_next_round(&mut self) {
+        if self.champion().is_some() {
+            return;
+        }
+        let mut round = BracketRound::default();
+        let groups: Vec<Vec<usize>> = (0..self.kind.lives())
+            .map(|losses| {
+                (0..self.names.len())
+                    .filter(|&p| self.losses(p) == losses)
+                    .collect()
+            })
+            .collect();
+        if groups.iter().all(|group| group.len() <= 1) {
+            // Grand final: the last upper-bracket player meets the last
+            // lower-bracket one.
+            let finalists: Vec<usize> = groups.into_iter().flatten().collect();
+            round.pairings.push(Pairing {
+                players: [finalists[0], finalists[1]],
+                winner: None,
+            });
+        } else {
+            for mut group in groups {
+                if group.len() % 2 == 1 {
+                    // The best seed among those with the fewest byes.
+                    let bye = *group.iter().min_by_key(|&&p| self.byes(p)).unwrap();
+                    group.retain(|&p| p != bye);
+                    round.byes.push(bye);
+                }
+                let half = group.len() / 2;
+                for i in 0..half {
+                    round.pairings.push(Pairing {
+                        players: [group[i], group[group.len() - 1 - i]],
+                        winner: None,
+                    });
+                }
+            }
+        }
+        self.rounds.push(round);
+    }
+
+    pub fn get_path() -> Result<PathBuf, String> {
+        Ok(get_save_dir()?.join(TOURNAMENT_FILE))
+    }
+
+    /// The tournament in progress, if one was saved.
+    pub fn load() -> Result<Option<Self>, String> {
+        Self::load_from_file(&Self::get_path()?)
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json =
+            fs::read_to_string(path).map_err(|e| format!("Error reading tournament: {e}"))?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Error parsing tournament: {e}"))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to_file(&Self::get_path()?)
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| format!("Error serializing tournament: {e}"))?;
+        write_atomic(path, json.as_bytes(), false)
+            .map_err(|e| format!("Error writing tournament: {e}"))
+    }
+
+    pub fn delete() -> Result<(), String> {
+        let path = Self::get_path()?;
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| format!("Error deleting tournament: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks `name` as a new entrant alongside those already in `taken`.
+fn check_entrant(taken: &[String], name: &str) -> Result<(), String> {
+    if let (false, error) = name_filter::is_name_valid(name) {
+        return Err(error.unwrap_or_else(|| "Invalid name".to_string()));
+    }
+    if taken.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+        return Err(format!("{} is already in", name));
+    }
+    Ok(())
+}
+
+/// The tournament setup screen: entrants typed in one at a time.
+pub struct TournamentSetup {
+    pub names: Vec<String>,
+    pub input: String,
+    pub kind: BracketKind,
+    pub selection: usize,
+    pub error: Option<String>,
+}
+
+impl Default for TournamentSetup {
+    fn default() -> Self {
+        Self {
+            names: Vec::new(),
+            input: String::new(),
+            kind: BracketKind::Single,
+            selection: 0,
+            error: None,
+        }
+    }
+}
+
+impl Game {
+    pub fn open_tournament_setup(&mut self) {
+        let (arenas, _) = VersusLayout::load_all(Path::new(ARENA_DIR));
+        self.versus_arenas = arenas;
+        match Tournament::load() {
+            Ok(saved) => self.tournament = saved,
+            Err(e) => {
+                self.tournament = None;
+                self.show_error(e);
+            }
+        }
+        self.tournament_setup.error = None;
+        self.state = GameState::TournamentSetup;
+    }
+
+    pub fn update_tournament_setup(&mut self) {
+        let typed: Vec<char> = std::iter::from_fn(get_char_pressed).collect();
+        let setup = &mut self.tournament_setup;
+        // Letters go into the name, so only the arrow keys navigate.
+        if is_key_pressed(KeyCode::Up) && setup.selection > 0 {
+            setup.selection -= 1;
+            self.audio.play_menu_select();
+        }
+        if is_key_pressed(KeyCode::Down) && setup.selection < TOURNAMENT_ROWS - 1 {
+            setup.selection += 1;
+            self.audio.play_menu_select();
+        }
+        let change = is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::Right);
+        let confirm = is_key_pressed(KeyCode::Enter);
+        match setup.selection {
+            0 => {
+                for ch in typed {
+                    if !ch.is_control() && setup.input.chars().count() < 20 {
+                        setup.input.push(ch);
+                    }
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    // On an empty field it takes back the last entrant.
+                    if setup.input.pop().is_none() {
+                        setup.names.pop();
+                    }
+                    setup.error = None;
+                }
+                if confirm {
+                    let name = setup.input.trim().to_string();
+                    if setup.names.len() >= MAX_ENTRANTS {
+                        setup.error = Some(format!("{} players at most", MAX_ENTRANTS));
+                    } else {
+                        match check_entrant(&setup.names, &name) {
+                            Ok(()) => {
+                                setup.names.push(name);
+                                setup.input.clear();
+                                setup.error = None;
+                                self.audio.play_menu_select();
+                            }
+                            Err(e) => setup.error = Some(e),
+                        }
+                    }
+                }
+            }
+            1 if change || confirm => {
+                setup.kind = setup.kind.toggle();
+                self.audio.play_menu_select();
+            }
+            2 if confirm => {
+                let started = Tournament::new(setup.names.clone(), setup.kind, self.versus_rules)
+                    .and_then(|tournament| tournament.save().map(|_| tournament));
+                match started {
+                    Ok(tournament) => {
+                        self.audio.play_menu_select();
+                        self.tournament = Some(tournament);
+                        self.state = GameState::TournamentBracket;
+                    }
+                    Err(e) => setup.error = Some(e),
+                }
+            }
+            3 if confirm && self.tournament.is_some() => {
+                self.audio.play_menu_select();
+                self.state = GameState::TournamentBracket;
+            }
+            4 if confirm => {
+                self.audio.play_menu_select();
+                self.state = GameState::VersusSetup;
+            }
+            _ => {}
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.state = GameState::VersusSetup;
+        }
+    }
+
+    pub fn update_tournament_bracket(&mut self) {
+        let Some(tournament) = &self.tournament else {
+            self.state = GameState::TournamentSetup;
+            return;
+        };
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+            self.audio.play_menu_select();
+            if tournament.champion().is_some() {
+                // Done with: nothing left to resume.
+                if let Err(e) = Tournament::delete() {
+                    self.show_error(e);
+                }
+                self.tournament = None;
+                self.tournament_setup = TournamentSetup::default();
+                self.state = GameState::VersusSetup;
+            } else {
+                self.start_tournament_match();
+            }
+            return;
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.state = GameState::VersusSetup;
+        }
+    }
+
+    fn start_tournament_match(&mut self) {
+        let Some(tournament) = &self.tournament else {
+            return;
+        };
+        if self.rules_before_tournament.is_none() {
+            self.rules_before_tournament = Some(self.versus_rules);
+        }
+        self.versus_rules = tournament.rules;
+        self.choose_versus_arena();
+        self.load_versus_map(::rand::random());
+        self.tournament_playing = true;
+        self.versus_played = true;
+        self.start_transition(GameState::Versus);
+    }
+
+    /// Names for P1 and P2 while a tournament match is on.
+    pub fn tournament_match_names(&self) -> Option<[&str; 2]> {
+        if !self.tournament_playing {
+            return None;
+        }
+        let tournament = self.tournament.as_ref()?;
+        let pairing = tournament.next_pairing()?;
+        Some(pairing.players.map(|p| tournament.names[p].as_str()))
+    }
+
+    /// Ends a tournament match, finished or abandoned, and puts the
+    /// player's own rules back.
+    pub fn leave_tournament_match(&mut self) {
+        self.tournament_playing = false;
+        if let Some(rules) = self.rules_before_tournament.take() {
+            self.versus_rules = rules;
+        }
+    }
+
+    /// Takes the finished match back to the bracket. A drawn match isn't
+    /// settled, so the same pairing comes up again.
+    pub fn finish_tournament_match(&mut self) {
+        let winner = self.versus_match_winner();
+        self.leave_tournament_match();
+        let Some(tournament) = self.tournament.as_mut() else {
+            return;
+        };
+        if let Some(side) = winner {
+            tournament.report(side);
+        }
+        if let Err(e) = tournament.save() {
+            self.show_error(e);
+        }
+        self.state = GameState::TournamentBracket;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entrants(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Player{}", i + 1)).collect()
+    }
+
+    /// Plays the tournament out with `pick` choosing each winning side.
+    fn play_out(tournament: &mut Tournament, pick: impl Fn(&Pairing) -> usize) -> usize {
+        for _ in 0..200 {
+            if let Some(champion) = tournament.champion() {
+                return champion;
+            }
+            let side = pick(tournament.next_pairing().unwrap());
+            tournament.report(side);
+        }
+        panic!("tournament never finished");
+    }
+
+    #[test]
+    fn single_elimination_plays_everyone_once_per_round() {
+        for count in MIN_ENTRANTS..=MAX_ENTRANTS {
+            let mut tournament =
+                Tournament::new(entrants(count), BracketKind::Single, VersusRules::default())
+                    .unwrap();
+            // The better seed always wins.
+            let champion = play_out(&mut tournament, |p| (p.players[1] < p.players[0]) as usize);
+            assert_eq!(champion, 0);
+            let played: usize = tournament.rounds.iter().map(|r| r.pairings.len()).sum();
+            assert_eq!(played, count - 1, "{} players", count);
+        }
+    }
+
+    #[test]
+    fn double_elimination_needs_two_losses() {
+        for count in MIN_ENTRANTS..=MAX_ENTRANTS {
+            let mut tournament =
+                Tournament::new(entrants(count), BracketKind::Double, VersusRules::default())
+                    .unwrap();
+            // The worse seed always wins, so the top seed drops to the
+            // lower bracket first.
+            let champion = play_out(&mut tournament, |p| (p.players[1] > p.players[0]) as usize);
+            assert_eq!(champion, count - 1);
+            for player in 0..count {
+                let expected = if player == champion { 0 } else { 2 };
+                assert_eq!(tournament.losses(player), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn grand_final_is_replayed_after_a_lower_bracket_win() {
+        let mut tournament =
+            Tournament::new(entrants(4), BracketKind::Double, VersusRules::default()).unwrap();
+        // Seed 1 beats seed 4; seed 2 loses to seed 3.
+        tournament.report(0);
+        tournament.report(1);
+        // Upper: seed 1 beats seed 3. Lower: seed 2 knocks out seed 4.
+        tournament.report(0);
+        tournament.report(0);
+        // Seed 1 waits while seed 2 wins the lower final.
+        assert_eq!(tournament.rounds[2].byes, vec![0]);
+        tournament.report(0);
+        assert_eq!(tournament.next_pairing().unwrap().players, [0, 1]);
+        tournament.report(1);
+        // Both finalists have lost once now, so it's played again.
+        assert!(tournament.champion().is_none());
+        assert_eq!(tournament.next_pairing().unwrap().players, [0, 1]);
+        tournament.report(1);
+        assert_eq!(tournament.champion(), Some(1));
+    }
+
+    #[test]
+    fn entrants_are_validated() {
+        let rules = VersusRules::default();
+        assert!(Tournament::new(entrants(3), BracketKind::Single, rules).is_err());
+        assert!(Tournament::new(entrants(17), BracketKind::Single, rules).is_err());
+        let mut names = entrants(4);
+        names[3] = "player1".to_string();
+        assert!(Tournament::new(names, BracketKind::Single, rules).is_err());
+        let mut names = entrants(4);
+        names[2] = "ab".to_string();
+        assert!(Tournament::new(names, BracketKind::Single, rules).is_err());
+    }
+
+    #[test]
+    fn tournament_resumes_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TOURNAMENT_FILE);
+        assert!(Tournament::load_from_file(&path).unwrap().is_none());
+        let mut tournament =
+            Tournament::new(entrants(6), BracketKind::Double, VersusRules::default()).unwrap();
+        tournament.report(0);
+        tournament.save_to_file(&path).unwrap();
+        let loaded = Tournament::load_from_file(&path).unwrap().unwrap();
+        assert_eq!(loaded.names, tournament.names);
+        assert_eq!(loaded.next_match(), tournament.next_match());
+        assert_eq!(loaded.rules.players, 2);
+    }
+}
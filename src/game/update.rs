@@ -1,8 +1,10 @@
 use super::*;
+use crate::trigger::latched_states;
 
 impl Game {
     pub fn update(&mut self, dt: f32) {
         self.update_transition(dt);
+        self.update_versus_stream(dt);
 
         if self.error_timer > 0.0 {
             self.error_timer -= dt;
@@ -80,6 +82,7 @@ impl Game {
                     match self.menu_selection {
                         0 => {
                             self.has_new_save = false;
+                            self.refresh_save_slots();
                             self.start_transition(GameState::ContinueMenu);
                             self.continue_selection = 0;
                             self.continue_mode = ContinueMode::View;
@@ -88,15 +91,16 @@ impl Game {
                             self.start_transition(GameState::NameInput);
                             self.name_input.clear();
                             self.name_input_error = None;
+                            self.save_passphrase = None;
                         }
                         2 => {
                             self.start_transition(GameState::LevelSelect);
                             self.level_selection = 0;
+                            self.save_passphrase = None;
                         }
                         3 => {
-                            self.versus_played = true;
-                            self.load_versus_map();
-                            self.start_transition(GameState::Versus);
+                            self.versus_setup_selection = 0;
+                            self.state = GameState::VersusSetup;
                         }
                         4 => {
                             self.state = GameState::Settings;
@@ -160,8 +164,8 @@ impl Game {
                     self.state = GameState::Menu;
                 }
             }
-            GameState::ContinueMenu => {
-                if self.continue_mode == ContinueMode::View {
+            GameState::ContinueMenu => match self.continue_mode {
+                ContinueMode::View => {
                     if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
                         if self.continue_selection > 0 {
                             self.continue_selection -= 1;
@@ -174,28 +178,36 @@ impl Game {
                             self.audio.play_menu_select();
                         }
                     }
+                    let protected = self
+                        .save_slots
+                        .get(self.continue_selection)
+                        .is_some_and(|slot| slot.protected);
                     if is_key_pressed(KeyCode::Enter) {
-                        match self.load_game(self.continue_selection) {
-                            Ok(_) => {
-                                self.load_level(
-                                    self.current_level,
-                                    self.last_checkpoint_pos.is_some(),
-                                    Some(self.time_remaining),
-                                    Some(self.coins_collected),
-                                );
-                                self.start_transition(GameState::Playing);
-                            }
-                            Err(e) => {
-                                let error_msg = format!("Error loading save: {}", e);
-                                eprintln!("{}", error_msg);
-                                self.show_error(error_msg);
+                        if protected {
+                            self.open_passphrase_prompt(ContinueMode::Unlock);
+                        } else {
+                            match self.load_game(self.continue_selection) {
+                                Ok(_) => self.resume_loaded_game(),
+                                Err(e) => {
+                                    let error_msg = format!("Error loading save: {}", e);
+                                    eprintln!("{}", error_msg);
+                                    self.show_error(error_msg);
+                                }
                             }
                         }
                     }
+                    if is_key_pressed(KeyCode::P) && SaveData::slot_exists(self.continue_selection)
+                    {
+                        self.open_passphrase_prompt(if protected {
+                            ContinueMode::RemoveLock
+                        } else {
+                            ContinueMode::Lock
+                        });
+                    }
                     if is_key_pressed(KeyCode::Delete) || is_key_pressed(KeyCode::Backspace) {
                         match SaveData::get_save_path(self.continue_selection) {
-                            Ok(path) => {
-                                if SaveData::save_exists(&path)
+                            Ok(_) => {
+                                if SaveData::slot_exists(self.continue_selection)
                                     || SaveData::legacy_exists(self.continue_selection)
                                 {
                                     self.continue_mode = ContinueMode::DeleteConfirm;
@@ -211,30 +223,57 @@ impl Game {
                     if is_key_pressed(KeyCode::Escape) {
                         self.state = GameState::Menu;
                     }
-                } else {
+                }
+                ContinueMode::DeleteConfirm => {
                     if is_key_pressed(KeyCode::Y) {
                         if let Err(e) = SaveData::delete_save(self.continue_selection) {
                             let error_msg = format!("Error deleting save: {}", e);
                             eprintln!("{}", error_msg);
                             self.show_error(error_msg);
                         }
+                        self.refresh_save_slots();
                         self.continue_mode = ContinueMode::View;
                     }
                     if is_key_pressed(KeyCode::N) || is_key_pressed(KeyCode::Escape) {
                         self.continue_mode = ContinueMode::View;
                     }
                 }
-            }
+                ContinueMode::Unlock
+                | ContinueMode::Lock
+                | ContinueMode::ConfirmLock
+                | ContinueMode::RemoveLock => {
+                    self.update_passphrase_prompt();
+                }
+            },
             GameState::Settings => {
+                if self.confirm_key_rotation {
+                    if is_key_pressed(KeyCode::Y) {
+                        self.confirm_key_rotation = false;
+                        match rotate_save_key() {
+                            Ok(count) => {
+                                self.settings_notice =
+                                    Some(format!("New save key, {count} saves re-encrypted"));
+                                self.refresh_save_slots();
+                            }
+                            Err(e) => self.show_error(e),
+                        }
+                    }
+                    if is_key_pressed(KeyCode::N) || is_key_pressed(KeyCode::Escape) {
+                        self.confirm_key_rotation = false;
+                    }
+                    return;
+                }
                 if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
                     if self.settings_selection > 0 {
                         self.settings_selection -= 1;
+                        self.settings_notice = None;
                         self.audio.play_menu_select();
                     }
                 }
                 if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
-                    if self.settings_selection < 7 {
+                    if self.settings_selection < 10 {
                         self.settings_selection += 1;
+                        self.settings_notice = None;
                         self.audio.play_menu_select();
                     }
                 }
@@ -278,6 +317,14 @@ impl Game {
                             self.assist_mode = !self.assist_mode;
                             self.audio.play_menu_select();
                         }
+                        7 => {
+                            self.health_mode = self.health_mode.prev();
+                            self.audio.play_menu_select();
+                        }
+                        8 => {
+                            self.coop_camera_mode = self.coop_camera_mode.toggle();
+                            self.audio.play_menu_select();
+                        }
                         _ => {}
                     }
                 }
@@ -321,6 +368,14 @@ impl Game {
                             self.assist_mode = !self.assist_mode;
                             self.audio.play_menu_select();
                         }
+                        7 => {
+                            self.health_mode = self.health_mode.next();
+                            self.audio.play_menu_select();
+                        }
+                        8 => {
+                            self.coop_camera_mode = self.coop_camera_mode.toggle();
+                            self.audio.play_menu_select();
+                        }
                         _ => {}
                     }
                 }
@@ -337,7 +392,13 @@ impl Game {
                             self.controls_player = 1;
                             self.controls_waiting_input = None;
                         }
-                        7 => {
+                        9 => {
+                            self.audio.play_menu_select();
+                            self.settings_notice = None;
+                            self.confirm_key_rotation = true;
+                        }
+                        10 => {
+                            self.settings_notice = None;
                             self.audio.play_menu_select();
                             if self.came_from_pause {
                                 self.came_from_pause = false;
@@ -351,6 +412,7 @@ impl Game {
                     }
                 }
                 if is_key_pressed(KeyCode::Escape) {
+                    self.settings_notice = None;
                     self.audio.play_menu_select();
                     if self.came_from_pause {
                         self.came_from_pause = false;
@@ -408,6 +470,8 @@ impl Game {
                         KeyCode::Key7,
                         KeyCode::Key8,
                         KeyCode::Key9,
+                        KeyCode::LeftShift,
+                        KeyCode::RightShift,
                     ];
                     for &keycode in &keys_to_check {
                         if is_key_pressed(keycode) {
@@ -420,6 +484,8 @@ impl Game {
                                 ControlAction::Left => controls.left = Some(keycode),
                                 ControlAction::Right => controls.right = Some(keycode),
                                 ControlAction::Jump => controls.jump = Some(keycode),
+                                ControlAction::Action => controls.action = Some(keycode),
+                                ControlAction::Item => controls.item = Some(keycode),
                             }
                             self.controls_waiting_input = None;
                             self.audio.play_menu_select();
@@ -439,7 +505,7 @@ impl Game {
                         }
                     }
                     if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
-                        if self.controls_selection < 2 {
+                        if self.controls_selection < 4 {
                             self.controls_selection += 1;
                             self.audio.play_menu_select();
                         }
@@ -463,6 +529,8 @@ impl Game {
                             0 => ControlAction::Left,
                             1 => ControlAction::Right,
                             2 => ControlAction::Jump,
+                            3 => ControlAction::Action,
+                            4 => ControlAction::Item,
                             _ => return,
                         };
                         self.controls_waiting_input = Some((self.controls_player, action));
@@ -539,33 +607,15 @@ impl Game {
                     {
                         if self.level_selection == 0 && !self.tutorial_completed {
                             self.start_transition(GameState::Tutorial);
+                        } else if self.menu_selection == 2 {
+                            self.coop_setup_selection = 0;
+                            self.state = GameState::CoopSetup;
+                            self.audio.play_menu_select();
                         } else {
                             self.last_checkpoint_pos = None;
-                            self.load_level(self.level_selection + 1, false, None, None);
                             self.score = 0;
-                            if matches!(self.state, GameState::LevelSelect) {
-                                if self.menu_selection == 2 {
-                                    self.player2 = Some(Player::new(
-                                        150.0,
-                                        GROUND_Y - PLAYER_HEIGHT,
-                                        self.player_sprite_texture_p1
-                                            .as_ref()
-                                            .map(|t| std::rc::Rc::clone(t)),
-                                        self.player_sprite_texture_p2
-                                            .as_ref()
-                                            .map(|t| std::rc::Rc::clone(t)),
-                                    ));
-                                    if let Some(ref mut p2) = self.player2 {
-                                        p2.on_ground = true;
-                                        p2.vel_y = 0.0;
-                                    }
-                                    self.start_transition(GameState::Coop);
-                                } else {
-                                    self.start_transition(GameState::Playing);
-                                }
-                            } else {
-                                self.start_transition(GameState::Playing);
-                            }
+                            self.load_level(self.level_selection + 1, false, None, None);
+                            self.start_transition(GameState::Playing);
                         }
                     }
                 }
@@ -574,21 +624,133 @@ impl Game {
                     self.menu_selection = 0;
                 }
             }
+            GameState::CoopSetup => {
+                if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
+                    if self.coop_setup_selection > 0 {
+                        self.coop_setup_selection -= 1;
+                        self.audio.play_menu_select();
+                    }
+                }
+                if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
+                    if self.coop_setup_selection < 3 {
+                        self.coop_setup_selection += 1;
+                        self.audio.play_menu_select();
+                    }
+                }
+                let toggle = is_key_pressed(KeyCode::Left)
+                    || is_key_pressed(KeyCode::A)
+                    || is_key_pressed(KeyCode::Right)
+                    || is_key_pressed(KeyCode::D);
+                let confirm = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space);
+                match self.coop_setup_selection {
+                    0 if toggle || confirm => {
+                        self.coop_rules.lives = self.coop_rules.lives.toggle();
+                        self.audio.play_menu_select();
+                    }
+                    1 if toggle || confirm => {
+                        self.coop_rules.revive_bubble = !self.coop_rules.revive_bubble;
+                        self.audio.play_menu_select();
+                    }
+                    2 if toggle || confirm => {
+                        self.coop_rules.partner_physics = !self.coop_rules.partner_physics;
+                        self.audio.play_menu_select();
+                    }
+                    3 if confirm => {
+                        self.audio.play_menu_select();
+                        self.last_checkpoint_pos = None;
+                        self.score = 0;
+                        self.start_coop_level(self.level_selection + 1, false, None, None);
+                        self.start_transition(GameState::Coop);
+                    }
+                    _ => {}
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    self.state = GameState::LevelSelect;
+                }
+            }
+            GameState::VersusSetup => {
+                if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
+                    if self.versus_setup_selection > 0 {
+                        self.versus_setup_selection -= 1;
+                        self.audio.play_menu_select();
+                    }
+                }
+                if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
+                    if self.versus_setup_selection < VERSUS_RULE_ROWS + 5 {
+                        self.versus_setup_selection += 1;
+                        self.audio.play_menu_select();
+                    }
+                }
+                let left = is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A);
+                let right = is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D);
+                let confirm = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space);
+                if self.versus_setup_selection < VERSUS_RULE_ROWS {
+                    if left || right || confirm {
+                        self.versus_rules.cycle(self.versus_setup_selection, !left);
+                        self.audio.play_menu_select();
+                    }
+                } else if confirm {
+                    self.audio.play_menu_select();
+                    match self.versus_setup_selection - VERSUS_RULE_ROWS {
+                        0 => self.open_arena_select(),
+                        1 => self.open_net_lobby(),
+                        2 => self.open_broadcast_menu(),
+                        3 => self.open_versus_stats(),
+                        4 => self.open_tournament_setup(),
+                        _ => {
+                            self.versus_handicap_player = 0;
+                            self.versus_handicap_selection = 0;
+                            self.state = GameState::VersusHandicaps;
+                        }
+                    }
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    self.state = GameState::Menu;
+                }
+            }
+            GameState::NetLobby => self.update_net_lobby(dt),
+            GameState::BroadcastSetup => self.update_broadcast_menu(),
+            GameState::Spectate => self.update_spectate(dt),
+            GameState::VersusStats => self.update_versus_stats(),
+            GameState::VersusHandicaps => self.update_versus_handicaps(),
+            GameState::TournamentSetup => self.update_tournament_setup(),
+            GameState::TournamentBracket => self.update_tournament_bracket(),
+            GameState::VersusArenaSelect => {
+                let choices = self.versus_arenas.len() + 1;
+                if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
+                    self.versus_arena_selection =
+                        (self.versus_arena_selection + choices - 1) % choices;
+                    self.audio.play_menu_select();
+                }
+                if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
+                    self.versus_arena_selection = (self.versus_arena_selection + 1) % choices;
+                    self.audio.play_menu_select();
+                }
+                if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+                    self.audio.play_menu_select();
+                    self.versus_played = true;
+                    self.choose_versus_arena();
+                    self.load_versus_map(::rand::random());
+                    self.start_transition(GameState::Versus);
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    self.state = GameState::VersusSetup;
+                }
+            }
             GameState::Playing => {
                 let effective_dt = if self.assist_mode {
                     dt * ASSIST_MODE_SLOW_MOTION
                 } else {
                     dt
                 };
-                if is_key_pressed(KeyCode::P) {
-                    self.state = GameState::Pause;
-                    self.pause_selection = 0;
-                    self.came_from_pause = false;
+                if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) {
+                    self.open_pause();
                     return;
                 }
-                if is_key_pressed(KeyCode::Escape) {
-                    self.transition_to_menu();
-                    self.menu_selection = 0;
+                if self.can_player2_drop_in()
+                    && self.is_control_just_pressed(&self.player2_controls, ControlAction::Jump)
+                {
+                    self.drop_in_player2();
                     return;
                 }
                 if self.level_start_fade_timer > 0.0 {
@@ -610,6 +772,7 @@ impl Game {
                 for checkpoint in &mut self.checkpoints {
                     if checkpoint.check_activation(px, py, pw, ph) {
                         self.last_checkpoint_pos = Some((checkpoint.x, checkpoint.y));
+                        self.checkpoint_trigger_states = latched_states(&self.triggers);
                         self.score += SCORE_CHECKPOINT;
                         self.audio.play_coin();
                     }
@@ -619,6 +782,11 @@ impl Game {
                     &self.platforms,
                     (px, py, pw, ph),
                 );
+                Self::check_player_platform_collisions(
+                    &mut self.player,
+                    &self.gate_platforms,
+                    (px, py, pw, ph),
+                );
                 let p1_jump = self.is_control_pressed(&self.player1_controls, ControlAction::Jump);
                 let jumped = self.player.handle_jump_custom(p1_jump);
                 if jumped {
@@ -636,19 +804,23 @@ impl Game {
                 } else {
                     self.footstep_timer = 0.0;
                 }
+                let p1_action =
+                    self.is_control_just_pressed(&self.player1_controls, ControlAction::Action);
+                self.update_level_triggers(effective_dt, (p1_action, false));
                 let player_left = self.player.x;
                 let player_right = self.player.x + self.player.width;
-                if player_left < 0.0 {
-                    self.player.x = 0.0;
+                if player_left < self.world_bounds.left {
+                    self.player.x = self.world_bounds.left;
                     self.player.vel_x = 0.0;
                 }
-                if player_right > WORLD_WIDTH {
-                    self.player.x = WORLD_WIDTH - self.player.width;
+                if player_right > self.world_bounds.right {
+                    self.player.x = self.world_bounds.right - self.player.width;
                     self.player.vel_x = 0.0;
                 }
-                if self.player.y > FALL_DEATH_Y {
+                if self.player.y > self.world_bounds.fall_death_y() {
                     self.handle_player_death();
                 }
+                let health_enabled = self.health_enabled();
                 let mut player_vel_y_update = None;
                 for enemy in &mut self.enemies {
                     if !enemy.alive {
@@ -656,14 +828,25 @@ impl Game {
                     }
                     enemy.update(effective_dt);
                     Self::check_enemy_platform_collisions(enemy, &self.platforms);
+                    Self::check_enemy_platform_collisions(enemy, &self.gate_platforms);
                     if enemy.on_ground {
                         enemy.check_edge(&self.platforms);
                     }
                     enemy.check_ground_collision(GROUND_Y);
                     match enemy.check_player_collision(px, py, pw, ph, self.player.vel_y) {
                         Some(true) => {
-                            self.handle_player_death();
-                            break;
+                            if !health_enabled {
+                                self.handle_player_death();
+                                break;
+                            }
+                            if !self.player.is_invulnerable() {
+                                if self.player.take_damage(enemy.x + enemy.width / 2.0) {
+                                    self.handle_player_death();
+                                    break;
+                                }
+                                self.audio.play_hurt();
+                                self.camera_shake.trigger_kill();
+                            }
                         }
                         Some(false) => {
                             let enemy_x = enemy.x;
@@ -720,9 +903,18 @@ impl Game {
                     }
                     self.state = GameState::LevelComplete;
                 }
-                let screen_width = screen_width();
-                self.camera
-                    .update(self.player.x, screen_width, self.camera_shake.get_offset());
+                let target = (
+                    self.player.x + self.player.width / 2.0,
+                    self.player.y + self.player.height / 2.0,
+                );
+                self.camera.update(
+                    target,
+                    self.player.facing_right,
+                    (screen_width(), screen_height()),
+                    &self.world_bounds,
+                    self.camera_shake.get_offset(),
+                    dt,
+                );
             }
             GameState::LevelComplete => {
                 if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
@@ -744,236 +936,45 @@ impl Game {
                 }
             }
             GameState::Versus => {
-                if is_key_pressed(KeyCode::Escape) {
-                    self.transition_to_menu();
-                    self.menu_selection = 0;
-                    self.player2 = None;
+                if self.netplay.is_some() {
+                    self.update_net_match(dt);
                     return;
                 }
-                if self.level_start_fade_timer > 0.0 {
-                    self.level_start_fade_timer -= dt;
-                }
-                let effective_dt = if self.assist_mode {
-                    dt * ASSIST_MODE_SLOW_MOTION
-                } else {
-                    dt
-                };
-                self.versus_time_remaining -= effective_dt;
-                if self.versus_time_remaining <= 0.0 {
-                    self.versus_time_remaining = 0.0;
-                    self.audio.play_level_complete();
-                    self.state = GameState::VersusEnd;
+                if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) {
+                    self.open_pause();
                     return;
                 }
-                if self.respawn_timer_p1 > 0.0 {
-                    self.respawn_timer_p1 -= dt;
-                    if self.respawn_timer_p1 <= 0.0 {
-                        let map_width = self
-                            .versus_platforms
-                            .iter()
-                            .map(|plat| plat.x + plat.width)
-                            .fold(SCREEN_WIDTH as f32, f32::max);
-                        let center_x = map_width / 2.0;
-                        let offset = 140.0;
-                        self.player = Player::new(
-                            center_x - offset,
-                            GROUND_Y - PLAYER_HEIGHT,
-                            self.player_sprite_texture_p1
-                                .as_ref()
-                                .map(|t| std::rc::Rc::clone(t)),
-                            self.player_sprite_texture_p2
-                                .as_ref()
-                                .map(|t| std::rc::Rc::clone(t)),
-                        );
-                        self.player.on_ground = true;
-                        self.player.vel_y = 0.0;
-                    }
-                }
-                if self.respawn_timer_p2 > 0.0 {
-                    self.respawn_timer_p2 -= dt;
-                    if let Some(ref mut p2) = self.player2 {
-                        if self.respawn_timer_p2 <= 0.0 {
-                            let map_width = self
-                                .versus_platforms
-                                .iter()
-                                .map(|plat| plat.x + plat.width)
-                                .fold(SCREEN_WIDTH as f32, f32::max);
-                            let center_x = map_width / 2.0;
-                            let offset = 140.0;
-                            *p2 = Player::new(
-                                center_x + offset - PLAYER_WIDTH,
-                                GROUND_Y - PLAYER_HEIGHT,
-                                self.player_sprite_texture_p1
-                                    .as_ref()
-                                    .map(|t| std::rc::Rc::clone(t)),
-                                self.player_sprite_texture_p2
-                                    .as_ref()
-                                    .map(|t| std::rc::Rc::clone(t)),
-                            );
-                            p2.on_ground = true;
-                            p2.vel_y = 0.0;
-                        }
-                    }
-                }
-                if self.respawn_timer_p1 <= 0.0 {
-                    let p1_left =
-                        self.is_control_pressed(&self.player1_controls, ControlAction::Left);
-                    let p1_right =
-                        self.is_control_pressed(&self.player1_controls, ControlAction::Right);
-                    let p1_jump =
-                        self.is_control_pressed(&self.player1_controls, ControlAction::Jump);
-                    let use_easter_egg = self.is_easter_egg();
-                    let jumped = Self::update_versus_player_physics(
-                        &mut self.player,
-                        p1_left,
-                        p1_right,
-                        p1_jump,
-                        &self.versus_platforms,
-                        effective_dt,
-                    );
-                    if jumped {
-                        self.audio.play_jump(use_easter_egg);
-                    }
-                    if self.player.on_ground && self.player.vel_x.abs() > MIN_VELOCITY_FOR_FOOTSTEP
-                    {
-                        self.footstep_timer += effective_dt;
-                        if self.footstep_timer >= FOOTSTEP_INTERVAL {
-                            self.audio.play_footstep(use_easter_egg);
-                            self.footstep_timer = 0.0;
-                        }
-                    } else {
-                        self.footstep_timer = 0.0;
-                    }
-                }
-                let p2_controls = self.player2_controls.clone();
-                let p2_left = self.is_control_pressed(&p2_controls, ControlAction::Left);
-                let p2_right = self.is_control_pressed(&p2_controls, ControlAction::Right);
-                let p2_jump = self.is_control_pressed(&p2_controls, ControlAction::Jump);
-                if let Some(ref mut p2) = self.player2 {
-                    if self.respawn_timer_p2 <= 0.0 {
-                        let jumped = Self::update_versus_player_physics(
-                            p2,
-                            p2_left,
-                            p2_right,
-                            p2_jump,
-                            &self.versus_platforms,
-                            effective_dt,
-                        );
-                        if jumped {
-                            self.audio.play_jump(false);
-                        }
-                    } else {
-                        p2.update(effective_dt);
-                        let (px2, py2, pw2, ph2) = p2.get_rect();
-                        Self::check_player_platform_collisions(
-                            p2,
-                            &self.versus_platforms,
-                            (px2, py2, pw2, ph2),
-                        );
-                    }
-                }
-                if self.respawn_timer_p1 > 0.0 {
-                    self.player.update(effective_dt);
-                    let (px, py, pw, ph) = self.player.get_rect();
-                    Self::check_player_platform_collisions(
-                        &mut self.player,
-                        &self.versus_platforms,
-                        (px, py, pw, ph),
-                    );
-                }
-                if self.respawn_timer_p1 <= 0.0 && self.respawn_timer_p2 <= 0.0 {
-                    if let Some(ref mut p2) = self.player2 {
-                        if self.player.check_stomp(p2, self.player.vel_y) {
-                            self.player1_score += 1;
-                            self.player1_streak += 1;
-                            self.player2_streak = 0;
-                            let points = Self::calculate_versus_points(self.player1_streak);
-                            self.player1_points += points;
-                            self.audio.play_enemy_death();
-                            self.respawn_timer_p2 = 2.0;
-                            self.player.vel_y = JUMP_FORCE * JUMP_BOUNCE_MULTIPLIER;
-                        } else if p2.check_stomp(&self.player, p2.vel_y) {
-                            self.player2_score += 1;
-                            self.player2_streak += 1;
-                            self.player1_streak = 0;
-                            let points = Self::calculate_versus_points(self.player2_streak);
-                            self.player2_points += points;
-                            self.audio.play_enemy_death();
-                            self.respawn_timer_p1 = 2.0;
-                            p2.vel_y = JUMP_FORCE * 0.6;
-                        }
-                    }
-                }
-                let p1_left = self.player.x;
-                let p1_right = self.player.x + self.player.width;
-                if p1_left < 0.0 {
-                    self.player.x = 0.0;
-                    self.player.vel_x = 0.0;
-                }
-                if p1_right > WORLD_WIDTH {
-                    self.player.x = WORLD_WIDTH - self.player.width;
-                    self.player.vel_x = 0.0;
-                }
-                if let Some(ref mut p2) = self.player2 {
-                    let p2_left = p2.x;
-                    let p2_right = p2.x + p2.width;
-                    if p2_left < 0.0 {
-                        p2.x = 0.0;
-                        p2.vel_x = 0.0;
-                    }
-                    if p2_right > WORLD_WIDTH {
-                        p2.x = WORLD_WIDTH - p2.width;
-                        p2.vel_x = 0.0;
-                    }
-                }
-                if self.player.y > FALL_DEATH_Y && self.respawn_timer_p1 <= 0.0 {
-                    self.player2_score += 1;
-                    self.player2_streak += 1;
-                    self.player1_streak = 0;
-                    let points = Self::calculate_versus_points(self.player2_streak);
-                    self.player2_points += points;
-                    self.audio.play_enemy_death();
-                    self.respawn_timer_p1 = 2.0;
-                }
-                if let Some(ref p2) = self.player2 {
-                    if p2.y > FALL_DEATH_Y && self.respawn_timer_p2 <= 0.0 {
-                        self.player1_score += 1;
-                        self.player1_streak += 1;
-                        self.player2_streak = 0;
-                        let points = Self::calculate_versus_points(self.player1_streak);
-                        self.player1_points += points;
-                        self.audio.play_enemy_death();
-                        self.respawn_timer_p2 = 2.0;
-                    }
-                }
-                if let Some(ref p2) = self.player2 {
-                    let center_x = (self.player.x + p2.x) / 2.0;
-                    let screen_width = screen_width();
-                    let shake = self.camera_shake.get_offset();
-                    let map_width = self
-                        .versus_platforms
-                        .iter()
-                        .map(|plat| plat.x + plat.width)
-                        .fold(screen_width, f32::max);
-                    let target = center_x - screen_width / 2.0 + shake;
-                    self.camera.x = target.clamp(0.0, (map_width - screen_width).max(0.0));
-                } else {
-                    let screen_width = screen_width();
-                    let shake = self.camera_shake.get_offset();
-                    let map_width = self
-                        .versus_platforms
-                        .iter()
-                        .map(|plat| plat.x + plat.width)
-                        .fold(screen_width, f32::max);
-                    let target = self.player.x - screen_width / 2.0 + shake;
-                    self.camera.x = target.clamp(0.0, (map_width - screen_width).max(0.0));
+                let inputs: Vec<VersusInput> = self
+                    .versus_fighters
+                    .iter()
+                    .map(|fighter| self.read_versus_input(&fighter.controls))
+                    .collect();
+                self.step_versus(dt, &inputs);
+                // Straight away, so closing the window on the results
+                // screen doesn't lose the match.
+                if self.state == GameState::VersusEnd {
+                    self.record_versus_match(0);
                 }
             }
             GameState::VersusEnd => {
+                if self.netplay.is_some() {
+                    self.update_net_match(dt);
+                    return;
+                }
+                if is_key_pressed(KeyCode::R) && !self.tournament_playing {
+                    self.audio.play_menu_select();
+                    self.load_versus_map(::rand::random());
+                    self.state = GameState::Versus;
+                    return;
+                }
                 if is_key_pressed(KeyCode::Enter)
                     || is_key_pressed(KeyCode::Space)
                     || is_key_pressed(KeyCode::Escape)
                 {
+                    if self.tournament_playing {
+                        self.finish_tournament_match();
+                        return;
+                    }
                     self.state = GameState::Menu;
                     self.menu_selection = 0;
                     self.player2 = None;
@@ -985,17 +986,18 @@ impl Game {
                 } else {
                     dt
                 };
-                if is_key_pressed(KeyCode::P) {
-                    self.state = GameState::Pause;
-                    self.pause_selection = 0;
-                    self.came_from_pause = false;
+                if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) {
+                    self.open_pause();
                     return;
                 }
-                if is_key_pressed(KeyCode::Escape) {
-                    self.transition_to_menu();
-                    self.menu_selection = 0;
-                    self.player2 = None;
-                    return;
+                if self.is_control_pressed(&self.player2_controls, ControlAction::Action) {
+                    self.p2_leave_timer += dt;
+                    if self.p2_leave_timer >= COOP_DROP_OUT_HOLD && self.coop_player_active(true) {
+                        self.drop_out_player2();
+                        return;
+                    }
+                } else {
+                    self.p2_leave_timer = 0.0;
                 }
                 if self.level_start_fade_timer > 0.0 {
                     self.level_start_fade_timer -= dt;
@@ -1069,7 +1071,15 @@ impl Game {
                         }
                     }
                 }
-                if self.respawn_timer_p1 <= 0.0 {
+                self.update_revive_bubbles(effective_dt);
+                if !matches!(self.state, GameState::Coop) {
+                    return;
+                }
+                let stacking = self.coop_rules.partner_physics
+                    && self.coop_carrying.is_none()
+                    && self.coop_player_active(true)
+                    && self.coop_player_active(false);
+                if self.coop_player_active(true) && self.coop_carrying != Some(false) {
                     let p1_left =
                         self.is_control_pressed(&self.player1_controls, ControlAction::Left);
                     let p1_right =
@@ -1080,7 +1090,9 @@ impl Game {
                     for checkpoint in &mut self.checkpoints {
                         if checkpoint.check_activation(px, py, pw, ph) {
                             self.last_checkpoint_pos = Some((checkpoint.x, checkpoint.y));
+                            self.checkpoint_trigger_states = latched_states(&self.triggers);
                             self.score += SCORE_CHECKPOINT;
+                            self.coop_score_p1 += SCORE_CHECKPOINT;
                             self.audio.play_coin();
                         }
                     }
@@ -1089,6 +1101,16 @@ impl Game {
                         &self.platforms,
                         (px, py, pw, ph),
                     );
+                    Self::check_player_platform_collisions(
+                        &mut self.player,
+                        &self.gate_platforms,
+                        (px, py, pw, ph),
+                    );
+                    if stacking {
+                        if let Some(ref p2) = self.player2 {
+                            self.player.land_on_partner(p2, effective_dt);
+                        }
+                    }
                     let p1_jump =
                         self.is_control_pressed(&self.player1_controls, ControlAction::Jump);
                     let jumped = self.player.handle_jump_custom(p1_jump);
@@ -1111,15 +1133,19 @@ impl Game {
                 let p2_left = self.is_control_pressed(&p2_controls, ControlAction::Left);
                 let p2_right = self.is_control_pressed(&p2_controls, ControlAction::Right);
                 let p2_jump = self.is_control_pressed(&p2_controls, ControlAction::Jump);
+                let p2_active = self.coop_player_active(false);
+                let p2_held = self.coop_carrying == Some(true);
                 if let Some(ref mut p2) = self.player2 {
-                    if self.respawn_timer_p2 <= 0.0 {
+                    if p2_active && !p2_held {
                         p2.handle_movement_custom(p2_left, p2_right);
                         p2.update(effective_dt);
                         let (px2, py2, pw2, ph2) = p2.get_rect();
                         for checkpoint in &mut self.checkpoints {
                             if checkpoint.check_activation(px2, py2, pw2, ph2) {
                                 self.last_checkpoint_pos = Some((checkpoint.x, checkpoint.y));
+                                self.checkpoint_trigger_states = latched_states(&self.triggers);
                                 self.score += SCORE_CHECKPOINT;
+                                self.coop_score_p2 += SCORE_CHECKPOINT;
                                 self.audio.play_coin();
                             }
                         }
@@ -1128,12 +1154,20 @@ impl Game {
                             &self.platforms,
                             (px2, py2, pw2, ph2),
                         );
+                        Self::check_player_platform_collisions(
+                            p2,
+                            &self.gate_platforms,
+                            (px2, py2, pw2, ph2),
+                        );
+                        if stacking {
+                            p2.land_on_partner(&self.player, effective_dt);
+                        }
                         let jumped = p2.handle_jump_custom(p2_jump);
                         if jumped {
                             self.audio.play_jump(false);
                         }
                         p2.update_animation(effective_dt);
-                    } else {
+                    } else if self.respawn_timer_p2 > 0.0 {
                         p2.update(effective_dt);
                         let (px2, py2, pw2, ph2) = p2.get_rect();
                         Self::check_player_platform_collisions(
@@ -1143,6 +1177,20 @@ impl Game {
                         );
                     }
                 }
+                let action_tapped = (
+                    self.is_control_just_pressed(&self.player1_controls, ControlAction::Action),
+                    self.is_control_just_pressed(&p2_controls, ControlAction::Action),
+                );
+                let jump_tapped = (
+                    self.is_control_just_pressed(&self.player1_controls, ControlAction::Jump),
+                    self.is_control_just_pressed(&p2_controls, ControlAction::Jump),
+                );
+                let used_on_trigger = self.update_level_triggers(effective_dt, action_tapped);
+                let action_tapped = (
+                    action_tapped.0 && !used_on_trigger.0,
+                    action_tapped.1 && !used_on_trigger.1,
+                );
+                self.update_partner_carry(action_tapped, jump_tapped);
                 if self.respawn_timer_p1 > 0.0 {
                     self.player.update(effective_dt);
                     let (px, py, pw, ph) = self.player.get_rect();
@@ -1154,52 +1202,43 @@ impl Game {
                 }
                 let player_left = self.player.x;
                 let player_right = self.player.x + self.player.width;
-                if player_left < 0.0 {
-                    self.player.x = 0.0;
+                let bounds = self.world_bounds;
+                if player_left < bounds.left {
+                    self.player.x = bounds.left;
                     self.player.vel_x = 0.0;
                 }
-                if player_right > WORLD_WIDTH {
-                    self.player.x = WORLD_WIDTH - self.player.width;
+                if player_right > bounds.right {
+                    self.player.x = bounds.right - self.player.width;
                     self.player.vel_x = 0.0;
                 }
                 if let Some(ref mut p2) = self.player2 {
                     let p2_left = p2.x;
                     let p2_right = p2.x + p2.width;
-                    if p2_left < 0.0 {
-                        p2.x = 0.0;
+                    if p2_left < bounds.left {
+                        p2.x = bounds.left;
                         p2.vel_x = 0.0;
                     }
-                    if p2_right > WORLD_WIDTH {
-                        p2.x = WORLD_WIDTH - p2.width;
+                    if p2_right > bounds.right {
+                        p2.x = bounds.right - p2.width;
                         p2.vel_x = 0.0;
                     }
                 }
-                if self.player.y > FALL_DEATH_Y && self.respawn_timer_p1 <= 0.0 {
-                    self.audio.play_death();
-                    if self.lives > 0 {
-                        self.lives -= 1;
-                    }
-                    if self.lives == 0 {
-                        self.game_over_fade_timer = GAME_OVER_FADE_TIMER;
-                        self.state = GameState::GameOver;
-                    } else {
-                        self.respawn_timer_p1 = RESPAWN_TIMER;
-                    }
+                if self.player.y > bounds.fall_death_y() && self.coop_player_active(true) {
+                    self.handle_coop_player_death(true);
                 }
-                if let Some(ref p2) = self.player2 {
-                    if p2.y > FALL_DEATH_Y && self.respawn_timer_p2 <= 0.0 {
-                        self.audio.play_death();
-                        if self.lives > 0 {
-                            self.lives -= 1;
-                        }
-                        if self.lives == 0 {
-                            self.game_over_fade_timer = GAME_OVER_FADE_TIMER;
-                            self.state = GameState::GameOver;
-                        } else {
-                            self.respawn_timer_p2 = RESPAWN_TIMER;
-                        }
-                    }
+                let p2_fell = self
+                    .player2
+                    .as_ref()
+                    .is_some_and(|p2| p2.y > bounds.fall_death_y());
+                if p2_fell && self.coop_player_active(false) {
+                    self.handle_coop_player_death(false);
                 }
+                if !matches!(self.state, GameState::Coop) {
+                    return;
+                }
+                let health_enabled = self.health_enabled();
+                let p1_active = self.coop_player_active(true);
+                let p2_active = self.coop_player_active(false);
                 let mut player_vel_y_update = None;
                 let mut player2_vel_y_update = None;
                 for enemy in &mut self.enemies {
@@ -1208,15 +1247,31 @@ impl Game {
                     }
                     enemy.update(effective_dt);
                     Self::check_enemy_platform_collisions(enemy, &self.platforms);
+                    Self::check_enemy_platform_collisions(enemy, &self.gate_platforms);
                     if enemy.on_ground {
                         enemy.check_edge(&self.platforms);
                     }
                     enemy.check_ground_collision(GROUND_Y);
                     let (px, py, pw, ph) = self.player.get_rect();
-                    match enemy.check_player_collision(px, py, pw, ph, self.player.vel_y) {
+                    let p1_hit = if p1_active {
+                        enemy.check_player_collision(px, py, pw, ph, self.player.vel_y)
+                    } else {
+                        None
+                    };
+                    match p1_hit {
                         Some(true) => {
-                            self.handle_player_death_versus_p1();
-                            break;
+                            if !health_enabled {
+                                self.handle_coop_player_death(true);
+                                break;
+                            }
+                            if !self.player.is_invulnerable() {
+                                if self.player.take_damage(enemy.x + enemy.width / 2.0) {
+                                    self.handle_coop_player_death(true);
+                                    break;
+                                }
+                                self.audio.play_hurt();
+                                self.camera_shake.trigger_kill();
+                            }
                         }
                         Some(false) => {
                             let enemy_x = enemy.x;
@@ -1226,6 +1281,7 @@ impl Game {
                             player_vel_y_update = Some(JUMP_FORCE * JUMP_BOUNCE_MULTIPLIER);
                             self.audio.play_enemy_death();
                             self.score += SCORE_ENEMY;
+                            self.coop_score_p1 += SCORE_ENEMY;
                             self.camera_shake.trigger_kill();
                             for _ in 0..PARTICLE_COUNT {
                                 let angle = rand::gen_range(0.0, std::f32::consts::PI * 2.0);
@@ -1240,12 +1296,25 @@ impl Game {
                         }
                         None => {}
                     }
-                    if let Some(ref p2) = self.player2 {
+                    if !enemy.alive || !p2_active {
+                        continue;
+                    }
+                    if let Some(ref mut p2) = self.player2 {
                         let (px2, py2, pw2, ph2) = p2.get_rect();
                         match enemy.check_player_collision(px2, py2, pw2, ph2, p2.vel_y) {
                             Some(true) => {
-                                self.handle_player_death_versus_p2();
-                                break;
+                                if !health_enabled {
+                                    self.handle_coop_player_death(false);
+                                    break;
+                                }
+                                if !p2.is_invulnerable() {
+                                    if p2.take_damage(enemy.x + enemy.width / 2.0) {
+                                        self.handle_coop_player_death(false);
+                                        break;
+                                    }
+                                    self.audio.play_hurt();
+                                    self.camera_shake.trigger_kill();
+                                }
                             }
                             Some(false) => {
                                 let enemy_x = enemy.x;
@@ -1255,6 +1324,7 @@ impl Game {
                                 player2_vel_y_update = Some(JUMP_FORCE * JUMP_BOUNCE_MULTIPLIER);
                                 self.audio.play_enemy_death();
                                 self.score += SCORE_ENEMY;
+                                self.coop_score_p2 += SCORE_ENEMY;
                                 self.camera_shake.trigger_kill();
                                 for _ in 0..PARTICLE_COUNT {
                                     let angle = rand::gen_range(0.0, std::f32::consts::PI * 2.0);
@@ -1286,28 +1356,36 @@ impl Game {
                     }
                     coin.update(effective_dt);
                     let (px, py, pw, ph) = self.player.get_rect();
-                    if coin.check_collection(px, py, pw, ph) {
-                        coins_to_collect.push((coin.x, coin.y));
+                    if p1_active && coin.check_collection(px, py, pw, ph) {
+                        coins_to_collect.push((coin.x, coin.y, true));
+                        continue;
                     }
                     if let Some(ref p2) = self.player2 {
                         let (px2, py2, pw2, ph2) = p2.get_rect();
-                        if coin.check_collection(px2, py2, pw2, ph2) {
-                            coins_to_collect.push((coin.x, coin.y));
+                        if p2_active && coin.check_collection(px2, py2, pw2, ph2) {
+                            coins_to_collect.push((coin.x, coin.y, false));
                         }
                     }
                 }
-                for (coin_x, coin_y) in coins_to_collect {
+                for (coin_x, coin_y, by_p1) in coins_to_collect {
                     self.handle_coin_collection(coin_x, coin_y);
-                }
-                if (self.player.x > LEVEL_COMPLETE_X || self.coins_collected >= self.total_coins)
-                    && (self.player2.is_none()
-                        || self
-                            .player2
-                            .as_ref()
-                            .map(|p| {
-                                p.x > LEVEL_COMPLETE_X || self.coins_collected >= self.total_coins
-                            })
-                            .unwrap_or(true))
+                    if by_p1 {
+                        self.coop_coins_p1 += 1;
+                        self.coop_score_p1 += SCORE_COIN;
+                    } else {
+                        self.coop_coins_p2 += 1;
+                        self.coop_score_p2 += SCORE_COIN;
+                    }
+                }
+                // Everyone still standing has to reach the goal; downed partners come along.
+                let p1_done = !p1_active || self.player.x > LEVEL_COMPLETE_X;
+                let p2_done = !p2_active
+                    || self
+                        .player2
+                        .as_ref()
+                        .is_some_and(|p2| p2.x > LEVEL_COMPLETE_X);
+                if (p1_active || p2_active)
+                    && ((p1_done && p2_done) || self.coins_collected >= self.total_coins)
                 {
                     let time_bonus = (self.time_remaining * SCORE_TIME_BONUS) as u32;
                     self.score += SCORE_LEVEL_COMPLETE + time_bonus;
@@ -1324,19 +1402,28 @@ impl Game {
                     }
                     self.state = GameState::LevelComplete;
                 }
-                if let Some(ref p2) = self.player2 {
-                    let center_x = (self.player.x + p2.x) / 2.0;
-                    let screen_w = screen_width();
-                    let shake = self.camera_shake.get_offset();
-                    self.camera.update(center_x, screen_w, shake);
-                } else {
-                    let player_x = self.player.x;
-                    let screen_w = screen_width();
-                    let shake = self.camera_shake.get_offset();
-                    self.camera.update(player_x, screen_w, shake);
-                }
+                self.update_coop_camera(dt);
             }
             GameState::Pause => {
+                if let Some(option) = self.pause_confirm {
+                    if is_key_pressed(KeyCode::Enter)
+                        || is_key_pressed(KeyCode::Space)
+                        || is_key_pressed(KeyCode::Y)
+                    {
+                        self.audio.play_menu_select();
+                        self.pause_confirm = None;
+                        self.apply_pause_option(option);
+                    } else if is_key_pressed(KeyCode::Escape)
+                        || is_key_pressed(KeyCode::Backspace)
+                        || is_key_pressed(KeyCode::N)
+                    {
+                        self.audio.play_menu_select();
+                        self.pause_confirm = None;
+                    }
+                    return;
+                }
+                let options = self.pause_options();
+                self.pause_selection = self.pause_selection.min(options.len() - 1);
                 if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
                     if self.pause_selection > 0 {
                         self.pause_selection -= 1;
@@ -1345,7 +1432,7 @@ impl Game {
                     }
                 }
                 if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
-                    if self.pause_selection < 3 {
+                    if self.pause_selection < options.len() - 1 {
                         self.pause_selection += 1;
                         self.pause_animation.trigger(self.pause_selection);
                         self.audio.play_menu_select();
@@ -1353,28 +1440,16 @@ impl Game {
                 }
                 if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
                     self.audio.play_menu_select();
-                    match self.pause_selection {
-                        0 => {
-                            self.state = GameState::Playing;
-                        }
-                        1 => {
-                            self.came_from_pause = true;
-                            self.state = GameState::Settings;
-                            self.settings_selection = 0;
-                        }
-                        2 => {
-                            self.came_from_pause = true;
-                            self.state = GameState::Credits;
-                        }
-                        3 => {
-                            self.state = GameState::Menu;
-                            self.menu_selection = 0;
-                        }
-                        _ => {}
+                    let option = options[self.pause_selection];
+                    if option.needs_confirm() {
+                        self.pause_confirm = Some(option);
+                    } else {
+                        self.apply_pause_option(option);
                     }
+                    return;
                 }
                 if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) {
-                    self.state = GameState::Playing;
+                    self.state = self.pause_origin;
                 }
             }
             GameState::Respawn => {
@@ -1397,13 +1472,14 @@ impl Game {
                 if self.game_over_fade_timer <= 0.0 {
                     if is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) {
                         self.lives = DEFAULT_LIVES;
-                        self.load_level(
-                            self.current_level,
-                            self.last_checkpoint_pos.is_some(),
-                            None,
-                            None,
-                        );
-                        self.state = GameState::Playing;
+                        let use_checkpoint = self.last_checkpoint_pos.is_some();
+                        if self.player2.is_some() {
+                            self.start_coop_level(self.current_level, use_checkpoint, None, None);
+                            self.state = GameState::Coop;
+                        } else {
+                            self.load_level(self.current_level, use_checkpoint, None, None);
+                            self.state = GameState::Playing;
+                        }
                     }
                     if is_key_pressed(KeyCode::Escape) {
                         self.state = GameState::LevelSelect;
@@ -1413,4 +1489,188 @@ impl Game {
             }
         }
     }
+
+    /// One tick of a Versus match. `inputs` holds each human fighter's
+    /// buttons by seat; CPU seats pick their own. Everything random comes
+    /// from `versus_rng`, so the same inputs always play out the same way,
+    /// which online play relies on.
+    pub fn step_versus(&mut self, dt: f32, inputs: &[VersusInput]) {
+        if self.level_start_fade_timer > 0.0 {
+            self.level_start_fade_timer -= dt;
+        }
+        if self.versus_round_intro > 0.0 {
+            self.versus_round_intro -= dt;
+            return;
+        }
+        // Slow motion would desync an online match, so assist mode only
+        // applies offline.
+        let effective_dt = if self.assist_mode && self.netplay.is_none() {
+            dt * ASSIST_MODE_SLOW_MOTION
+        } else {
+            dt
+        };
+        if self.versus_rules.time_limit.is_some() {
+            self.versus_time_remaining -= effective_dt;
+            if self.versus_time_remaining <= 0.0 {
+                self.versus_time_remaining = 0.0;
+                let winner = self.versus_time_up_winner();
+                self.end_versus_round(winner);
+                return;
+            }
+        }
+        for index in 0..self.versus_fighters.len() {
+            let fighter = &mut self.versus_fighters[index];
+            if fighter.out || fighter.respawn_timer <= 0.0 {
+                continue;
+            }
+            fighter.respawn_timer -= dt;
+            if fighter.respawn_timer <= 0.0 {
+                self.spawn_versus_fighter(index);
+            }
+        }
+        for fighter in &mut self.versus_fighters {
+            fighter.speed_timer = (fighter.speed_timer - effective_dt).max(0.0);
+            fighter.frozen_timer = (fighter.frozen_timer - effective_dt).max(0.0);
+            fighter.apply_handicap(&self.versus_rules.handicaps[fighter.slot]);
+        }
+        let mut fighter_inputs = Vec::with_capacity(self.versus_fighters.len());
+        for index in 0..self.versus_fighters.len() {
+            let input = match self.versus_fighters[index].cpu.take() {
+                Some(mut ai) => {
+                    let foe = self.nearest_versus_foe(index);
+                    let input = ai.update(
+                        &self.versus_fighters[index].player,
+                        foe.map(|foe| &self.versus_fighters[foe].player),
+                        &self.versus_platforms,
+                        &self.versus_hazards,
+                        &mut self.versus_rng,
+                        effective_dt,
+                    );
+                    let fighter = &mut self.versus_fighters[index];
+                    fighter.cpu = Some(ai);
+                    // The CPU uses an item as soon as it has one.
+                    let use_item = fighter.item.is_some() && foe.is_some();
+                    (input.left, input.right, input.jump, use_item)
+                }
+                None => {
+                    let input = inputs.get(index).copied().unwrap_or_default();
+                    (input.left, input.right, input.jump, input.item)
+                }
+            };
+            fighter_inputs.push(input);
+        }
+        let mut item_users = Vec::new();
+        let use_easter_egg = self.is_easter_egg();
+        for (index, (fighter, (left, right, jump, use_item))) in self
+            .versus_fighters
+            .iter_mut()
+            .zip(fighter_inputs)
+            .enumerate()
+        {
+            if fighter.out {
+                continue;
+            }
+            // A frozen fighter can't move or use items until it thaws.
+            let (left, right, jump) = if fighter.frozen_timer > 0.0 {
+                (false, false, false)
+            } else {
+                (left, right, jump)
+            };
+            if use_item && fighter.frozen_timer <= 0.0 && fighter.in_play() {
+                item_users.push(index);
+            }
+            let player = &mut fighter.player;
+            if fighter.respawn_timer > 0.0 {
+                player.update(effective_dt);
+                let rect = player.get_rect();
+                Self::check_player_platform_collisions(player, &self.versus_platforms, rect);
+                continue;
+            }
+            let jumped = Self::update_versus_player_physics(
+                player,
+                left,
+                right,
+                jump,
+                &self.versus_platforms,
+                effective_dt,
+            );
+            let is_p1 = fighter.slot == 0;
+            if jumped {
+                self.audio.play_jump(is_p1 && use_easter_egg);
+            }
+            if is_p1 {
+                if player.on_ground && player.vel_x.abs() > MIN_VELOCITY_FOR_FOOTSTEP {
+                    self.footstep_timer += effective_dt;
+                    if self.footstep_timer >= FOOTSTEP_INTERVAL {
+                        self.audio.play_footstep(use_easter_egg);
+                        self.footstep_timer = 0.0;
+                    }
+                } else {
+                    self.footstep_timer = 0.0;
+                }
+            }
+        }
+        if self.versus_rules.items {
+            for index in item_users {
+                self.use_versus_item(index);
+            }
+        }
+        if let Some((stomper, victim)) = self.find_versus_stomp() {
+            self.versus_fighters[stomper].player.vel_y = JUMP_FORCE * JUMP_BOUNCE_MULTIPLIER;
+            if self.hit_versus_fighter(victim, Some(stomper)) {
+                return;
+            }
+        }
+        let bounds = self.versus_bounds;
+        for fighter in &mut self.versus_fighters {
+            Self::keep_in_versus_arena(&mut fighter.player, &bounds, self.versus_wrap);
+        }
+        for index in 0..self.versus_fighters.len() {
+            let fighter = &self.versus_fighters[index];
+            let knocked_out = fighter.in_play()
+                && (fighter.player.y > bounds.fall_death_y()
+                    || self.touches_versus_hazard(fighter.player.get_rect()));
+            if knocked_out {
+                let scorer = self.versus_fall_credit(index);
+                self.release_versus_objective(index, false);
+                if self.award_versus_ko(index, scorer) {
+                    return;
+                }
+            }
+        }
+        if self.update_versus_items(effective_dt) {
+            return;
+        }
+        if self.update_versus_objectives(effective_dt) {
+            return;
+        }
+        // Frame everyone still on the field; fall back to all
+        // fighters while they're all waiting to respawn.
+        let any_in_play = self.versus_fighters.iter().any(|f| f.in_play());
+        let (min_x, max_x, min_y, max_y) = self
+            .versus_fighters
+            .iter()
+            .filter(|f| f.in_play() || !any_in_play)
+            .map(|f| &f.player)
+            .fold(
+                (f32::MAX, f32::MIN, f32::MAX, f32::MIN),
+                |(min_x, max_x, min_y, max_y), p| {
+                    (
+                        min_x.min(p.x),
+                        max_x.max(p.x),
+                        min_y.min(p.y),
+                        max_y.max(p.y),
+                    )
+                },
+            );
+        let (center_x, center_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let (view_w, view_h) = (screen_width(), screen_height());
+        let shake = self.camera_shake.get_offset();
+        let target_x = center_x - view_w / 2.0 + shake;
+        let target_y = center_y + PLAYER_HEIGHT / 2.0 - view_h / 2.0;
+        let max_x = (bounds.right - view_w).max(bounds.left);
+        let max_y = (bounds.bottom - view_h).max(bounds.top);
+        self.camera.x = target_x.clamp(bounds.left, max_x);
+        self.camera.y = target_y.clamp(bounds.top, max_y);
+    }
 }
@@ -0,0 +1,429 @@
+use std::collections::VecDeque;
+
+use super::versus_layout::Hazard;
+use crate::constants::*;
+use crate::platform::Platform;
+use crate::player::Player;
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Clearance kept between the jump apex and a platform the AI tries to reach.
+const RISE_MARGIN: f32 = 12.0;
+/// Horizontal distance to the foe at which a jump comes down on their head.
+const ATTACK_RANGE: f32 = 200.0;
+/// How close the foe has to be overhead before it counts as an incoming stomp.
+const THREAT_RANGE: f32 = 120.0;
+/// Close enough to a steering target to stop pressing towards it.
+const STEER_DEADZONE: f32 = 6.0;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AiDifficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl AiDifficulty {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AiDifficulty::Easy => "EASY",
+            AiDifficulty::Medium => "MEDIUM",
+            AiDifficulty::Hard => "HARD",
+        }
+    }
+
+    /// Seconds between fresh looks at the field.
+    fn reaction_time(&self) -> f32 {
+        match self {
+            AiDifficulty::Easy => 0.45,
+            AiDifficulty::Medium => 0.2,
+            AiDifficulty::Hard => 0.05,
+        }
+    }
+
+    /// How far off the ideal take-off point a jump may start.
+    fn sloppiness(&self) -> f32 {
+        match self {
+            AiDifficulty::Easy => 36.0,
+            AiDifficulty::Medium => 14.0,
+            AiDifficulty::Hard => 0.0,
+        }
+    }
+
+    /// Chance of noticing an incoming stomp in time to get out of the way.
+    fn dodge_chance(&self) -> f32 {
+        match self {
+            AiDifficulty::Easy => 0.25,
+            AiDifficulty::Medium => 0.6,
+            AiDifficulty::Hard => 0.95,
+        }
+    }
+}
+
+/// The same three buttons a human player has.
+#[derive(Clone, Copy, Default)]
+pub struct AiInput {
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+}
+
+/// One step of a route: where to stand on the current platform, and whether
+/// to jump from there, to end up on platform `target`.
+#[derive(Clone, Copy)]
+struct Hop {
+    target: usize,
+    takeoff_x: f32,
+    jump: bool,
+}
+
+/// CPU opponent. It only produces key presses, so it moves under exactly the
+/// physics a human player does.
+#[derive(Clone)]
+pub struct VersusAi {
+    difficulty: AiDifficulty,
+    think_timer: f32,
+    hop: Option<Hop>,
+    dodging: bool,
+    aim_error: f32,
+}
+
+impl VersusAi {
+    pub fn new(difficulty: AiDifficulty) -> Self {
+        Self {
+            difficulty,
+            think_timer: 0.0,
+            hop: None,
+            dodging: false,
+            aim_error: 0.0,
+        }
+    }
+
+    /// Picks this frame's input. `foe` is `None` while no opponent is on
+    /// the field.
+    pub fn update(
+        &mut self,
+        me: &Player,
+        foe: Option<&Player>,
+        platforms: &[Platform],
+        hazards: &[Hazard],
+        rng: &mut StdRng,
+        dt: f32,
+    ) -> AiInput {
+        let Some(foe) = foe else {
+            return AiInput::default();
+        };
+        self.think_timer -= dt;
+        if self.think_timer <= 0.0 {
+            self.think_timer = self.difficulty.reaction_time();
+            self.think(me, foe, platforms, rng);
+        }
+        let mut input = self.act(me, foe, platforms);
+        if me.on_ground && !input.jump {
+            let step = if input.right {
+                24.0
+            } else if input.left {
+                -24.0
+            } else {
+                0.0
+            };
+            let ahead = (me.x + step, me.y, me.width, me.height);
+            if step != 0.0 && hazards.iter().any(|h| h.overlaps(ahead)) {
+                input.jump = true;
+            }
+        }
+        input
+    }
+
+    /// Re-plans the route. Only runs every `reaction_time`, which is most of
+    /// what separates the difficulties.
+    fn think(&mut self, me: &Player, foe: &Player, platforms: &[Platform], rng: &mut StdRng) {
+        self.dodging =
+            is_stomp_threat(me, foe) && rng.gen_range(0.0..1.0) < self.difficulty.dodge_chance();
+        let sloppiness = self.difficulty.sloppiness();
+        self.aim_error = if sloppiness > 0.0 {
+            rng.gen_range(-sloppiness..sloppiness)
+        } else {
+            0.0
+        };
+        // Mid-air there is nothing to decide until the landing.
+        let Some(mine) = standing_on(me, platforms) else {
+            return;
+        };
+        self.hop = match platform_under(foe, platforms) {
+            Some(goal) if goal != mine => route(me, platforms, mine, goal),
+            _ => None,
+        };
+    }
+
+    fn act(&self, me: &Player, foe: &Player, platforms: &[Platform]) -> AiInput {
+        let mut input = AiInput::default();
+        let my_center = me.x + me.width / 2.0;
+        let foe_center = foe.x + foe.width / 2.0;
+        if self.dodging {
+            if foe_center > my_center {
+                input.left = true;
+            } else {
+                input.right = true;
+            }
+            return input;
+        }
+        let above_foe = me.y + me.height <= foe.y + 10.0;
+        if !me.on_ground {
+            let target_x = match self.hop {
+                _ if above_foe && (foe_center - my_center).abs() < ATTACK_RANGE => foe_center,
+                Some(hop) => platforms[hop.target].x + platforms[hop.target].width / 2.0,
+                None => foe_center,
+            };
+            steer(&mut input, my_center, target_x);
+            return input;
+        }
+        match self.hop {
+            Some(hop) => {
+                let takeoff_x = hop.takeoff_x + self.aim_error;
+                if (takeoff_x - my_center).abs() <= STEER_DEADZONE {
+                    let target = &platforms[hop.target];
+                    steer(&mut input, my_center, target.x + target.width / 2.0);
+                    input.jump = hop.jump;
+                } else {
+                    steer(&mut input, my_center, takeoff_x);
+                }
+            }
+            None => {
+                // Same footing as the foe: close in and jump so the fall
+                // comes down on top of them.
+                let dx = (foe_center - my_center).abs();
+                steer(&mut input, my_center, foe_center);
+                let level = (foe.y + foe.height - (me.y + me.height)).abs() < 40.0;
+                input.jump = level && dx < ATTACK_RANGE + self.aim_error && dx > me.width / 2.0;
+            }
+        }
+        input
+    }
+}
+
+fn steer(input: &mut AiInput, from_x: f32, to_x: f32) {
+    if to_x > from_x + STEER_DEADZONE {
+        input.right = true;
+    } else if to_x < from_x - STEER_DEADZONE {
+        input.left = true;
+    }
+}
+
+/// The foe is overhead, close, and either falling or about to.
+fn is_stomp_threat(me: &Player, foe: &Player) -> bool {
+    let dx = (foe.x + foe.width / 2.0) - (me.x + me.width / 2.0);
+    foe.y + foe.height < me.y + 20.0
+        && dx.abs() < THREAT_RANGE
+        && (foe.vel_y > 0.0 || !foe.on_ground)
+}
+
+fn spans(player: &Player, platform: &Platform) -> bool {
+    player.x + player.width > platform.x && player.x < platform.x + platform.width
+}
+
+fn standing_on(player: &Player, platforms: &[Platform]) -> Option<usize> {
+    if !player.on_ground {
+        return None;
+    }
+    let feet = player.y + player.height;
+    platforms
+        .iter()
+        .position(|p| spans(player, p) && (feet - p.y).abs() < 5.0)
+}
+
+/// The nearest platform below the player's feet, whether they're standing on
+/// it or in the air above it.
+fn platform_under(player: &Player, platforms: &[Platform]) -> Option<usize> {
+    let feet = player.y + player.height;
+    platforms
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| spans(player, p) && p.y >= feet - 5.0)
+        .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y))
+        .map(|(i, _)| i)
+}
+
+/// Times at which a jump from standing reaches `rise` pixels higher on the
+/// way up and on the way down, or `None` if the arc never gets that high.
+fn jump_times(me: &Player, rise: f32) -> Option<(f32, f32)> {
+    let speed = -JUMP_FORCE * me.jump_multiplier;
+    let disc = speed * speed - 2.0 * GRAVITY * rise;
+    if disc < 0.0 {
+        return None;
+    }
+    let root = disc.sqrt();
+    Some(((speed - root) / GRAVITY, (speed + root) / GRAVITY))
+}
+
+/// How `me` gets from platform `a` onto `b` with a single jump or drop, if
+/// that is possible at all.
+fn hop_between(me: &Player, a: &Platform, b: &Platform, target: usize) -> Option<Hop> {
+    let half = PLAYER_WIDTH / 2.0;
+    let speed = PLAYER_SPEED * me.speed_multiplier;
+    let rise = a.y - b.y;
+    let (t_clear, t_land) = jump_times(me, rise.max(0.0) + RISE_MARGIN)?;
+    let a_left = a.x + half.min(a.width / 2.0);
+    let a_right = a.x + a.width - half.min(a.width / 2.0);
+    if rise > 0.0 {
+        // Jump from beside `b`, far enough out to clear its edge on the way up.
+        let min_gap = speed * t_clear;
+        let max_gap = speed * t_land - half;
+        let from_left = (b.x - min_gap - half).clamp(a_left, a_right);
+        let from_right = (b.x + b.width + min_gap + half).clamp(a_left, a_right);
+        let left_gap = b.x - (from_left + half);
+        let right_gap = (from_right - half) - (b.x + b.width);
+        // An unclamped takeoff sits exactly `min_gap` out, give or take
+        // rounding, so allow a pixel of slack.
+        let fits = (min_gap - 1.0)..=max_gap;
+        let left_ok = fits.contains(&left_gap);
+        let right_ok = fits.contains(&right_gap);
+        let takeoff_x = match (left_ok, right_ok) {
+            (true, true) if a.x + a.width / 2.0 > b.x + b.width / 2.0 => from_right,
+            (true, _) => from_left,
+            (false, true) => from_right,
+            (false, false) => return None,
+        };
+        return Some(Hop {
+            target,
+            takeoff_x,
+            jump: true,
+        });
+    }
+    // Level or lower: leave `a` over whichever edge `b` sticks out past,
+    // jumping if there's a gap to cover.
+    let reach = speed * t_land;
+    let right_gap = b.x - (a.x + a.width);
+    let left_gap = a.x - (b.x + b.width);
+    let (takeoff_x, gap) = if b.x + b.width > a.x + a.width && right_gap <= reach {
+        (a_right, right_gap)
+    } else if b.x < a.x && left_gap <= reach {
+        (a_left, left_gap)
+    } else {
+        return None;
+    };
+    Some(Hop {
+        target,
+        takeoff_x,
+        jump: gap > 0.0,
+    })
+}
+
+/// First hop of `me`'s shortest route from platform `from` to `to`.
+fn route(me: &Player, platforms: &[Platform], from: usize, to: usize) -> Option<Hop> {
+    let mut first_hop: Vec<Option<Hop>> = vec![None; platforms.len()];
+    let mut visited = vec![false; platforms.len()];
+    let mut queue = VecDeque::from([from]);
+    visited[from] = true;
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            return first_hop[current];
+        }
+        for next in 0..platforms.len() {
+            if visited[next] {
+                continue;
+            }
+            if let Some(hop) = hop_between(me, &platforms[current], &platforms[next], next) {
+                visited[next] = true;
+                first_hop[next] = if current == from {
+                    Some(hop)
+                } else {
+                    first_hop[current]
+                };
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A player standing on `platform`, centred on `center_x`.
+    fn standing(platform: &Platform, center_x: f32) -> Player {
+        let mut player = Player::new(
+            center_x - PLAYER_WIDTH / 2.0,
+            platform.y - PLAYER_HEIGHT,
+            None,
+            None,
+        );
+        player.on_ground = true;
+        player
+    }
+
+    fn ground() -> Platform {
+        Platform::new(0.0, 500.0, 1000.0, 40.0)
+    }
+
+    #[test]
+    fn route_climbs_through_a_middle_ledge() {
+        // The top ledge is 200px up, out of reach of one jump from the floor.
+        let platforms = [
+            ground(),
+            Platform::new(400.0, 400.0, 150.0, 20.0),
+            Platform::new(650.0, 300.0, 150.0, 20.0),
+        ];
+        let me = standing(&platforms[0], 100.0);
+        assert!(hop_between(&me, &platforms[0], &platforms[2], 2).is_none());
+        let hop = route(&me, &platforms, 0, 2).expect("two hops reach the top");
+        assert_eq!(hop.target, 1);
+        assert!(hop.jump);
+        let next = route(&me, &platforms, 1, 2).expect("the middle ledge reaches the top");
+        assert_eq!(next.target, 2);
+    }
+
+    #[test]
+    fn hop_between_gives_up_on_a_ledge_too_high() {
+        let floor = ground();
+        let ledge = Platform::new(400.0, 300.0, 150.0, 20.0);
+        let me = standing(&floor, 100.0);
+        assert!(hop_between(&me, &floor, &ledge, 1).is_none());
+        assert!(route(&me, &[floor, ledge], 0, 1).is_none());
+    }
+
+    #[test]
+    fn takeoff_is_on_the_ledge_side_facing_the_middle() {
+        let floor = ground();
+        let me = standing(&floor, 500.0);
+        let left = Platform::new(200.0, 400.0, 150.0, 20.0);
+        let hop = hop_between(&me, &floor, &left, 1).unwrap();
+        assert!(hop.takeoff_x > left.x + left.width);
+        let right = Platform::new(650.0, 400.0, 150.0, 20.0);
+        let hop = hop_between(&me, &floor, &right, 1).unwrap();
+        assert!(hop.takeoff_x < right.x);
+    }
+
+    #[test]
+    fn multipliers_change_what_is_reachable() {
+        let floor = ground();
+        let ledge = Platform::new(400.0, 360.0, 150.0, 20.0);
+        let mut me = standing(&floor, 100.0);
+        assert!(hop_between(&me, &floor, &ledge, 1).is_none());
+        me.jump_multiplier = 1.2;
+        assert!(hop_between(&me, &floor, &ledge, 1).is_some());
+
+        let near = Platform::new(0.0, 500.0, 400.0, 40.0);
+        let far = Platform::new(700.0, 500.0, 200.0, 40.0);
+        let mut me = standing(&near, 200.0);
+        assert!(hop_between(&me, &near, &far, 1).is_none());
+        me.speed_multiplier = 1.5;
+        let hop = hop_between(&me, &near, &far, 1).expect("a faster run clears the gap");
+        assert!(hop.jump);
+    }
+
+    #[test]
+    fn a_grounded_foe_beside_is_no_stomp_threat() {
+        let floor = ground();
+        let me = standing(&floor, 300.0);
+        let beside = standing(&floor, 360.0);
+        assert!(!is_stomp_threat(&me, &beside));
+        let mut overhead = beside.clone();
+        overhead.y -= 150.0;
+        overhead.on_ground = false;
+        overhead.vel_y = 100.0;
+        assert!(is_stomp_threat(&me, &overhead));
+    }
+}
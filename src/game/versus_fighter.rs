@@ -0,0 +1,260 @@
+use super::versus_ai::VersusAi;
+use super::versus_handicap::Handicap;
+use super::versus_items::VersusItem;
+use super::{Game, PlayerControls};
+use crate::constants::SPEED_BOOST_MULTIPLIER;
+use crate::player::Player;
+use macroquad::prelude::*;
+
+pub const MAX_VERSUS_PLAYERS: usize = 4;
+
+/// Name tag and scoreboard colour for each seat.
+const SLOT_COLORS: [Color; MAX_VERSUS_PLAYERS] = [BLUE, ORANGE, DARKGREEN, PURPLE];
+/// Seats 3 and 4 reuse the P1 and P2 sprites, tinted so all four stand apart.
+const SLOT_TINTS: [Color; MAX_VERSUS_PLAYERS] = [
+    WHITE,
+    WHITE,
+    Color::new(0.6, 1.0, 0.6, 1.0),
+    Color::new(1.0, 0.65, 1.0, 1.0),
+];
+const SLOT_SCHEMES: [&str; MAX_VERSUS_PLAYERS] = ["WASD", "Arrow Keys", "IJKL", "Numpad 4/6/8"];
+
+pub fn slot_color(slot: usize) -> Color {
+    SLOT_COLORS[slot % MAX_VERSUS_PLAYERS]
+}
+
+/// Fixed keys for the seats that don't have a remappable scheme in settings.
+pub fn extra_slot_controls(slot: usize) -> PlayerControls {
+    let (left, right, jump, action, item) = if slot == 2 {
+        (KeyCode::J, KeyCode::L, KeyCode::I, KeyCode::K, KeyCode::O)
+    } else {
+        (
+            KeyCode::Kp4,
+            KeyCode::Kp6,
+            KeyCode::Kp8,
+            KeyCode::Kp5,
+            KeyCode::Kp0,
+        )
+    };
+    PlayerControls {
+        left: Some(left),
+        right: Some(right),
+        jump: Some(jump),
+        action: Some(action),
+        item: Some(item),
+        left_gamepad: None,
+        right_gamepad: None,
+        jump_gamepad: None,
+        action_gamepad: None,
+        item_gamepad: None,
+    }
+}
+
+/// One fighter's buttons for a tick.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct VersusInput {
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    /// Only on the tick the item key goes down.
+    pub item: bool,
+}
+
+impl VersusInput {
+    /// Packs the buttons into one byte for sending online.
+    pub fn to_bits(self) -> u8 {
+        self.left as u8 | (self.right as u8) << 1 | (self.jump as u8) << 2 | (self.item as u8) << 3
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            left: bits & 1 != 0,
+            right: bits & 2 != 0,
+            jump: bits & 4 != 0,
+            item: bits & 8 != 0,
+        }
+    }
+}
+
+/// One seat in a Versus match, human or CPU, with its match tallies.
+#[derive(Clone)]
+pub struct VersusFighter {
+    pub player: Player,
+    pub slot: usize,
+    pub team: usize,
+    pub controls: PlayerControls,
+    pub cpu: Option<VersusAi>,
+    pub kills: u32,
+    pub round_kills: u32,
+    pub streak: u32,
+    /// Longest streak this match.
+    pub best_streak: u32,
+    pub points: u32,
+    pub stocks: u32,
+    pub respawn_timer: f32,
+    /// Out of stocks; sits out the rest of the round.
+    pub out: bool,
+    /// Progress towards the mode's goal this round: seconds or coins.
+    pub objective: f32,
+    /// `objective` summed over the match, for the end screen.
+    pub objective_total: f32,
+    /// Single-use item slot.
+    pub item: Option<VersusItem>,
+    pub shield: bool,
+    pub speed_timer: f32,
+    /// Stuck in place by a freeze trap while this runs.
+    pub frozen_timer: f32,
+}
+
+impl VersusFighter {
+    pub fn new(
+        player: Player,
+        slot: usize,
+        team: usize,
+        controls: PlayerControls,
+        cpu: Option<VersusAi>,
+    ) -> Self {
+        Self {
+            player,
+            slot,
+            team,
+            controls,
+            cpu,
+            kills: 0,
+            round_kills: 0,
+            streak: 0,
+            best_streak: 0,
+            points: 0,
+            stocks: 0,
+            respawn_timer: 0.0,
+            out: false,
+            objective: 0.0,
+            objective_total: 0.0,
+            item: None,
+            shield: false,
+            speed_timer: 0.0,
+            frozen_timer: 0.0,
+        }
+    }
+
+    pub fn add_objective(&mut self, amount: f32) {
+        self.objective += amount;
+        self.objective_total += amount;
+    }
+
+    pub fn name(&self) -> String {
+        format!("P{}", self.slot + 1)
+    }
+
+    pub fn color(&self) -> Color {
+        slot_color(self.slot)
+    }
+
+    pub fn controls_hint(&self) -> String {
+        let scheme = match self.cpu {
+            Some(_) => "CPU",
+            None => SLOT_SCHEMES[self.slot % MAX_VERSUS_PLAYERS],
+        };
+        format!("{}: {}", self.name(), scheme)
+    }
+
+    /// Starts a match on the seat's handicap head start.
+    pub fn apply_head_start(&mut self, handicap: &Handicap) {
+        self.points = handicap.head_start;
+    }
+
+    /// Credits a knockout. The streak bonus is scaled by the handicap.
+    pub fn score_ko(&mut self, handicap: &Handicap) {
+        self.kills += 1;
+        self.round_kills += 1;
+        self.streak += 1;
+        self.best_streak = self.best_streak.max(self.streak);
+        self.points += Game::calculate_versus_points(self.streak, handicap.points);
+    }
+
+    /// Knocked out: the streak ends, a stock goes, and the fighter sits
+    /// out `respawn_delay` plus the handicap's extra. Out for the round
+    /// once the last stock is gone.
+    pub fn take_ko(&mut self, respawn_delay: f32, handicap: &Handicap, stocks_on: bool) {
+        self.streak = 0;
+        self.respawn_timer = respawn_delay + handicap.extra_respawn_delay;
+        self.stocks = self.stocks.saturating_sub(1);
+        self.out = stocks_on && self.stocks == 0;
+    }
+
+    /// Sets this tick's walk and jump strength from the handicap and any
+    /// speed boost.
+    pub fn apply_handicap(&mut self, handicap: &Handicap) {
+        let boost = if self.speed_timer > 0.0 {
+            SPEED_BOOST_MULTIPLIER
+        } else {
+            1.0
+        };
+        self.player.speed_multiplier = handicap.speed * boost;
+        self.player.jump_multiplier = handicap.jump;
+    }
+
+    /// On the field and able to stomp or be stomped.
+    pub fn in_play(&self) -> bool {
+        !self.out && self.respawn_timer <= 0.0
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        if !self.in_play() {
+            return;
+        }
+        let tint = if self.frozen_timer > 0.0 {
+            Color::new(0.55, 0.85, 1.0, 1.0)
+        } else {
+            SLOT_TINTS[self.slot % MAX_VERSUS_PLAYERS]
+        };
+        let center_x = self.player.x - camera_x + self.player.width / 2.0;
+        let center_y = self.player.y - camera_y + self.player.height / 2.0;
+        if self.speed_timer > 0.0 {
+            let trail = if self.player.facing_right { -1.0 } else { 1.0 };
+            for i in 1..=3 {
+                let x = center_x + trail * (self.player.width / 2.0 + 6.0 * i as f32);
+                draw_line(
+                    x,
+                    center_y - 8.0,
+                    x,
+                    center_y + 8.0,
+                    2.0,
+                    VersusItem::SpeedBoost.color(),
+                );
+            }
+        }
+        self.player
+            .draw_vs(camera_x, camera_y, self.slot.is_multiple_of(2), tint);
+        if self.shield {
+            let radius = self.player.height.max(self.player.width) / 2.0 + 6.0;
+            let color = VersusItem::Shield.color();
+            draw_circle(
+                center_x,
+                center_y,
+                radius,
+                Color::new(color.r, color.g, color.b, 0.2),
+            );
+            draw_circle_lines(center_x, center_y, radius, 2.0, color);
+        }
+        let tag = self.name();
+        let tag_width = measure_text(&tag, None, 18u16, 1.0).width;
+        draw_text(
+            &tag,
+            self.player.x - camera_x + self.player.width / 2.0 - tag_width / 2.0,
+            self.player.y - camera_y - 6.0,
+            18.0,
+            self.color(),
+        );
+    }
+}
+
+#[cfg(test)]
+impl VersusFighter {
+    /// A human fighter standing at `(x, y)`, with no sprites and the seat's
+    /// default keys.
+    pub fn for_test(slot: usize, team: usize, x: f32, y: f32) -> Self {
+        let player = Player::new(x, y, None, None);
+        Self::new(player, slot, team, extra_slot_controls(slot), None)
+    }
+}
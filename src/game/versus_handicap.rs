@@ -0,0 +1,244 @@
+use super::versus_fighter::MAX_VERSUS_PLAYERS;
+use super::versus_rules::step;
+use super::{Game, GameState};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const EXTRA_RESPAWN_DELAYS: [f32; 5] = [0.0, 0.5, 1.0, 2.0, 3.0];
+const SPEED_MULTIPLIERS: [f32; 5] = [0.8, 0.9, 1.0, 1.1, 1.2];
+const JUMP_MULTIPLIERS: [f32; 5] = [0.9, 0.95, 1.0, 1.05, 1.1];
+const HEAD_STARTS: [u32; 5] = [0, 200, 500, 1000, 2000];
+const POINT_MULTIPLIERS: [f32; 5] = [0.5, 0.75, 1.0, 1.5, 2.0];
+
+/// Rows on the handicap screen: the player, their five settings, RESET ALL
+/// and BACK.
+pub const HANDICAP_ROWS: usize = 8;
+
+/// Per-player adjustments to even out a skill gap. Neutral by default.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Handicap {
+    /// Seconds added to the rules' respawn delay.
+    pub extra_respawn_delay: f32,
+    /// Scales walking speed, on top of any speed boost.
+    pub speed: f32,
+    /// Scales jump force.
+    pub jump: f32,
+    /// Points the player starts the match with.
+    pub head_start: u32,
+    /// Scales the points each stomp is worth.
+    pub points: f32,
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Self {
+            extra_respawn_delay: 0.0,
+            speed: 1.0,
+            jump: 1.0,
+            head_start: 0,
+            points: 1.0,
+        }
+    }
+}
+
+impl Handicap {
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn cycle(&mut self, row: usize, forward: bool) {
+        match row {
+            0 => {
+                self.extra_respawn_delay =
+                    step(&EXTRA_RESPAWN_DELAYS, self.extra_respawn_delay, forward)
+            }
+            1 => self.speed = step(&SPEED_MULTIPLIERS, self.speed, forward),
+            2 => self.jump = step(&JUMP_MULTIPLIERS, self.jump, forward),
+            3 => self.head_start = step(&HEAD_STARTS, self.head_start, forward),
+            4 => self.points = step(&POINT_MULTIPLIERS, self.points, forward),
+            _ => {}
+        }
+    }
+
+    pub fn row_labels(&self) -> Vec<String> {
+        vec![
+            format!("EXTRA RESPAWN DELAY: +{:.1}s", self.extra_respawn_delay),
+            format!("MOVE SPEED: {:.0}%", self.speed * 100.0),
+            format!("JUMP FORCE: {:.0}%", self.jump * 100.0),
+            format!("HEAD START: {} POINTS", self.head_start),
+            format!("POINTS PER STOMP: x{}", self.points),
+        ]
+    }
+
+    /// Short form for the HUD, listing only what differs from neutral.
+    pub fn tag(&self) -> String {
+        let neutral = Self::default();
+        let mut parts = Vec::new();
+        if self.extra_respawn_delay != neutral.extra_respawn_delay {
+            parts.push(format!("Respawn +{:.1}s", self.extra_respawn_delay));
+        }
+        if self.speed != neutral.speed {
+            parts.push(format!("Speed {:.0}%", self.speed * 100.0));
+        }
+        if self.jump != neutral.jump {
+            parts.push(format!("Jump {:.0}%", self.jump * 100.0));
+        }
+        if self.head_start != neutral.head_start {
+            parts.push(format!("+{} pts", self.head_start));
+        }
+        if self.points != neutral.points {
+            parts.push(format!("Points x{}", self.points));
+        }
+        parts.join(" ")
+    }
+}
+
+impl Game {
+    pub fn update_versus_handicaps(&mut self) {
+        if (is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W))
+            && self.versus_handicap_selection > 0
+        {
+            self.versus_handicap_selection -= 1;
+            self.audio.play_menu_select();
+        }
+        if (is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S))
+            && self.versus_handicap_selection < HANDICAP_ROWS - 1
+        {
+            self.versus_handicap_selection += 1;
+            self.audio.play_menu_select();
+        }
+        let left = is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A);
+        let right = is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D);
+        let confirm = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space);
+        let players = self.versus_rules.players;
+        match self.versus_handicap_selection {
+            0 if left || right || confirm => {
+                self.versus_handicap_player = if left {
+                    (self.versus_handicap_player + players - 1) % players
+                } else {
+                    (self.versus_handicap_player + 1) % players
+                };
+                self.audio.play_menu_select();
+            }
+            row @ 1..=5 if left || right || confirm => {
+                self.versus_rules.handicaps[self.versus_handicap_player].cycle(row - 1, !left);
+                self.audio.play_menu_select();
+            }
+            6 if confirm => {
+                self.versus_rules.handicaps = [Handicap::default(); MAX_VERSUS_PLAYERS];
+                self.audio.play_menu_select();
+            }
+            7 if confirm => {
+                self.audio.play_menu_select();
+                self.state = GameState::VersusSetup;
+            }
+            _ => {}
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.state = GameState::VersusSetup;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::versus_fighter::VersusFighter;
+
+    /// A seat as a match starts it, head start included.
+    fn seated(slot: usize, handicap: &Handicap) -> VersusFighter {
+        let mut fighter = VersusFighter::for_test(slot, slot, 0.0, 0.0);
+        fighter.apply_head_start(handicap);
+        fighter
+    }
+
+    #[test]
+    fn points_multiplier_scales_every_stomp() {
+        let neutral = Handicap::default();
+        let doubled = Handicap {
+            points: 2.0,
+            ..neutral
+        };
+        let halved = Handicap {
+            points: 0.5,
+            ..neutral
+        };
+        let mut plain = seated(0, &neutral);
+        let mut strong = seated(1, &doubled);
+        let mut weak = seated(2, &halved);
+        for _ in 0..3 {
+            plain.score_ko(&neutral);
+            strong.score_ko(&doubled);
+            weak.score_ko(&halved);
+        }
+        assert_eq!(plain.points, 200 + 400 + 800);
+        assert_eq!(strong.points, plain.points * 2);
+        assert_eq!(weak.points, plain.points / 2);
+        assert_eq!((strong.kills, strong.best_streak), (3, 3));
+    }
+
+    #[test]
+    fn head_start_adds_to_what_is_scored() {
+        let handicap = Handicap {
+            head_start: 1000,
+            points: 1.5,
+            ..Handicap::default()
+        };
+        let mut fighter = VersusFighter::for_test(0, 0, 0.0, 0.0);
+        fighter.points = 70;
+        fighter.apply_head_start(&handicap);
+        assert_eq!(fighter.points, 1000, "replaces the last match's points");
+        fighter.score_ko(&handicap);
+        assert_eq!(fighter.points, 1000 + 300);
+        fighter.apply_head_start(&Handicap::default());
+        assert_eq!(fighter.points, 0);
+    }
+
+    #[test]
+    fn extra_respawn_delay_applies_to_the_victim() {
+        let slow = Handicap {
+            extra_respawn_delay: 1.0,
+            ..Handicap::default()
+        };
+        let mut fighter = seated(0, &slow);
+        fighter.streak = 4;
+        fighter.stocks = 2;
+        fighter.take_ko(2.0, &slow, true);
+        assert_eq!(fighter.respawn_timer, 3.0);
+        assert_eq!(fighter.streak, 0);
+        assert!(!fighter.out);
+        fighter.take_ko(2.0, &slow, true);
+        assert!(fighter.out);
+    }
+
+    #[test]
+    fn speed_and_jump_stack_with_a_boost() {
+        let handicap = Handicap {
+            speed: 0.8,
+            jump: 1.1,
+            ..Handicap::default()
+        };
+        let mut fighter = seated(0, &handicap);
+        fighter.apply_handicap(&handicap);
+        assert_eq!(fighter.player.speed_multiplier, 0.8);
+        assert_eq!(fighter.player.jump_multiplier, 1.1);
+        fighter.speed_timer = 1.0;
+        fighter.apply_handicap(&handicap);
+        assert_eq!(
+            fighter.player.speed_multiplier,
+            0.8 * crate::constants::SPEED_BOOST_MULTIPLIER
+        );
+    }
+
+    #[test]
+    fn cycling_walks_through_the_options_and_back() {
+        let mut handicap = Handicap::default();
+        handicap.cycle(3, true);
+        assert_eq!(handicap.head_start, 200);
+        assert!(!handicap.is_neutral());
+        assert_eq!(handicap.tag(), "+200 pts");
+        handicap.cycle(3, false);
+        assert!(handicap.is_neutral());
+        assert!(handicap.tag().is_empty());
+    }
+}
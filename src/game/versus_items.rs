@@ -0,0 +1,484 @@
+use super::versus_fighter::VersusFighter;
+use super::Game;
+use crate::constants::*;
+use ::rand::rngs::StdRng;
+use ::rand::Rng;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What an item box holds. Each one is used once from the fighter's slot.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VersusItem {
+    /// Absorbs the next stomp or shell hit.
+    Shield,
+    SpeedBoost,
+    /// Fired ahead; turns towards the nearest foe and knocks them out.
+    Shell,
+    /// Left on the platform underfoot; the first foe to step on it is frozen.
+    FreezeTrap,
+}
+
+const ITEMS: [VersusItem; 4] = [
+    VersusItem::Shield,
+    VersusItem::SpeedBoost,
+    VersusItem::Shell,
+    VersusItem::FreezeTrap,
+];
+
+impl VersusItem {
+    pub fn random(rng: &mut StdRng) -> Self {
+        ITEMS[rng.gen_range(0..ITEMS.len())]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VersusItem::Shield => "SHIELD",
+            VersusItem::SpeedBoost => "SPEED",
+            VersusItem::Shell => "SHELL",
+            VersusItem::FreezeTrap => "FREEZE",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            VersusItem::Shield => SKYBLUE,
+            VersusItem::SpeedBoost => GOLD,
+            VersusItem::Shell => RED,
+            VersusItem::FreezeTrap => Color::new(0.55, 0.85, 1.0, 1.0),
+        }
+    }
+}
+
+/// A "?" box sitting on a platform until someone with a free slot opens it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ItemBox {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl ItemBox {
+    pub fn rect(&self) -> (f32, f32, f32, f32) {
+        (self.x, self.y, ITEM_BOX_SIZE, ITEM_BOX_SIZE)
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32, time: f32) {
+        let bob = (time * 3.0).sin() * 3.0;
+        let x = self.x - camera_x;
+        let y = self.y - camera_y + bob;
+        draw_rectangle(x, y, ITEM_BOX_SIZE, ITEM_BOX_SIZE, GOLD);
+        draw_rectangle_lines(x, y, ITEM_BOX_SIZE, ITEM_BOX_SIZE, 2.0, BROWN);
+        let mark_width = measure_text("?", None, 22u16, 1.0).width;
+        draw_text(
+            "?",
+            x + ITEM_BOX_SIZE / 2.0 - mark_width / 2.0,
+            y + ITEM_BOX_SIZE - 6.0,
+            22.0,
+            WHITE,
+        );
+    }
+}
+
+/// A fired shell. It can't hit its own team.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Shell {
+    pub x: f32,
+    pub y: f32,
+    vel_x: f32,
+    vel_y: f32,
+    pub owner: usize,
+    team: usize,
+    life: f32,
+}
+
+impl Shell {
+    pub fn rect(&self) -> (f32, f32, f32, f32) {
+        (self.x, self.y, SHELL_SIZE, SHELL_SIZE)
+    }
+
+    /// Centre of the nearest foe on the field, which the shell homes in on.
+    fn target(&self, fighters: &[VersusFighter]) -> Option<(f32, f32)> {
+        let (sx, sy) = (self.x + SHELL_SIZE / 2.0, self.y + SHELL_SIZE / 2.0);
+        fighters
+            .iter()
+            .filter(|f| f.team != self.team && f.in_play())
+            .map(|f| {
+                (
+                    f.player.x + f.player.width / 2.0,
+                    f.player.y + f.player.height / 2.0,
+                )
+            })
+            .min_by(|a, b| {
+                let dist = |p: &(f32, f32)| (p.0 - sx).hypot(p.1 - sy);
+                dist(a).total_cmp(&dist(b))
+            })
+    }
+
+    /// First foe the shell is touching who can be hit.
+    fn victim(&self, fighters: &[VersusFighter]) -> Option<usize> {
+        let (x, y, w, h) = self.rect();
+        fighters.iter().position(|f| {
+            let (fx, fy, fw, fh) = f.player.get_rect();
+            f.team != self.team
+                && f.in_play()
+                && !f.player.is_invulnerable()
+                && fx < x + w
+                && fx + fw > x
+                && fy < y + h
+                && fy + fh > y
+        })
+    }
+
+    /// Turns at most `SHELL_TURN_RATE` towards `target` and moves on.
+    fn update(&mut self, target: Option<(f32, f32)>, dt: f32) {
+        self.life -= dt;
+        if let Some((tx, ty)) = target {
+            let heading = self.vel_y.atan2(self.vel_x);
+            let wanted = (ty - (self.y + SHELL_SIZE / 2.0)).atan2(tx - (self.x + SHELL_SIZE / 2.0));
+            let mut turn = wanted - heading;
+            while turn > std::f32::consts::PI {
+                turn -= std::f32::consts::TAU;
+            }
+            while turn < -std::f32::consts::PI {
+                turn += std::f32::consts::TAU;
+            }
+            let max_turn = SHELL_TURN_RATE * dt;
+            let heading = heading + turn.clamp(-max_turn, max_turn);
+            self.vel_x = heading.cos() * SHELL_SPEED;
+            self.vel_y = heading.sin() * SHELL_SPEED;
+        }
+        self.x += self.vel_x * dt;
+        self.y += self.vel_y * dt;
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let center_x = self.x + SHELL_SIZE / 2.0 - camera_x;
+        let center_y = self.y + SHELL_SIZE / 2.0 - camera_y;
+        draw_circle(center_x, center_y, SHELL_SIZE / 2.0, RED);
+        draw_circle_lines(center_x, center_y, SHELL_SIZE / 2.0, 2.0, WHITE);
+        draw_circle(center_x, center_y, SHELL_SIZE / 5.0, WHITE);
+    }
+}
+
+/// A patch of ice on a platform top, left by a fighter for the other teams.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FreezeTrap {
+    pub x: f32,
+    pub y: f32,
+    team: usize,
+    life: f32,
+}
+
+impl FreezeTrap {
+    /// A foe on the ground with their feet on the trap.
+    fn springs_on(&self, fighter: &VersusFighter) -> bool {
+        let player = &fighter.player;
+        let feet = player.y + player.height;
+        self.team != fighter.team
+            && fighter.in_play()
+            && player.on_ground
+            && (feet - self.y).abs() < 6.0
+            && player.x < self.x + FREEZE_TRAP_WIDTH
+            && player.x + player.width > self.x
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let x = self.x - camera_x;
+        let y = self.y - camera_y;
+        draw_rectangle(
+            x,
+            y - 6.0,
+            FREEZE_TRAP_WIDTH,
+            6.0,
+            Color::new(0.55, 0.85, 1.0, 0.8),
+        );
+        for i in 0..4 {
+            let spike_x = x + 5.0 + i as f32 * (FREEZE_TRAP_WIDTH - 10.0) / 3.0;
+            draw_triangle(
+                vec2(spike_x - 4.0, y - 6.0),
+                vec2(spike_x + 4.0, y - 6.0),
+                vec2(spike_x, y - 14.0),
+                WHITE,
+            );
+        }
+    }
+}
+
+impl Game {
+    /// Clears boxes, shells and traps, and empties every item slot, at the
+    /// start of each round.
+    pub fn reset_versus_items(&mut self) {
+        self.versus_item_boxes.clear();
+        self.versus_shells.clear();
+        self.versus_traps.clear();
+        self.versus_item_timer = 0.0;
+        for fighter in &mut self.versus_fighters {
+            fighter.item = None;
+        }
+    }
+
+    /// Spawns boxes, opens them, and moves shells and traps. Returns true
+    /// when a shell hit decided the round.
+    pub fn update_versus_items(&mut self, dt: f32) -> bool {
+        if !self.versus_rules.items {
+            return false;
+        }
+        self.versus_item_timer += dt;
+        if self.versus_item_timer >= ITEM_BOX_INTERVAL {
+            self.versus_item_timer = 0.0;
+            if self.versus_item_boxes.len() < ITEM_BOX_MAX {
+                self.spawn_item_box();
+            }
+        }
+        for fighter in &mut self.versus_fighters {
+            if !fighter.in_play() || fighter.item.is_some() {
+                continue;
+            }
+            let (x, y, w, h) = fighter.player.get_rect();
+            let opened = self.versus_item_boxes.iter().position(|b| {
+                let (bx, by, bw, bh) = b.rect();
+                x < bx + bw && x + w > bx && y < by + bh && y + h > by
+            });
+            if let Some(index) = opened {
+                self.versus_item_boxes.swap_remove(index);
+                fighter.item = Some(VersusItem::random(&mut self.versus_rng));
+                self.audio.play_coin();
+            }
+        }
+        self.update_freeze_traps(dt);
+        self.update_shells(dt)
+    }
+
+    /// Puts a box on a random platform, clear of hazards and other boxes.
+    fn spawn_item_box(&mut self) {
+        if self.versus_platforms.is_empty() {
+            return;
+        }
+        let bounds = self.versus_bounds;
+        for _ in 0..8 {
+            let platform =
+                self.versus_platforms[self.versus_rng.gen_range(0..self.versus_platforms.len())];
+            let left = platform.x.max(bounds.left);
+            let right = (platform.x + platform.width).min(bounds.right) - ITEM_BOX_SIZE;
+            if right <= left {
+                continue;
+            }
+            let item_box = ItemBox {
+                x: self.versus_rng.gen_range(left..right),
+                y: platform.y - ITEM_BOX_SIZE,
+            };
+            let crowded = self
+                .versus_item_boxes
+                .iter()
+                .any(|b| (b.x - item_box.x).abs() < ITEM_BOX_SIZE * 2.0 && b.y == item_box.y);
+            if crowded || self.touches_versus_hazard(item_box.rect()) {
+                continue;
+            }
+            self.versus_item_boxes.push(item_box);
+            return;
+        }
+    }
+
+    /// Uses whatever is in the fighter's slot. A freeze trap needs solid
+    /// ground underfoot, so it stays in the slot while airborne.
+    pub fn use_versus_item(&mut self, index: usize) {
+        let fighter = &mut self.versus_fighters[index];
+        let Some(item) = fighter.item else {
+            return;
+        };
+        let player = &fighter.player;
+        match item {
+            VersusItem::Shield => fighter.shield = true,
+            VersusItem::SpeedBoost => fighter.speed_timer = SPEED_BOOST_DURATION,
+            VersusItem::Shell => {
+                let direction = if player.facing_right { 1.0 } else { -1.0 };
+                self.versus_shells.push(Shell {
+                    x: player.x + player.width / 2.0 - SHELL_SIZE / 2.0 + direction * player.width,
+                    y: player.y + player.height / 2.0 - SHELL_SIZE / 2.0,
+                    vel_x: direction * SHELL_SPEED,
+                    vel_y: 0.0,
+                    owner: index,
+                    team: fighter.team,
+                    life: SHELL_LIFETIME,
+                });
+            }
+            VersusItem::FreezeTrap if player.on_ground => {
+                self.versus_traps.push(FreezeTrap {
+                    x: player.x + player.width / 2.0 - FREEZE_TRAP_WIDTH / 2.0,
+                    y: player.y + player.height,
+                    team: fighter.team,
+                    life: FREEZE_TRAP_LIFETIME,
+                });
+            }
+            VersusItem::FreezeTrap => return,
+        }
+        self.versus_fighters[index].item = None;
+        self.audio.play_menu_select();
+    }
+
+    fn update_freeze_traps(&mut self, dt: f32) {
+        for trap in &mut self.versus_traps {
+            trap.life -= dt;
+        }
+        self.versus_traps.retain(|trap| trap.life > 0.0);
+        for fighter in &mut self.versus_fighters {
+            let sprung = self
+                .versus_traps
+                .iter()
+                .position(|trap| trap.springs_on(fighter));
+            if let Some(index) = sprung {
+                self.versus_traps.swap_remove(index);
+                fighter.frozen_timer = FREEZE_DURATION;
+                fighter.player.vel_x = 0.0;
+                self.audio.play_hurt();
+            }
+        }
+    }
+
+    fn update_shells(&mut self, dt: f32) -> bool {
+        let bounds = self.versus_bounds;
+        let mut index = 0;
+        while index < self.versus_shells.len() {
+            let target = self.versus_shells[index].target(&self.versus_fighters);
+            self.versus_shells[index].update(target, dt);
+            let shell = &self.versus_shells[index];
+            let (x, y, w, h) = shell.rect();
+            let gone = shell.life <= 0.0
+                || x + w < bounds.left
+                || x > bounds.right
+                || y > bounds.fall_death_y()
+                || y + h < bounds.top;
+            let victim = shell.victim(&self.versus_fighters);
+            if gone || victim.is_some() {
+                let owner = self.versus_shells.swap_remove(index).owner;
+                if let Some(victim) = victim {
+                    if self.hit_versus_fighter(victim, Some(owner)) {
+                        return true;
+                    }
+                }
+                continue;
+            }
+            index += 1;
+        }
+        false
+    }
+
+    /// A stomp or shell hit on `victim`. A shield soaks it up; otherwise
+    /// it's a knockout that knocks their objective loose. Returns true when
+    /// that decided the round.
+    pub fn hit_versus_fighter(&mut self, victim: usize, scorer: Option<usize>) -> bool {
+        let fighter = &mut self.versus_fighters[victim];
+        if fighter.shield {
+            fighter.shield = false;
+            fighter.player.invulnerable_timer = SHIELD_BREAK_GRACE;
+            self.audio.play_hurt();
+            return false;
+        }
+        self.release_versus_objective(victim, true);
+        self.award_versus_ko(victim, scorer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::rand::SeedableRng;
+
+    fn shell(x: f32, y: f32, team: usize) -> Shell {
+        Shell {
+            x,
+            y,
+            vel_x: SHELL_SPEED,
+            vel_y: 0.0,
+            owner: team,
+            team,
+            life: SHELL_LIFETIME,
+        }
+    }
+
+    fn trap(x: f32, y: f32, team: usize) -> FreezeTrap {
+        FreezeTrap {
+            x,
+            y,
+            team,
+            life: FREEZE_TRAP_LIFETIME,
+        }
+    }
+
+    #[test]
+    fn rolls_are_even_and_follow_the_seed() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let rolls: Vec<VersusItem> = (0..4000).map(|_| VersusItem::random(&mut rng)).collect();
+        for item in ITEMS {
+            let count = rolls.iter().filter(|&&roll| roll == item).count();
+            assert!((850..1150).contains(&count), "{}: {}", item.label(), count);
+        }
+        // Netplay peers roll from the same seed and must agree.
+        let mut again = StdRng::seed_from_u64(42);
+        assert!(rolls
+            .iter()
+            .all(|&roll| roll == VersusItem::random(&mut again)));
+    }
+
+    #[test]
+    fn shell_hits_foes_but_not_its_own_team() {
+        let fighters = vec![
+            VersusFighter::for_test(0, 0, 100.0, 100.0),
+            VersusFighter::for_test(1, 1, 300.0, 100.0),
+        ];
+        assert_eq!(shell(105.0, 110.0, 0).victim(&fighters), None);
+        assert_eq!(shell(305.0, 110.0, 0).victim(&fighters), Some(1));
+        assert_eq!(shell(105.0, 110.0, 1).victim(&fighters), Some(0));
+        assert_eq!(shell(500.0, 110.0, 0).victim(&fighters), None);
+    }
+
+    #[test]
+    fn shell_skips_shielded_respawning_and_out_fighters() {
+        let mut fighters = vec![
+            VersusFighter::for_test(0, 0, 0.0, 0.0),
+            VersusFighter::for_test(1, 1, 300.0, 100.0),
+        ];
+        let shot = shell(305.0, 110.0, 0);
+        fighters[1].player.invulnerable_timer = 1.0;
+        assert_eq!(shot.victim(&fighters), None);
+        fighters[1].player.invulnerable_timer = 0.0;
+        fighters[1].respawn_timer = 1.0;
+        assert_eq!(shot.victim(&fighters), None);
+        fighters[1].respawn_timer = 0.0;
+        fighters[1].out = true;
+        assert_eq!(shot.victim(&fighters), None);
+    }
+
+    #[test]
+    fn shell_homes_in_on_the_nearest_foe() {
+        let fighters = vec![
+            VersusFighter::for_test(0, 0, 0.0, 100.0),
+            VersusFighter::for_test(1, 1, 600.0, 100.0),
+            VersusFighter::for_test(2, 2, 200.0, -200.0),
+        ];
+        let mut shot = shell(50.0, 110.0, 0);
+        let target = shot.target(&fighters).unwrap();
+        assert_eq!(target.0, 200.0 + PLAYER_WIDTH / 2.0);
+        shot.update(Some(target), 0.1);
+        assert!(shot.vel_y < 0.0, "should turn up towards P3");
+        assert!((shot.vel_x.hypot(shot.vel_y) - SHELL_SPEED).abs() < 0.01);
+    }
+
+    #[test]
+    fn freeze_trap_springs_on_foes_standing_on_it() {
+        let floor = 400.0;
+        let ice = trap(100.0, floor, 0);
+        let foe = VersusFighter::for_test(1, 1, 110.0, floor - PLAYER_HEIGHT);
+        let friend = VersusFighter::for_test(2, 0, 110.0, floor - PLAYER_HEIGHT);
+        assert!(ice.springs_on(&foe));
+        assert!(!ice.springs_on(&friend));
+
+        let mut airborne = foe.clone();
+        airborne.player.on_ground = false;
+        assert!(!ice.springs_on(&airborne));
+        let beside =
+            VersusFighter::for_test(1, 1, 100.0 + FREEZE_TRAP_WIDTH + 1.0, floor - PLAYER_HEIGHT);
+        assert!(!ice.springs_on(&beside));
+        let above = VersusFighter::for_test(1, 1, 110.0, floor - PLAYER_HEIGHT - 40.0);
+        assert!(!ice.springs_on(&above));
+    }
+}
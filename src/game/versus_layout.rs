@@ -1,13 +1,200 @@
+use crate::camera::WorldBounds;
 use crate::constants::*;
 use crate::platform::Platform;
+use macroquad::prelude::*;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
 
+/// Every `.json` file in here is offered on the arena select screen.
+pub const ARENA_DIR: &str = "assets/arenas";
+
+/// Touching one of these counts as a knockout, like falling out.
+#[derive(Clone, Copy)]
+pub struct Hazard {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Hazard {
+    pub fn overlaps(&self, rect: (f32, f32, f32, f32)) -> bool {
+        let (rx, ry, rw, rh) = rect;
+        rx < self.x + self.width
+            && rx + rw > self.x
+            && ry < self.y + self.height
+            && ry + rh > self.y
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y;
+        draw_rectangle(
+            screen_x,
+            screen_y,
+            self.width,
+            self.height,
+            Color::new(0.6, 0.1, 0.1, 0.3),
+        );
+        let spike_width = 16.0;
+        let spikes = (self.width / spike_width).floor().max(1.0) as usize;
+        let step = self.width / spikes as f32;
+        for i in 0..spikes {
+            let left = screen_x + step * i as f32;
+            draw_triangle(
+                vec2(left, screen_y + self.height),
+                vec2(left + step, screen_y + self.height),
+                vec2(left + step / 2.0, screen_y),
+                RED,
+            );
+        }
+    }
+}
+
+/// On-disk arena format. Rects are `[x, y, width, height]`; `top` and
+/// `bottom` default to the regular level's sky and fall line. Without a
+/// `hill` zone or `crab` spawn, both go over the most central platform.
+#[derive(Deserialize)]
+struct ArenaFile {
+    name: String,
+    width: f32,
+    #[serde(default)]
+    top: f32,
+    #[serde(default = "default_arena_bottom")]
+    bottom: f32,
+    #[serde(default)]
+    wrap: bool,
+    spawns: Vec<[f32; 2]>,
+    platforms: Vec<[f32; 4]>,
+    #[serde(default)]
+    hazards: Vec<[f32; 4]>,
+    #[serde(default)]
+    hill: Option<[f32; 4]>,
+    #[serde(default)]
+    crab: Option<[f32; 2]>,
+}
+
+fn default_arena_bottom() -> f32 {
+    FALL_DEATH_Y
+}
+
+#[derive(Clone)]
 pub struct VersusLayout {
-    pub spawn_p1: (f32, f32),
-    pub spawn_p2: (f32, f32),
+    pub name: String,
+    pub bounds: WorldBounds,
+    /// Leaving one side of the arena enters from the other.
+    pub wrap: bool,
+    /// At least two, one per seat in order; seats beyond the list reuse it.
+    pub spawns: Vec<(f32, f32)>,
     pub platforms: Vec<Platform>,
+    pub hazards: Vec<Hazard>,
+    /// King of the Hill zone as `(x, y, width, height)`.
+    pub hill: (f32, f32, f32, f32),
+    /// Where the crab appears in Capture the Crab.
+    pub crab_spawn: (f32, f32),
+}
+
+/// The platform nearest the arena's middle, ignoring floors that span most
+/// of it, and the hill zone and crab spawn that sit on top of it.
+fn central_objectives(
+    platforms: &[Platform],
+    bounds: &WorldBounds,
+) -> ((f32, f32, f32, f32), (f32, f32)) {
+    let arena_width = bounds.right - bounds.left;
+    let middle = bounds.left + arena_width / 2.0;
+    let distance = |p: &Platform| (p.x + p.width / 2.0 - middle).abs();
+    let platform = platforms
+        .iter()
+        .filter(|p| p.width < arena_width / 2.0)
+        .min_by(|a, b| {
+            distance(a)
+                .total_cmp(&distance(b))
+                .then(a.y.total_cmp(&b.y))
+        })
+        .or_else(|| platforms.first())
+        .copied()
+        .unwrap_or_else(|| Platform::new(bounds.left, GROUND_Y, arena_width, 20.0));
+    let hill = (
+        platform.x,
+        platform.y - HILL_ZONE_HEIGHT,
+        platform.width,
+        HILL_ZONE_HEIGHT,
+    );
+    let crab = (
+        platform.x + platform.width / 2.0 - CRAB_SIZE / 2.0,
+        platform.y - CRAB_SIZE,
+    );
+    (hill, crab)
 }
 
 impl VersusLayout {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let file: ArenaFile =
+            serde_json::from_str(json).map_err(|e| format!("Invalid arena: {}", e))?;
+        if file.width <= 0.0 || file.bottom <= file.top {
+            return Err(format!("Arena '{}' has empty bounds", file.name));
+        }
+        if file.spawns.len() < 2 {
+            return Err(format!("Arena '{}' needs at least 2 spawns", file.name));
+        }
+        if file.platforms.is_empty() {
+            return Err(format!("Arena '{}' has no platforms", file.name));
+        }
+        let bounds = WorldBounds::new(0.0, file.width, file.top, file.bottom);
+        let platforms: Vec<Platform> = file
+            .platforms
+            .iter()
+            .map(|&[x, y, w, h]| Platform::new(x, y, w, h))
+            .collect();
+        let (default_hill, default_crab) = central_objectives(&platforms, &bounds);
+        Ok(Self {
+            name: file.name,
+            bounds,
+            wrap: file.wrap,
+            spawns: file.spawns.iter().map(|&[x, y]| (x, y)).collect(),
+            platforms,
+            hazards: file
+                .hazards
+                .iter()
+                .map(|&[x, y, width, height]| Hazard {
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+                .collect(),
+            hill: file.hill.map_or(default_hill, |[x, y, w, h]| (x, y, w, h)),
+            crab_spawn: file.crab.map_or(default_crab, |[x, y]| (x, y)),
+        })
+    }
+
+    /// The built-in arena followed by every arena file in `dir`, in file
+    /// name order. Files that fail to load are skipped and reported.
+    pub fn load_all(dir: &Path) -> (Vec<Self>, Vec<String>) {
+        let mut arenas = vec![Self::balanced_default()];
+        let mut errors = Vec::new();
+        let mut paths: Vec<_> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect(),
+            Err(_) => return (arenas, errors),
+        };
+        paths.sort();
+        for path in paths {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            match fs::read_to_string(&path) {
+                Ok(json) => match Self::from_json(&json) {
+                    Ok(arena) => arenas.push(arena),
+                    Err(e) => errors.push(format!("{}: {}", file_name, e)),
+                },
+                Err(e) => errors.push(format!("{}: {}", file_name, e)),
+            }
+        }
+        (arenas, errors)
+    }
+
     pub fn balanced_default() -> Self {
         // Expand arena width for more horizontal play
         let screen_w = (SCREEN_WIDTH as f32) * 1.5;
@@ -63,13 +250,44 @@ impl VersusLayout {
             step_right_high,
         ];
 
+        let bounds = WorldBounds::new(0.0, screen_w + 100.0, 0.0, FALL_DEATH_Y);
+        let (hill, crab_spawn) = central_objectives(&platforms, &bounds);
         VersusLayout {
-            spawn_p1: (screen_w / 2.0 - 140.0, GROUND_Y - PLAYER_HEIGHT),
-            spawn_p2: (
-                screen_w / 2.0 + 140.0 - PLAYER_WIDTH,
-                GROUND_Y - PLAYER_HEIGHT,
-            ),
+            name: "Classic".to_string(),
+            bounds,
+            wrap: false,
+            spawns: vec![
+                (screen_w / 2.0 - 140.0, GROUND_Y - PLAYER_HEIGHT),
+                (
+                    screen_w / 2.0 + 140.0 - PLAYER_WIDTH,
+                    GROUND_Y - PLAYER_HEIGHT,
+                ),
+                (160.0, GROUND_Y - PLAYER_HEIGHT),
+                (screen_w - 160.0 - PLAYER_WIDTH, GROUND_Y - PLAYER_HEIGHT),
+            ],
             platforms,
+            hazards: Vec::new(),
+            hill,
+            crab_spawn,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_arenas_all_load() {
+        let (arenas, errors) = VersusLayout::load_all(Path::new(ARENA_DIR));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(arenas.len() > 1);
+    }
+
+    #[test]
+    fn arena_needs_two_spawns() {
+        let json = r#"{"name": "Solo", "width": 800, "spawns": [[0, 0]],
+            "platforms": [[0, 550, 800, 50]]}"#;
+        assert!(VersusLayout::from_json(json).is_err());
+    }
+}
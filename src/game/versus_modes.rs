@@ -0,0 +1,476 @@
+use super::versus_fighter::VersusFighter;
+use super::versus_rules::VersusMode;
+use super::Game;
+use crate::coin::Coin;
+use crate::constants::*;
+use crate::platform::Platform;
+use ::rand::Rng;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Something lying loose in the arena, like a dropped coin or the crab.
+/// It falls until it lands on a platform top.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Loose {
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    vel_x: f32,
+    vel_y: f32,
+    /// Nobody can grab it until this runs out, so a knocked-loose item
+    /// isn't picked straight back up.
+    pickup_delay: f32,
+}
+
+impl Loose {
+    pub fn new(x: f32, y: f32, size: f32) -> Self {
+        Self {
+            x,
+            y,
+            size,
+            vel_x: 0.0,
+            vel_y: 0.0,
+            pickup_delay: 0.0,
+        }
+    }
+
+    /// Thrown out of a stomped player.
+    pub fn tossed(x: f32, y: f32, size: f32, vel_x: f32, vel_y: f32) -> Self {
+        Self {
+            vel_x,
+            vel_y,
+            pickup_delay: VERSUS_PICKUP_DELAY,
+            ..Self::new(x, y, size)
+        }
+    }
+
+    pub fn update(&mut self, dt: f32, platforms: &[Platform]) {
+        self.pickup_delay = (self.pickup_delay - dt).max(0.0);
+        let previous_bottom = self.y + self.size;
+        self.vel_y = (self.vel_y + GRAVITY * dt).min(TERMINAL_VELOCITY);
+        self.x += self.vel_x * dt;
+        self.y += self.vel_y * dt;
+        if self.vel_y < 0.0 {
+            return;
+        }
+        let bottom = self.y + self.size;
+        let landing = platforms.iter().find(|p| {
+            self.x + self.size > p.x
+                && self.x < p.x + p.width
+                && previous_bottom <= p.y
+                && bottom >= p.y
+        });
+        if let Some(platform) = landing {
+            self.y = platform.y - self.size;
+            self.vel_x = 0.0;
+            self.vel_y = 0.0;
+        }
+    }
+
+    pub fn can_be_picked_up_by(&self, rect: (f32, f32, f32, f32)) -> bool {
+        let (rx, ry, rw, rh) = rect;
+        self.pickup_delay <= 0.0
+            && rx < self.x + self.size
+            && rx + rw > self.x
+            && ry < self.y + self.size
+            && ry + rh > self.y
+    }
+
+    /// Draws it with the regular coin sprite; every Coin Rush coin spins in step.
+    pub fn draw_as_coin(&self, rotation: f32, camera_x: f32, camera_y: f32) {
+        let mut sprite = Coin::new(self.x, self.y);
+        sprite.rotation = rotation;
+        sprite.draw(camera_x, camera_y);
+    }
+}
+
+/// The Capture the Crab objective, either loose or held by a fighter.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Crab {
+    pub body: Loose,
+    pub home: (f32, f32),
+    /// Index into `versus_fighters` of whoever is holding it.
+    pub carrier: Option<usize>,
+}
+
+impl Crab {
+    pub fn new(home: (f32, f32)) -> Self {
+        Self {
+            body: Loose::new(home.0, home.1, CRAB_SIZE),
+            home,
+            carrier: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.home);
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let x = self.body.x - camera_x;
+        let y = self.body.y - camera_y;
+        let size = self.body.size;
+        let shell = Color::new(0.87, 0.3, 0.15, 1.0);
+        draw_circle(x - 2.0, y + size * 0.35, 6.0, shell);
+        draw_circle(x + size + 2.0, y + size * 0.35, 6.0, shell);
+        draw_rectangle(x, y + size * 0.3, size, size * 0.6, shell);
+        draw_rectangle_lines(x, y + size * 0.3, size, size * 0.6, 2.0, BLACK);
+        draw_circle(x + size * 0.3, y + size * 0.2, 3.0, BLACK);
+        draw_circle(x + size * 0.7, y + size * 0.2, 3.0, BLACK);
+    }
+}
+
+/// A team's progress towards the mode's goal this round.
+pub fn team_objective(fighters: &[VersusFighter], team: usize) -> f32 {
+    fighters
+        .iter()
+        .filter(|f| f.team == team)
+        .map(|f| f.objective)
+        .sum()
+}
+
+/// A team's knockouts this round, stocks left and match points.
+pub fn team_tally(fighters: &[VersusFighter], team: usize) -> (u32, u32, u32) {
+    fighters
+        .iter()
+        .filter(|f| f.team == team)
+        .fold((0, 0, 0), |(kills, stocks, points), f| {
+            (kills + f.round_kills, stocks + f.stocks, points + f.points)
+        })
+}
+
+/// Whichever of `teams` is alone at the top of `key`, if any.
+fn sole_leader<K: Ord>(teams: usize, key: impl Fn(usize) -> K) -> Option<usize> {
+    let keys: Vec<K> = (0..teams).map(key).collect();
+    let best = keys.iter().max()?;
+    let mut leaders = keys.iter().enumerate().filter(|(_, k)| *k == best);
+    match (leaders.next(), leaders.next()) {
+        (Some((team, _)), None) => Some(team),
+        _ => None,
+    }
+}
+
+/// The team that reached `mode`'s goal, if one did. Teams crossing it on
+/// the same tick go to whoever is further past; a dead heat plays on.
+pub fn goal_winner(mode: VersusMode, fighters: &[VersusFighter], teams: usize) -> Option<usize> {
+    let goal = mode.goal()?;
+    if (0..teams).all(|team| team_objective(fighters, team) < goal) {
+        return None;
+    }
+    sole_leader(teams, |team| {
+        (team_objective(fighters, team) * 100.0) as u32
+    })
+}
+
+/// Round result under stocks once at most one team has anyone left in:
+/// `Some` of the survivor, or of `None` when the last fighters went out
+/// together. `None` while two or more teams are still standing.
+pub fn last_team_standing(fighters: &[VersusFighter], teams: usize) -> Option<Option<usize>> {
+    let mut standing = (0..teams).filter(|&team| fighters.iter().any(|f| f.team == team && !f.out));
+    match (standing.next(), standing.next()) {
+        (survivor, None) => Some(survivor),
+        _ => None,
+    }
+}
+
+/// Round winner when the clock runs out: furthest towards the mode's
+/// goal, then most knockouts this round, then stocks left, then match
+/// points. A tie at the top is a draw.
+pub fn time_up_winner(fighters: &[VersusFighter], teams: usize) -> Option<usize> {
+    sole_leader(teams, |team| {
+        let (kills, stocks, points) = team_tally(fighters, team);
+        let objective = (team_objective(fighters, team) * 100.0) as u32;
+        (objective, kills, stocks, points)
+    })
+}
+
+impl Game {
+    /// Clears coins and returns the crab home at the start of each round.
+    pub fn reset_versus_objectives(&mut self) {
+        self.versus_coins.clear();
+        self.versus_coin_timer = 0.0;
+        self.versus_crab.reset();
+    }
+
+    /// Runs the current mode's objective for one tick. Returns true when a
+    /// team reached the goal and the round ended.
+    pub fn update_versus_objectives(&mut self, dt: f32) -> bool {
+        self.versus_coin_rotation += COIN_ROTATION_SPEED * dt;
+        match self.versus_rules.mode {
+            VersusMode::Deathmatch => return false,
+            VersusMode::KingOfTheHill => self.update_hill(dt),
+            VersusMode::CoinRush => self.update_coin_rush(dt),
+            VersusMode::CaptureTheCrab => self.update_crab(dt),
+        }
+        let teams = self.versus_team_rounds.len();
+        let winner = goal_winner(self.versus_rules.mode, &self.versus_fighters, teams);
+        if winner.is_some() {
+            self.end_versus_round(winner);
+            return true;
+        }
+        false
+    }
+
+    pub fn versus_team_objective(&self, team: usize) -> f32 {
+        team_objective(&self.versus_fighters, team)
+    }
+
+    /// Team holding the hill on its own, if any.
+    pub fn versus_hill_holder(&self) -> Option<usize> {
+        let (hx, hy, hw, hh) = self.versus_hill;
+        let mut teams = self
+            .versus_fighters
+            .iter()
+            .filter(|f| f.in_play())
+            .filter(|f| {
+                let (x, y, w, h) = f.player.get_rect();
+                x < hx + hw && x + w > hx && y < hy + hh && y + h > hy
+            })
+            .map(|f| f.team);
+        let first = teams.next()?;
+        teams.all(|team| team == first).then_some(first)
+    }
+
+    /// Only a team standing alone in the zone scores, shared between
+    /// whoever of them is inside.
+    fn update_hill(&mut self, dt: f32) {
+        let Some(team) = self.versus_hill_holder() else {
+            return;
+        };
+        let (hx, hy, hw, hh) = self.versus_hill;
+        let inside: Vec<usize> = (0..self.versus_fighters.len())
+            .filter(|&i| {
+                let f = &self.versus_fighters[i];
+                let (x, y, w, h) = f.player.get_rect();
+                f.team == team
+                    && f.in_play()
+                    && x < hx + hw
+                    && x + w > hx
+                    && y < hy + hh
+                    && y + h > hy
+            })
+            .collect();
+        let share = dt / inside.len() as f32;
+        for i in inside {
+            self.versus_fighters[i].add_objective(share);
+        }
+    }
+
+    fn update_coin_rush(&mut self, dt: f32) {
+        self.versus_coin_timer += dt;
+        if self.versus_coin_timer >= COIN_RUSH_SPAWN_INTERVAL {
+            self.versus_coin_timer = 0.0;
+            if self.versus_coins.len() < COIN_RUSH_MAX_COINS {
+                self.spawn_rush_coin();
+            }
+        }
+        let bottom = self.versus_bounds.fall_death_y();
+        for coin in &mut self.versus_coins {
+            coin.update(dt, &self.versus_platforms);
+        }
+        self.versus_coins.retain(|coin| coin.y < bottom);
+        for fighter in &mut self.versus_fighters {
+            if !fighter.in_play() {
+                continue;
+            }
+            let rect = fighter.player.get_rect();
+            let before = self.versus_coins.len();
+            self.versus_coins
+                .retain(|coin| !coin.can_be_picked_up_by(rect));
+            let picked = before - self.versus_coins.len();
+            if picked > 0 {
+                fighter.add_objective(picked as f32);
+                self.audio.play_coin();
+            }
+        }
+    }
+
+    /// Drops a coin over a random spot on a random platform, clear of hazards.
+    fn spawn_rush_coin(&mut self) {
+        if self.versus_platforms.is_empty() {
+            return;
+        }
+        let bounds = self.versus_bounds;
+        for _ in 0..8 {
+            let platform =
+                self.versus_platforms[self.versus_rng.gen_range(0..self.versus_platforms.len())];
+            let left = platform.x.max(bounds.left);
+            let right = (platform.x + platform.width).min(bounds.right) - COIN_SIZE;
+            if right <= left {
+                continue;
+            }
+            let x = self.versus_rng.gen_range(left..right);
+            let coin = Loose::new(x, platform.y - COIN_SIZE - 40.0, COIN_SIZE);
+            let landing = (x, platform.y - COIN_SIZE, COIN_SIZE, COIN_SIZE);
+            if self.touches_versus_hazard(landing) {
+                continue;
+            }
+            self.versus_coins.push(coin);
+            return;
+        }
+    }
+
+    fn update_crab(&mut self, dt: f32) {
+        match self.versus_crab.carrier {
+            Some(carrier) => {
+                let fighter = &mut self.versus_fighters[carrier];
+                let player = &fighter.player;
+                let body = &mut self.versus_crab.body;
+                body.x = player.x + player.width / 2.0 - body.size / 2.0;
+                body.y = player.y - body.size;
+                fighter.add_objective(dt);
+            }
+            None => {
+                self.versus_crab.body.update(dt, &self.versus_platforms);
+                if self.versus_crab.body.y > self.versus_bounds.fall_death_y() {
+                    self.versus_crab.reset();
+                }
+                let body = &self.versus_crab.body;
+                let taker = self
+                    .versus_fighters
+                    .iter()
+                    .position(|f| f.in_play() && body.can_be_picked_up_by(f.player.get_rect()));
+                if taker.is_some() {
+                    self.versus_crab.carrier = taker;
+                    self.audio.play_coin();
+                }
+            }
+        }
+    }
+
+    /// What a knocked-out fighter loses: a stomp (`scatter`) knocks half
+    /// their coins or the crab loose where they stood; falling out takes
+    /// the coins with them and sends the crab home.
+    pub fn release_versus_objective(&mut self, victim: usize, scatter: bool) {
+        match self.versus_rules.mode {
+            VersusMode::CoinRush => {
+                let fighter = &mut self.versus_fighters[victim];
+                let dropped = (fighter.objective / 2.0).ceil();
+                fighter.objective -= dropped;
+                if scatter {
+                    let (x, y, w, _) = fighter.player.get_rect();
+                    for _ in 0..dropped as u32 {
+                        let rng = &mut self.versus_rng;
+                        self.versus_coins.push(Loose::tossed(
+                            x + w / 2.0 - COIN_SIZE / 2.0,
+                            y,
+                            COIN_SIZE,
+                            rng.gen_range(-160.0..160.0),
+                            rng.gen_range(-380.0..-240.0),
+                        ));
+                    }
+                }
+            }
+            VersusMode::CaptureTheCrab if self.versus_crab.carrier == Some(victim) => {
+                if scatter {
+                    let body = &self.versus_crab.body;
+                    self.versus_crab.body = Loose::tossed(body.x, body.y, body.size, 0.0, -300.0);
+                    self.versus_crab.carrier = None;
+                } else {
+                    self.versus_crab.reset();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Free-for-all fighters, one per entry, with that much objective.
+    fn fighters(objectives: &[f32]) -> Vec<VersusFighter> {
+        objectives
+            .iter()
+            .enumerate()
+            .map(|(slot, &objective)| {
+                let mut fighter = VersusFighter::for_test(slot, slot, 0.0, 0.0);
+                fighter.objective = objective;
+                fighter
+            })
+            .collect()
+    }
+
+    #[test]
+    fn coin_rush_goes_to_the_first_team_at_the_goal() {
+        let mode = VersusMode::CoinRush;
+        let field = fighters(&[COIN_RUSH_GOAL - 1.0, COIN_RUSH_GOAL - 2.0]);
+        assert_eq!(goal_winner(mode, &field, 2), None);
+        let field = fighters(&[COIN_RUSH_GOAL - 1.0, COIN_RUSH_GOAL]);
+        assert_eq!(goal_winner(mode, &field, 2), Some(1));
+        // Two teams crossing at once: the one further past takes it.
+        let field = fighters(&[COIN_RUSH_GOAL + 2.0, COIN_RUSH_GOAL + 1.0, 0.0]);
+        assert_eq!(goal_winner(mode, &field, 3), Some(0));
+    }
+
+    #[test]
+    fn coin_rush_dead_heat_plays_on() {
+        let field = fighters(&[COIN_RUSH_GOAL, COIN_RUSH_GOAL]);
+        assert_eq!(goal_winner(VersusMode::CoinRush, &field, 2), None);
+    }
+
+    #[test]
+    fn crab_time_counts_per_team() {
+        let mode = VersusMode::CaptureTheCrab;
+        let half = CAPTURE_THE_CRAB_GOAL / 2.0;
+        let mut field = fighters(&[half, 1.0, half, 1.0]);
+        for fighter in &mut field {
+            fighter.team = fighter.slot % 2;
+        }
+        assert_eq!(team_objective(&field, 0), CAPTURE_THE_CRAB_GOAL);
+        assert_eq!(goal_winner(mode, &field, 2), Some(0));
+        // Nobody wins on objectives in Deathmatch.
+        assert_eq!(goal_winner(VersusMode::Deathmatch, &field, 2), None);
+    }
+
+    #[test]
+    fn crab_time_up_ties_are_a_draw() {
+        let field = fighters(&[12.5, 12.5]);
+        assert_eq!(time_up_winner(&field, 2), None);
+        let field = fighters(&[12.5, 12.0]);
+        assert_eq!(time_up_winner(&field, 2), Some(0));
+    }
+
+    #[test]
+    fn time_up_breaks_objective_ties_on_knockouts_then_stocks() {
+        let mut field = fighters(&[3.0, 3.0]);
+        field[1].round_kills = 1;
+        assert_eq!(time_up_winner(&field, 2), Some(1));
+        field[0].round_kills = 1;
+        field[0].stocks = 2;
+        field[1].stocks = 1;
+        assert_eq!(time_up_winner(&field, 2), Some(0));
+    }
+
+    #[test]
+    fn stocks_end_the_round_with_one_team_standing() {
+        let mut field = fighters(&[0.0, 0.0, 0.0]);
+        assert_eq!(last_team_standing(&field, 3), None);
+        field[0].out = true;
+        assert_eq!(last_team_standing(&field, 3), None);
+        field[2].out = true;
+        assert_eq!(last_team_standing(&field, 3), Some(Some(1)));
+    }
+
+    #[test]
+    fn stocks_running_out_together_is_a_draw() {
+        let mut field = fighters(&[0.0, 0.0]);
+        field[0].out = true;
+        field[1].out = true;
+        assert_eq!(last_team_standing(&field, 2), Some(None));
+    }
+
+    #[test]
+    fn a_team_stands_while_any_member_is_in() {
+        let mut field = fighters(&[0.0; 4]);
+        for fighter in &mut field {
+            fighter.team = fighter.slot % 2;
+        }
+        field[0].out = true;
+        field[1].out = true;
+        assert_eq!(last_team_standing(&field, 2), None);
+        field[3].out = true;
+        assert_eq!(last_team_standing(&field, 2), Some(Some(0)));
+    }
+}
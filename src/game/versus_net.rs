@@ -0,0 +1,561 @@
+use super::versus_fighter::{VersusFighter, VersusInput};
+use super::versus_items::{FreezeTrap, ItemBox, Shell};
+use super::versus_layout::{VersusLayout, ARENA_DIR};
+use super::versus_modes::{Crab, Loose};
+use super::versus_rules::VersusRules;
+use super::{ControlAction, Game, GameState};
+use crate::constants::*;
+use crate::netplay::{
+    answer_probes, Handshake, LanBrowser, LanLobby, LobbyInfo, NetPeer, RollbackSim, Transport,
+    UdpTransport, TICK_RATE,
+};
+use ::rand::rngs::StdRng;
+use ::rand::Rng;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const NET_TICK: f32 = 1.0 / TICK_RATE;
+
+/// Rows on the online lobby screen.
+pub const NET_LOBBY_ROWS: usize = 6;
+
+/// What the host offers during the handshake. The guest plays it on its own
+/// copy of the arena, found by name.
+#[derive(Serialize, Deserialize)]
+struct MatchSetup {
+    seed: u64,
+    arena: String,
+    rules: VersusRules,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum NetRole {
+    Host,
+    Join,
+}
+
+/// Settings and connection state of the online lobby screen.
+pub struct NetLobby {
+    pub role: NetRole,
+    pub port: String,
+    /// `host:port`, or just the host for the default port.
+    pub address: String,
+    /// Frames of input delay; more hides more lag but feels heavier.
+    pub input_delay: u32,
+    pub selection: usize,
+    handshake: Option<Handshake>,
+    pub status: Option<String>,
+    /// Looks for LAN lobbies while joining.
+    browser: Option<LanBrowser>,
+    pub lan_selection: usize,
+}
+
+impl Default for NetLobby {
+    fn default() -> Self {
+        Self {
+            role: NetRole::Host,
+            port: NET_DEFAULT_PORT.to_string(),
+            address: format!("127.0.0.1:{}", NET_DEFAULT_PORT),
+            input_delay: NET_DEFAULT_INPUT_DELAY,
+            selection: 0,
+            handshake: None,
+            status: None,
+            browser: None,
+            lan_selection: 0,
+        }
+    }
+}
+
+impl NetLobby {
+    pub fn connecting(&self) -> bool {
+        self.handshake.is_some()
+    }
+
+    pub fn guest_seen(&self) -> bool {
+        self.handshake.as_ref().is_some_and(|h| h.guest_seen())
+    }
+
+    /// Number of LAN lobbies found so far.
+    pub fn lan_count(&self) -> usize {
+        self.browser.as_ref().map_or(0, |b| b.lobbies().len())
+    }
+
+    pub fn selected_lan_lobby(&self) -> Option<&LanLobby> {
+        let lobbies = self.browser.as_ref()?.lobbies();
+        lobbies.get(self.lan_selection.min(lobbies.len().saturating_sub(1)))
+    }
+
+    /// Browses the LAN while joining and stops while hosting.
+    fn refresh_browser(&mut self) {
+        self.browser = None;
+        if self.role == NetRole::Join {
+            let ports = (NET_DEFAULT_PORT..NET_DEFAULT_PORT + NET_LAN_PORTS).collect();
+            match LanBrowser::new(ports) {
+                Ok(browser) => self.browser = Some(browser),
+                Err(e) => self.status = Some(e),
+            }
+        }
+    }
+}
+
+/// A running online match. The host is seat 0 and plays P1, the guest is
+/// seat 1 and plays P2; any CPU seats are simulated on both machines.
+pub struct NetMatch {
+    peer: NetPeer<VersusSnapshot>,
+    pub seat: usize,
+    accumulator: f32,
+    /// An item press waiting for the next tick, so a quick tap isn't lost
+    /// on a frame that runs no tick.
+    item_latched: bool,
+}
+
+impl NetMatch {
+    pub fn input_delay(&self) -> u32 {
+        self.peer.session().input_delay()
+    }
+
+    pub fn rollbacks(&self) -> u32 {
+        self.peer.session().rollbacks()
+    }
+}
+
+/// Everything a Versus tick reads or writes, for rolling back to.
+#[derive(Clone)]
+pub struct VersusSnapshot {
+    state: GameState,
+    fighters: Vec<VersusFighter>,
+    team_rounds: Vec<u32>,
+    round: u32,
+    round_intro: f32,
+    round_result: Option<usize>,
+    time_remaining: f32,
+    fade_timer: f32,
+    coins: Vec<Loose>,
+    coin_timer: f32,
+    coin_rotation: f32,
+    crab: Crab,
+    item_boxes: Vec<ItemBox>,
+    item_timer: f32,
+    shells: Vec<Shell>,
+    traps: Vec<FreezeTrap>,
+    rng: StdRng,
+}
+
+/// FNV-1a, fed a value at a time.
+struct Checksum(u64);
+
+impl Checksum {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn add(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn add_f32(&mut self, value: f32) {
+        self.add(value.to_bits() as u64);
+    }
+}
+
+impl RollbackSim for Game {
+    type State = VersusSnapshot;
+
+    fn save_state(&self) -> VersusSnapshot {
+        VersusSnapshot {
+            state: self.state,
+            fighters: self.versus_fighters.clone(),
+            team_rounds: self.versus_team_rounds.clone(),
+            round: self.versus_round,
+            round_intro: self.versus_round_intro,
+            round_result: self.versus_round_result,
+            time_remaining: self.versus_time_remaining,
+            fade_timer: self.level_start_fade_timer,
+            coins: self.versus_coins.clone(),
+            coin_timer: self.versus_coin_timer,
+            coin_rotation: self.versus_coin_rotation,
+            crab: self.versus_crab.clone(),
+            item_boxes: self.versus_item_boxes.clone(),
+            item_timer: self.versus_item_timer,
+            shells: self.versus_shells.clone(),
+            traps: self.versus_traps.clone(),
+            rng: self.versus_rng.clone(),
+        }
+    }
+
+    fn load_state(&mut self, snapshot: &VersusSnapshot) {
+        let snapshot = snapshot.clone();
+        self.state = snapshot.state;
+        self.versus_fighters = snapshot.fighters;
+        self.versus_team_rounds = snapshot.team_rounds;
+        self.versus_round = snapshot.round;
+        self.versus_round_intro = snapshot.round_intro;
+        self.versus_round_result = snapshot.round_result;
+        self.versus_time_remaining = snapshot.time_remaining;
+        self.level_start_fade_timer = snapshot.fade_timer;
+        self.versus_coins = snapshot.coins;
+        self.versus_coin_timer = snapshot.coin_timer;
+        self.versus_coin_rotation = snapshot.coin_rotation;
+        self.versus_crab = snapshot.crab;
+        self.versus_item_boxes = snapshot.item_boxes;
+        self.versus_item_timer = snapshot.item_timer;
+        self.versus_shells = snapshot.shells;
+        self.versus_traps = snapshot.traps;
+        self.versus_rng = snapshot.rng;
+    }
+
+    fn advance(&mut self, inputs: [u8; 2], replaying: bool) {
+        // Once the match is over the peers only keep trading inputs, in
+        // case a late one rolls the finish back.
+        if self.state != GameState::Versus {
+            return;
+        }
+        let sound = self.audio.is_enabled();
+        if replaying {
+            self.audio.set_enabled(false);
+        }
+        self.step_versus(NET_TICK, &inputs.map(VersusInput::from_bits));
+        self.audio.set_enabled(sound);
+    }
+
+    fn checksum(&self) -> u64 {
+        let mut sum = Checksum::new();
+        sum.add((self.state == GameState::VersusEnd) as u64);
+        for fighter in &self.versus_fighters {
+            let player = &fighter.player;
+            for value in [
+                player.x,
+                player.y,
+                player.vel_x,
+                player.vel_y,
+                fighter.respawn_timer,
+                fighter.objective,
+                fighter.frozen_timer,
+            ] {
+                sum.add_f32(value);
+            }
+            for value in [fighter.kills, fighter.stocks, fighter.points] {
+                sum.add(value as u64);
+            }
+            sum.add(fighter.item.map_or(0, |item| item as u64 + 1));
+            sum.add(fighter.shield as u64);
+        }
+        for &rounds in &self.versus_team_rounds {
+            sum.add(rounds as u64);
+        }
+        sum.add(self.versus_round as u64);
+        sum.add_f32(self.versus_time_remaining);
+        for coin in &self.versus_coins {
+            sum.add_f32(coin.x);
+            sum.add_f32(coin.y);
+        }
+        sum.add_f32(self.versus_crab.body.x);
+        sum.add_f32(self.versus_crab.body.y);
+        for shell in &self.versus_shells {
+            sum.add_f32(shell.x);
+            sum.add_f32(shell.y);
+        }
+        sum.add(self.versus_item_boxes.len() as u64);
+        sum.add(self.versus_traps.len() as u64);
+        // Peeking ahead catches the two sides having drawn different numbers.
+        sum.add(self.versus_rng.clone().gen());
+        sum.0
+    }
+}
+
+impl Game {
+    pub fn open_net_lobby(&mut self) {
+        let (arenas, _) = VersusLayout::load_all(Path::new(ARENA_DIR));
+        self.versus_arenas = arenas;
+        if self.versus_arena_selection > self.versus_arenas.len() {
+            self.versus_arena_selection = 0;
+        }
+        self.net_lobby.status = None;
+        self.net_lobby.refresh_browser();
+        self.state = GameState::NetLobby;
+    }
+
+    pub fn update_net_lobby(&mut self, dt: f32) {
+        // Drain typed characters every frame so nothing stale shows up
+        // when the address row is selected later.
+        let typed: Vec<char> = std::iter::from_fn(get_char_pressed).collect();
+        if let Some(handshake) = self.net_lobby.handshake.as_mut() {
+            let result = handshake.update(dt);
+            match result {
+                Ok(Some((transport, setup))) => {
+                    self.net_lobby.handshake = None;
+                    if let Err(e) = self.start_net_match(transport, &setup) {
+                        self.net_lobby.status = Some(e);
+                    }
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.net_lobby.handshake = None;
+                    self.net_lobby.status = Some(e);
+                }
+            }
+            let cancel = is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Enter);
+            if cancel && self.net_lobby.connecting() {
+                self.net_lobby.handshake = None;
+                self.net_lobby.status = Some("Cancelled".to_string());
+                self.audio.play_menu_select();
+            }
+            return;
+        }
+        if let Some(browser) = self.net_lobby.browser.as_mut() {
+            if let Err(e) = browser.update(dt) {
+                self.net_lobby.browser = None;
+                self.net_lobby.status = Some(e);
+            }
+        }
+        // Letters go into the address, so only the arrow keys navigate.
+        if is_key_pressed(KeyCode::Up) && self.net_lobby.selection > 0 {
+            self.net_lobby.selection -= 1;
+            self.audio.play_menu_select();
+        }
+        if is_key_pressed(KeyCode::Down) && self.net_lobby.selection < NET_LOBBY_ROWS - 1 {
+            self.net_lobby.selection += 1;
+            self.audio.play_menu_select();
+        }
+        let left = is_key_pressed(KeyCode::Left);
+        let right = is_key_pressed(KeyCode::Right);
+        let confirm = is_key_pressed(KeyCode::Enter);
+        let lobby = &mut self.net_lobby;
+        match lobby.selection {
+            0 if left || right || confirm => {
+                lobby.role = match lobby.role {
+                    NetRole::Host => NetRole::Join,
+                    NetRole::Join => NetRole::Host,
+                };
+                lobby.status = None;
+                lobby.refresh_browser();
+                self.audio.play_menu_select();
+            }
+            1 => {
+                let (text, limit) = match lobby.role {
+                    NetRole::Host => (&mut lobby.port, 5),
+                    NetRole::Join => (&mut lobby.address, 64),
+                };
+                for ch in typed {
+                    let allowed = match lobby.role {
+                        NetRole::Host => ch.is_ascii_digit(),
+                        NetRole::Join => ch.is_ascii_graphic(),
+                    };
+                    if allowed && text.len() < limit {
+                        text.push(ch);
+                    }
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    text.pop();
+                }
+            }
+            2 if lobby.role == NetRole::Host && (left || right) => {
+                let choices = self.versus_arenas.len() + 1;
+                self.versus_arena_selection = if left {
+                    (self.versus_arena_selection + choices - 1) % choices
+                } else {
+                    (self.versus_arena_selection + 1) % choices
+                };
+                self.audio.play_menu_select();
+            }
+            2 if lobby.role == NetRole::Join
+                && lobby.lan_count() > 0
+                && (left || right || confirm) =>
+            {
+                let found = lobby.lan_count();
+                lobby.lan_selection = if left {
+                    (lobby.lan_selection.min(found - 1) + found - 1) % found
+                } else if right {
+                    (lobby.lan_selection + 1) % found
+                } else {
+                    lobby.lan_selection.min(found - 1)
+                };
+                if let Some(address) = lobby.selected_lan_lobby().map(|l| l.address.to_string()) {
+                    lobby.address = address;
+                }
+                self.audio.play_menu_select();
+                if confirm {
+                    if let Err(e) = self.connect_net_lobby() {
+                        self.net_lobby.status = Some(e);
+                    }
+                }
+            }
+            3 if left || right => {
+                lobby.input_delay = if left {
+                    lobby.input_delay.saturating_sub(1)
+                } else {
+                    (lobby.input_delay + 1).min(NET_MAX_INPUT_DELAY)
+                };
+                self.audio.play_menu_select();
+            }
+            4 if confirm => {
+                self.audio.play_menu_select();
+                if let Err(e) = self.connect_net_lobby() {
+                    self.net_lobby.status = Some(e);
+                }
+            }
+            5 if confirm => {
+                self.audio.play_menu_select();
+                self.state = GameState::VersusSetup;
+            }
+            _ => {}
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.state = GameState::VersusSetup;
+        }
+        if self.state != GameState::NetLobby {
+            self.net_lobby.browser = None;
+        }
+    }
+
+    /// Starts hosting or joining with the lobby's settings.
+    fn connect_net_lobby(&mut self) -> Result<(), String> {
+        match self.net_lobby.role {
+            NetRole::Host => {
+                let rules = self.versus_rules;
+                rules.validate_online()?;
+                let port: u16 = self
+                    .net_lobby
+                    .port
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a port", self.net_lobby.port))?;
+                self.choose_versus_arena();
+                let setup = MatchSetup {
+                    seed: ::rand::random(),
+                    arena: self.versus_arenas[self.versus_arena].name.clone(),
+                    rules,
+                };
+                let setup =
+                    bincode::serialize(&setup).map_err(|e| format!("Setup error: {}", e))?;
+                let mut transport = UdpTransport::host(port)?;
+                let name = if self.player_name.is_empty() {
+                    "Player".to_string()
+                } else {
+                    self.player_name.clone()
+                };
+                transport.set_responder(answer_probes(LobbyInfo {
+                    name,
+                    details: format!(
+                        "{} | {}",
+                        self.versus_arenas[self.versus_arena].name,
+                        rules.summary()
+                    ),
+                }));
+                self.net_lobby.handshake = Some(Handshake::host(Box::new(transport), setup));
+                self.net_lobby.status = Some(format!("Waiting for a player on port {}...", port));
+            }
+            NetRole::Join => {
+                let address = if self.net_lobby.address.contains(':') {
+                    self.net_lobby.address.clone()
+                } else {
+                    format!("{}:{}", self.net_lobby.address, NET_DEFAULT_PORT)
+                };
+                let transport = UdpTransport::join(&address)?;
+                self.net_lobby.handshake = Some(Handshake::join(Box::new(transport)));
+                self.net_lobby.status = Some(format!("Connecting to {}...", address));
+            }
+        }
+        Ok(())
+    }
+
+    fn start_net_match(
+        &mut self,
+        transport: Box<dyn Transport>,
+        setup: &[u8],
+    ) -> Result<(), String> {
+        let setup: MatchSetup = bincode::deserialize(setup)
+            .map_err(|_| "The host sent a match this game can't read".to_string())?;
+        setup
+            .rules
+            .validate_online()
+            .map_err(|e| format!("The host sent a bad match: {e}"))?;
+        self.versus_arena = self
+            .versus_arenas
+            .iter()
+            .position(|arena| arena.name == setup.arena)
+            .ok_or_else(|| format!("Arena '{}' is missing on this machine", setup.arena))?;
+        self.versus_rules = setup.rules;
+        self.load_versus_map(setup.seed);
+        let seat = match self.net_lobby.role {
+            NetRole::Host => 0,
+            NetRole::Join => 1,
+        };
+        self.netplay = Some(NetMatch {
+            peer: NetPeer::new(transport, seat, self.net_lobby.input_delay),
+            seat,
+            accumulator: 0.0,
+            item_latched: false,
+        });
+        self.net_lobby.status = None;
+        self.net_lobby.browser = None;
+        self.versus_played = true;
+        self.tournament_playing = false;
+        // Straight in, without a fade: both sides must start on frame 0.
+        self.state = GameState::Versus;
+        Ok(())
+    }
+
+    /// Runs the online match at a fixed rate, with this machine's P1 keys
+    /// as the local input. ESC leaves, as does confirming on the end screen.
+    pub fn update_net_match(&mut self, dt: f32) {
+        let leave = is_key_pressed(KeyCode::Escape)
+            || (self.state == GameState::VersusEnd
+                && (is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space)));
+        if leave {
+            if let Some(seat) = self.netplay.as_ref().map(|net| net.seat) {
+                self.record_versus_match(seat);
+            }
+            self.leave_net_match();
+            self.state = GameState::Menu;
+            self.menu_selection = 0;
+            return;
+        }
+        let Some(mut net) = self.netplay.take() else {
+            return;
+        };
+        net.item_latched |=
+            self.is_control_just_pressed(&self.player1_controls, ControlAction::Item);
+        net.accumulator = (net.accumulator + dt).min(NET_TICK * NET_MAX_CATCH_UP as f32);
+        let mut result = Ok(());
+        while net.accumulator >= NET_TICK && result.is_ok() {
+            net.accumulator -= NET_TICK;
+            let mut input = self.read_versus_input(&self.player1_controls);
+            input.item = std::mem::take(&mut net.item_latched);
+            result = net.peer.tick(self, input.to_bits());
+        }
+        match result {
+            Ok(()) => {
+                // Record the result as soon as it's in, like offline, but
+                // only once no late input can roll the finish back.
+                let session = net.peer.session();
+                let settled = session.frame() <= session.confirmed_frame();
+                let seat = net.seat;
+                self.netplay = Some(net);
+                if settled {
+                    self.record_versus_match(seat);
+                }
+            }
+            Err(e) => {
+                // The result stays up after a finished match; otherwise
+                // there's nothing left to play.
+                self.record_versus_match(net.seat);
+                if self.state == GameState::Versus {
+                    self.state = GameState::Menu;
+                    self.menu_selection = 0;
+                }
+                self.show_error(e);
+            }
+        }
+    }
+
+    pub fn leave_net_match(&mut self) {
+        if let Some(mut net) = self.netplay.take() {
+            net.peer.quit();
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use super::versus_ai::AiDifficulty;
+use super::versus_fighter::MAX_VERSUS_PLAYERS;
+use super::versus_handicap::Handicap;
+use crate::constants::*;
+use serde::{Deserialize, Serialize};
+
+const TIME_LIMITS: [Option<f32>; 6] = [
+    Some(60.0),
+    Some(120.0),
+    Some(180.0),
+    Some(300.0),
+    Some(600.0),
+    None,
+];
+const STOMP_TARGETS: [Option<u32>; 5] = [None, Some(3), Some(5), Some(10), Some(15)];
+const STOCKS: [Option<u32>; 4] = [None, Some(1), Some(3), Some(5)];
+const BEST_OF: [u32; 3] = [1, 3, 5];
+const RESPAWN_DELAYS: [f32; 4] = [0.5, 1.0, 2.0, 3.0];
+const SPAWN_SHIELDS: [f32; 4] = [0.0, 1.0, 2.0, 3.0];
+const PLAYER_COUNTS: [usize; 3] = [2, 3, MAX_VERSUS_PLAYERS];
+const CPU_LEVELS: [AiDifficulty; 3] =
+    [AiDifficulty::Easy, AiDifficulty::Medium, AiDifficulty::Hard];
+const TEAM_MODES: [VersusTeams; 2] = [VersusTeams::FreeForAll, VersusTeams::TwoVsTwo];
+const MODES: [VersusMode; 4] = [
+    VersusMode::Deathmatch,
+    VersusMode::KingOfTheHill,
+    VersusMode::CoinRush,
+    VersusMode::CaptureTheCrab,
+];
+
+/// Number of rule rows on the Versus setup screen, before START.
+pub const VERSUS_RULE_ROWS: usize = 12;
+
+/// What players score besides stomps. Every mode except `Deathmatch` has an
+/// objective; the first team to its goal takes the round.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VersusMode {
+    Deathmatch,
+    /// Seconds spent alone in the hill zone.
+    KingOfTheHill,
+    /// Coins picked up; a stomp knocks some out of the victim.
+    CoinRush,
+    /// Seconds spent carrying the crab.
+    CaptureTheCrab,
+}
+
+impl VersusMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VersusMode::Deathmatch => "STOMP DEATHMATCH",
+            VersusMode::KingOfTheHill => "KING OF THE HILL",
+            VersusMode::CoinRush => "COIN RUSH",
+            VersusMode::CaptureTheCrab => "CAPTURE THE CRAB",
+        }
+    }
+
+    pub fn goal(&self) -> Option<f32> {
+        match self {
+            VersusMode::Deathmatch => None,
+            VersusMode::KingOfTheHill => Some(KING_OF_THE_HILL_GOAL),
+            VersusMode::CoinRush => Some(COIN_RUSH_GOAL),
+            VersusMode::CaptureTheCrab => Some(CAPTURE_THE_CRAB_GOAL),
+        }
+    }
+
+    /// Objective progress for the HUD, e.g. "12s" or "7 coins".
+    pub fn format_objective(&self, value: f32) -> String {
+        match self {
+            VersusMode::CoinRush => format!("{} coins", value as u32),
+            _ => format!("{}s", value as u32),
+        }
+    }
+
+    /// Match total for the end screen.
+    pub fn describe_total(&self, value: f32) -> String {
+        match self {
+            VersusMode::Deathmatch => String::new(),
+            VersusMode::KingOfTheHill => format!("{}s on the hill", value as u32),
+            VersusMode::CoinRush => format!("{} coins", value as u32),
+            VersusMode::CaptureTheCrab => format!("{}s with the crab", value as u32),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VersusTeams {
+    FreeForAll,
+    /// P1 and P3 against P2 and P4. Needs all four seats filled.
+    TwoVsTwo,
+}
+
+/// Moves `current` one step through `options`, wrapping at either end.
+pub fn step<T: PartialEq + Copy>(options: &[T], current: T, forward: bool) -> T {
+    let index = options.iter().position(|&o| o == current).unwrap_or(0);
+    let next = if forward {
+        (index + 1) % options.len()
+    } else {
+        (index + options.len() - 1) % options.len()
+    };
+    options[next]
+}
+
+/// How a Versus match is won. A round ends when the clock runs out, someone
+/// reaches the mode's goal or the stomp target, or someone runs out of
+/// stocks; the match goes to whoever takes the majority of `best_of` rounds.
+/// The last `cpu_players` seats are played by the computer.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VersusRules {
+    pub mode: VersusMode,
+    pub players: usize,
+    pub teams: VersusTeams,
+    pub cpu_players: usize,
+    pub cpu_level: AiDifficulty,
+    pub time_limit: Option<f32>,
+    pub stomp_target: Option<u32>,
+    pub stocks: Option<u32>,
+    pub best_of: u32,
+    pub respawn_delay: f32,
+    pub spawn_invulnerability: f32,
+    /// Item boxes spawn on the arena's platforms.
+    pub items: bool,
+    /// Per-seat adjustments, set on the handicap screen.
+    pub handicaps: [Handicap; MAX_VERSUS_PLAYERS],
+}
+
+impl Default for VersusRules {
+    fn default() -> Self {
+        Self {
+            mode: VersusMode::Deathmatch,
+            players: 2,
+            teams: VersusTeams::FreeForAll,
+            cpu_players: 0,
+            cpu_level: AiDifficulty::Medium,
+            time_limit: Some(600.0),
+            stomp_target: None,
+            stocks: None,
+            best_of: 1,
+            respawn_delay: 2.0,
+            spawn_invulnerability: 0.0,
+            items: false,
+            handicaps: [Handicap::default(); MAX_VERSUS_PLAYERS],
+        }
+    }
+}
+
+impl VersusRules {
+    pub fn rounds_to_win(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    pub fn team_count(&self) -> usize {
+        match self.teams {
+            VersusTeams::FreeForAll => self.players,
+            VersusTeams::TwoVsTwo => 2,
+        }
+    }
+
+    pub fn team_of(&self, slot: usize) -> usize {
+        match self.teams {
+            VersusTeams::FreeForAll => slot,
+            VersusTeams::TwoVsTwo => slot % 2,
+        }
+    }
+
+    /// Checks rules that came from somewhere other than the setup screen,
+    /// which never produces a seat count it can't seat.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(2..=MAX_VERSUS_PLAYERS).contains(&self.players) {
+            return Err(format!("{} players is not a valid match", self.players));
+        }
+        if self.cpu_players >= self.players {
+            return Err("A match needs at least one human player".to_string());
+        }
+        if self.teams == VersusTeams::TwoVsTwo && self.players != MAX_VERSUS_PLAYERS {
+            return Err("2 vs 2 needs all four seats filled".to_string());
+        }
+        Ok(())
+    }
+
+    /// `validate`, plus the one human per machine that online play needs.
+    pub fn validate_online(&self) -> Result<(), String> {
+        self.validate()?;
+        if self.players - self.cpu_players != 2 {
+            return Err("Online play needs exactly 2 human players".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn is_cpu(&self, slot: usize) -> bool {
+        slot >= self.players - self.cpu_players
+    }
+
+    pub fn cycle(&mut self, row: usize, forward: bool) {
+        match row {
+            0 => self.mode = step(&MODES, self.mode, forward),
+            1 => {
+                self.players = step(&PLAYER_COUNTS, self.players, forward);
+                self.cpu_players = self.cpu_players.min(self.players - 1);
+                if self.players != MAX_VERSUS_PLAYERS {
+                    self.teams = VersusTeams::FreeForAll;
+                }
+            }
+            2 if self.players == MAX_VERSUS_PLAYERS => {
+                self.teams = step(&TEAM_MODES, self.teams, forward)
+            }
+            3 => {
+                let counts: Vec<usize> = (0..self.players).collect();
+                self.cpu_players = step(&counts, self.cpu_players, forward);
+            }
+            4 => self.cpu_level = step(&CPU_LEVELS, self.cpu_level, forward),
+            5 => self.time_limit = step(&TIME_LIMITS, self.time_limit, forward),
+            6 => self.stomp_target = step(&STOMP_TARGETS, self.stomp_target, forward),
+            7 => self.stocks = step(&STOCKS, self.stocks, forward),
+            8 => self.best_of = step(&BEST_OF, self.best_of, forward),
+            9 => self.respawn_delay = step(&RESPAWN_DELAYS, self.respawn_delay, forward),
+            10 => {
+                self.spawn_invulnerability =
+                    step(&SPAWN_SHIELDS, self.spawn_invulnerability, forward)
+            }
+            11 => self.items = !self.items,
+            _ => {}
+        }
+    }
+
+    pub fn row_labels(&self) -> Vec<String> {
+        vec![
+            format!("GAME: {}", self.mode.label()),
+            format!("PLAYERS: {}", self.players),
+            match self.teams {
+                VersusTeams::FreeForAll => "TEAMS: FREE-FOR-ALL".to_string(),
+                VersusTeams::TwoVsTwo => "TEAMS: 2 VS 2".to_string(),
+            },
+            format!("CPU PLAYERS: {}", self.cpu_players),
+            format!("CPU LEVEL: {}", self.cpu_level.label()),
+            match self.time_limit {
+                Some(seconds) => format!("TIME LIMIT: {}s", seconds as u32),
+                None => "TIME LIMIT: OFF".to_string(),
+            },
+            match self.stomp_target {
+                Some(target) => format!("FIRST TO: {} STOMPS", target),
+                None => "FIRST TO: OFF".to_string(),
+            },
+            match self.stocks {
+                Some(stocks) => format!("STOCKS: {}", stocks),
+                None => "STOCKS: OFF".to_string(),
+            },
+            if self.best_of > 1 {
+                format!("ROUNDS: BEST OF {}", self.best_of)
+            } else {
+                "ROUNDS: SINGLE".to_string()
+            },
+            format!("RESPAWN DELAY: {:.1}s", self.respawn_delay),
+            if self.spawn_invulnerability > 0.0 {
+                format!("SPAWN SHIELD: {:.0}s", self.spawn_invulnerability)
+            } else {
+                "SPAWN SHIELD: OFF".to_string()
+            },
+            format!("ITEMS: {}", if self.items { "ON" } else { "OFF" }),
+        ]
+    }
+
+    /// One-line description for the end screen.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.mode.label().to_string()];
+        if self.teams == VersusTeams::TwoVsTwo {
+            parts.push("2 vs 2".to_string());
+        } else if self.players > 2 {
+            parts.push(format!("{}-way", self.players));
+        }
+        match self.time_limit {
+            Some(seconds) => parts.push(format!("{}s", seconds as u32)),
+            None => parts.push("No time limit".to_string()),
+        }
+        if let Some(target) = self.stomp_target {
+            parts.push(format!("First to {}", target));
+        }
+        if let Some(stocks) = self.stocks {
+            parts.push(format!("{} stocks", stocks));
+        }
+        if self.best_of > 1 {
+            parts.push(format!("Best of {}", self.best_of));
+        }
+        if self.items {
+            parts.push("Items".to_string());
+        }
+        if self.handicaps[..self.players]
+            .iter()
+            .any(|handicap| !handicap.is_neutral())
+        {
+            parts.push("Handicaps".to_string());
+        }
+        if self.cpu_players > 0 {
+            parts.push(format!(
+                "{} CPU ({})",
+                self.cpu_players,
+                self.cpu_level.label()
+            ));
+        }
+        parts.join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_rejects_seats_the_game_cannot_fill() {
+        let rules = VersusRules::default();
+        assert!(rules.validate().is_ok());
+        assert!(rules.validate_online().is_ok());
+
+        for players in [0, 1, MAX_VERSUS_PLAYERS + 1, usize::MAX] {
+            let bad = VersusRules { players, ..rules };
+            assert!(bad.validate().is_err(), "{} players", players);
+        }
+        let all_cpu = VersusRules {
+            players: 3,
+            cpu_players: 3,
+            ..rules
+        };
+        assert!(all_cpu.validate().is_err());
+        let short_teams = VersusRules {
+            players: 3,
+            teams: VersusTeams::TwoVsTwo,
+            ..rules
+        };
+        assert!(short_teams.validate().is_err());
+    }
+
+    #[test]
+    fn online_play_needs_two_humans() {
+        let three_humans = VersusRules {
+            players: 3,
+            ..VersusRules::default()
+        };
+        assert!(three_humans.validate().is_ok());
+        assert!(three_humans.validate_online().is_err());
+        let with_cpus = VersusRules {
+            players: MAX_VERSUS_PLAYERS,
+            cpu_players: 2,
+            ..VersusRules::default()
+        };
+        assert!(with_cpus.validate_online().is_ok());
+    }
+
+    #[test]
+    fn rule_rows_wrap_through_their_options() {
+        let mut rules = VersusRules::default();
+        assert_eq!(rules.time_limit, Some(600.0));
+        rules.cycle(5, true);
+        assert_eq!(rules.time_limit, None);
+        rules.cycle(5, true);
+        assert_eq!(rules.time_limit, Some(60.0));
+        rules.cycle(5, false);
+        assert_eq!(rules.time_limit, None);
+
+        rules.cycle(7, false);
+        assert_eq!(rules.stocks, Some(5));
+        rules.cycle(9, true);
+        assert_eq!(rules.respawn_delay, 3.0);
+        assert_eq!(rules.row_labels().len(), VERSUS_RULE_ROWS);
+    }
+
+    #[test]
+    fn best_of_needs_a_majority_of_rounds() {
+        for (best_of, needed) in [(1, 1), (3, 2), (5, 3)] {
+            let rules = VersusRules {
+                best_of,
+                ..VersusRules::default()
+            };
+            assert_eq!(rules.rounds_to_win(), needed);
+        }
+    }
+
+    #[test]
+    fn summary_lists_the_chosen_rules() {
+        let rules = VersusRules {
+            time_limit: None,
+            stomp_target: Some(5),
+            stocks: Some(3),
+            best_of: 3,
+            ..VersusRules::default()
+        };
+        assert_eq!(
+            rules.summary(),
+            "STOMP DEATHMATCH | No time limit | First to 5 | 3 stocks | Best of 3"
+        );
+        assert_eq!(VersusRules::default().summary(), "STOMP DEATHMATCH | 600s");
+    }
+
+    #[test]
+    fn player_count_wraps_between_the_limits() {
+        let mut rules = VersusRules::default();
+        rules.cycle(1, false);
+        assert_eq!(rules.players, MAX_VERSUS_PLAYERS);
+        rules.cycle(1, true);
+        assert_eq!(rules.players, 2);
+        for _ in 0..PLAYER_COUNTS.len() * 2 {
+            rules.cycle(1, true);
+            assert!((2..=MAX_VERSUS_PLAYERS).contains(&rules.players));
+            assert!(rules.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn two_vs_two_needs_every_seat() {
+        let mut rules = VersusRules::default();
+        rules.cycle(2, true);
+        assert!(rules.teams == VersusTeams::FreeForAll);
+        rules.players = MAX_VERSUS_PLAYERS;
+        rules.cycle(2, true);
+        assert!(rules.teams == VersusTeams::TwoVsTwo);
+        assert_eq!(rules.team_count(), 2);
+        let teams: Vec<usize> = (0..rules.players).map(|slot| rules.team_of(slot)).collect();
+        assert_eq!(teams, [0, 1, 0, 1]);
+
+        // Dropping a seat goes back to free-for-all.
+        rules.cycle(1, true);
+        assert!(rules.teams == VersusTeams::FreeForAll);
+        assert_eq!(rules.team_count(), rules.players);
+    }
+
+    #[test]
+    fn summary_only_counts_handicaps_of_seated_players() {
+        let mut rules = VersusRules::default();
+        rules.handicaps[MAX_VERSUS_PLAYERS - 1].head_start = 500;
+        assert!(!rules.summary().contains("Handicaps"));
+        rules.players = MAX_VERSUS_PLAYERS;
+        assert!(rules.summary().contains("Handicaps"));
+    }
+
+    #[test]
+    fn cpu_count_never_takes_every_seat() {
+        let mut rules = VersusRules {
+            players: MAX_VERSUS_PLAYERS,
+            ..VersusRules::default()
+        };
+        for _ in 0..MAX_VERSUS_PLAYERS * 2 {
+            rules.cycle(3, true);
+            assert!(rules.cpu_players < rules.players);
+            assert!(!rules.is_cpu(0));
+        }
+        rules.cpu_players = MAX_VERSUS_PLAYERS - 1;
+        let seats: Vec<bool> = (0..rules.players).map(|slot| rules.is_cpu(slot)).collect();
+        assert_eq!(seats, [false, true, true, true]);
+    }
+}
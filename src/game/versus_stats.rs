@@ -0,0 +1,90 @@
+use super::versus_fighter::VersusFighter;
+use super::{Game, GameState};
+use crate::versus_history::{MatchPlayer, MatchRecord, VersusHistory};
+use macroquad::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Records, head-to-head and recent matches.
+pub const STATS_PAGES: usize = 3;
+
+impl Game {
+    /// The name a fighter goes down in the history under: the profile name
+    /// for whoever played on this machine, a slot name for everyone else.
+    fn versus_record_name(&self, fighter: &VersusFighter, local_slot: usize) -> String {
+        if let Some(names) = self.tournament_match_names() {
+            names[fighter.slot].to_string()
+        } else if fighter.cpu.is_some() {
+            format!("CPU ({})", self.versus_rules.cpu_level.label())
+        } else if fighter.slot == local_slot && !self.player_name.is_empty() {
+            self.player_name.clone()
+        } else {
+            format!("Player {}", fighter.slot + 1)
+        }
+    }
+
+    /// Adds the match on the end screen to the history, once. `local_slot`
+    /// is the seat played from this machine.
+    pub fn record_versus_match(&mut self, local_slot: usize) {
+        if self.state != GameState::VersusEnd || self.versus_recorded {
+            return;
+        }
+        self.versus_recorded = true;
+        let record = MatchRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            arena: self
+                .versus_arenas
+                .get(self.versus_arena)
+                .map(|a| a.name.clone())
+                .unwrap_or_default(),
+            rules: self.versus_rules.summary(),
+            players: self
+                .versus_fighters
+                .iter()
+                .map(|fighter| MatchPlayer {
+                    name: self.versus_record_name(fighter, local_slot),
+                    team: fighter.team,
+                    cpu: fighter.cpu.is_some(),
+                    kills: fighter.kills,
+                    points: fighter.points,
+                    best_streak: fighter.best_streak,
+                })
+                .collect(),
+            winner: self.versus_match_winner(),
+        };
+        if let Err(e) = VersusHistory::append(record) {
+            self.show_error(e);
+        }
+    }
+
+    pub fn open_versus_stats(&mut self) {
+        match VersusHistory::load() {
+            Ok(history) => {
+                self.versus_history = history;
+                self.versus_stats_page = 0;
+                self.state = GameState::VersusStats;
+            }
+            Err(e) => self.show_error(e),
+        }
+    }
+
+    pub fn update_versus_stats(&mut self) {
+        if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
+            self.versus_stats_page = (self.versus_stats_page + STATS_PAGES - 1) % STATS_PAGES;
+            self.audio.play_menu_select();
+        }
+        if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
+            self.versus_stats_page = (self.versus_stats_page + 1) % STATS_PAGES;
+            self.audio.play_menu_select();
+        }
+        if is_key_pressed(KeyCode::Escape)
+            || is_key_pressed(KeyCode::Enter)
+            || is_key_pressed(KeyCode::Space)
+        {
+            self.audio.play_menu_select();
+            self.state = GameState::VersusSetup;
+        }
+    }
+}
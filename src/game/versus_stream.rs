@@ -0,0 +1,671 @@
+use super::versus_ai::VersusAi;
+use super::versus_fighter::{extra_slot_controls, VersusFighter};
+use super::versus_items::{FreezeTrap, ItemBox, Shell, VersusItem};
+use super::versus_layout::{VersusLayout, ARENA_DIR};
+use super::versus_modes::{Crab, Loose};
+use super::versus_rules::VersusRules;
+use super::{Game, GameState};
+use crate::constants::*;
+use crate::netplay::{read_recording, wire_options, StreamClient, StreamRecorder, StreamServer};
+use crate::save::get_save_dir;
+use bincode::Options;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Bumped whenever the broadcast format changes.
+const STREAM_VERSION: u32 = 3;
+/// Largest broadcast message a spectator will decode.
+const MAX_STREAM_MESSAGE: u64 = 64 * 1024;
+/// Seconds between broadcast frames.
+const STREAM_INTERVAL: f32 = 1.0 / 30.0;
+const RECORDING_DIR: &str = "recordings";
+const RECORDING_EXTENSION: &str = "jqr";
+
+const UNREADABLE: &str = "Unreadable broadcast";
+
+/// Rows on the broadcast screen.
+pub const BROADCAST_ROWS: usize = 6;
+
+#[derive(Serialize, Deserialize)]
+enum StreamMessage {
+    /// Sent when a match starts, and first to anyone who tunes in late.
+    Match {
+        version: u32,
+        arena: String,
+        rules: VersusRules,
+    },
+    Frame(Box<FrameView>),
+}
+
+impl StreamMessage {
+    fn encode(&self) -> Vec<u8> {
+        wire_options(MAX_STREAM_MESSAGE)
+            .serialize(self)
+            .unwrap_or_default()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        wire_options(MAX_STREAM_MESSAGE)
+            .deserialize(bytes)
+            .map_err(|_| UNREADABLE.to_string())
+    }
+}
+
+/// What a spectator needs to draw one fighter and their scoreboard line.
+#[derive(Serialize, Deserialize)]
+struct FighterView {
+    x: f32,
+    y: f32,
+    vel_x: f32,
+    vel_y: f32,
+    on_ground: bool,
+    facing_right: bool,
+    animation_frame: usize,
+    walk_bounce_timer: f32,
+    invulnerable_timer: f32,
+    slot: usize,
+    team: usize,
+    cpu: bool,
+    kills: u32,
+    round_kills: u32,
+    points: u32,
+    stocks: u32,
+    respawn_timer: f32,
+    out: bool,
+    objective: f32,
+    objective_total: f32,
+    item: Option<VersusItem>,
+    shield: bool,
+    speed_timer: f32,
+    frozen_timer: f32,
+}
+
+/// One broadcast frame of a running match, as seen through the host's camera.
+#[derive(Serialize, Deserialize)]
+struct FrameView {
+    /// Seconds since the match started, to pace recordings.
+    time: f32,
+    ended: bool,
+    camera: (f32, f32),
+    fighters: Vec<FighterView>,
+    team_rounds: Vec<u32>,
+    round: u32,
+    round_intro: f32,
+    round_result: Option<usize>,
+    time_remaining: f32,
+    coins: Vec<Loose>,
+    coin_rotation: f32,
+    crab: Crab,
+    item_boxes: Vec<ItemBox>,
+    shells: Vec<Shell>,
+    traps: Vec<FreezeTrap>,
+}
+
+impl FrameView {
+    /// Whether every seat, team and fighter index in the frame fits the
+    /// match `rules` set up, so applying it can't index out of bounds.
+    fn fits(&self, rules: &VersusRules) -> bool {
+        let teams = rules.team_count();
+        self.fighters.len() <= rules.players
+            && self
+                .fighters
+                .iter()
+                .all(|f| f.slot < rules.players && f.team < teams)
+            && self.team_rounds.len() == teams
+            && self.round_result.is_none_or(|team| team < teams)
+            && self
+                .crab
+                .carrier
+                .is_none_or(|carrier| carrier < self.fighters.len())
+            && self
+                .shells
+                .iter()
+                .all(|shell| shell.owner < self.fighters.len())
+    }
+}
+
+/// Broadcast screen settings, and the server and recorder they start.
+pub struct Broadcast {
+    pub port: String,
+    /// Every Versus match played from here on is saved for later viewing.
+    pub record: bool,
+    pub watch_address: String,
+    pub recordings: Vec<PathBuf>,
+    pub recording_selection: usize,
+    pub selection: usize,
+    pub status: Option<String>,
+    server: Option<StreamServer>,
+    recorder: Option<StreamRecorder>,
+    clock: f32,
+    send_timer: f32,
+}
+
+impl Default for Broadcast {
+    fn default() -> Self {
+        Self {
+            port: STREAM_DEFAULT_PORT.to_string(),
+            record: false,
+            watch_address: format!("127.0.0.1:{}", STREAM_DEFAULT_PORT),
+            recordings: Vec::new(),
+            recording_selection: 0,
+            selection: 0,
+            status: None,
+            server: None,
+            recorder: None,
+            clock: 0.0,
+            send_timer: 0.0,
+        }
+    }
+}
+
+impl Broadcast {
+    pub fn live(&self) -> bool {
+        self.server.is_some()
+    }
+
+    pub fn spectator_count(&self) -> usize {
+        self.server.as_ref().map_or(0, |s| s.spectator_count())
+    }
+}
+
+fn recording_dir() -> Result<PathBuf, String> {
+    Ok(get_save_dir()?.join(RECORDING_DIR))
+}
+
+/// Recordings in `dir`, newest first.
+fn list_recordings(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == RECORDING_EXTENSION)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths.reverse();
+    paths
+}
+
+pub fn recording_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+enum Source {
+    Live(StreamClient),
+    Recording {
+        messages: Vec<StreamMessage>,
+        clock: f32,
+        paused: bool,
+    },
+}
+
+/// A match being watched, live or from a recording. Nothing is simulated;
+/// every frame is taken as it arrives.
+pub struct Spectating {
+    source: Source,
+    /// Where the match comes from, for the HUD.
+    pub label: String,
+    /// The match setup has arrived and frames can be drawn.
+    pub watching: bool,
+    pub ended: bool,
+    /// Why nothing new is coming, once that's the case.
+    pub notice: Option<String>,
+}
+
+impl Spectating {
+    pub fn is_recording(&self) -> bool {
+        matches!(self.source, Source::Recording { .. })
+    }
+
+    pub fn paused(&self) -> bool {
+        matches!(self.source, Source::Recording { paused: true, .. })
+    }
+}
+
+impl Game {
+    pub fn open_broadcast_menu(&mut self) {
+        let (arenas, _) = VersusLayout::load_all(Path::new(ARENA_DIR));
+        self.versus_arenas = arenas;
+        self.broadcast.recordings = recording_dir()
+            .map(|dir| list_recordings(&dir))
+            .unwrap_or_default();
+        self.broadcast.recording_selection = 0;
+        self.broadcast.status = None;
+        self.state = GameState::BroadcastSetup;
+    }
+
+    pub fn update_broadcast_menu(&mut self) {
+        let typed: Vec<char> = std::iter::from_fn(get_char_pressed).collect();
+        // Letters go into the address, so only the arrow keys navigate.
+        if is_key_pressed(KeyCode::Up) && self.broadcast.selection > 0 {
+            self.broadcast.selection -= 1;
+            self.audio.play_menu_select();
+        }
+        if is_key_pressed(KeyCode::Down) && self.broadcast.selection < BROADCAST_ROWS - 1 {
+            self.broadcast.selection += 1;
+            self.audio.play_menu_select();
+        }
+        let left = is_key_pressed(KeyCode::Left);
+        let right = is_key_pressed(KeyCode::Right);
+        let confirm = is_key_pressed(KeyCode::Enter);
+        let broadcast = &mut self.broadcast;
+        match broadcast.selection {
+            0 if left || right || confirm => {
+                broadcast.status = None;
+                if broadcast.server.take().is_none() {
+                    match broadcast.port.parse() {
+                        Ok(port) => match StreamServer::bind(port) {
+                            Ok(server) => broadcast.server = Some(server),
+                            Err(e) => broadcast.status = Some(e),
+                        },
+                        Err(_) => {
+                            broadcast.status = Some(format!("'{}' is not a port", broadcast.port))
+                        }
+                    }
+                }
+                self.audio.play_menu_select();
+            }
+            1 if !broadcast.live() => {
+                for ch in typed {
+                    if ch.is_ascii_digit() && broadcast.port.len() < 5 {
+                        broadcast.port.push(ch);
+                    }
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    broadcast.port.pop();
+                }
+            }
+            2 if left || right || confirm => {
+                broadcast.record = !broadcast.record;
+                self.audio.play_menu_select();
+            }
+            3 => {
+                for ch in typed {
+                    if ch.is_ascii_graphic() && broadcast.watch_address.len() < 64 {
+                        broadcast.watch_address.push(ch);
+                    }
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    broadcast.watch_address.pop();
+                }
+                if confirm {
+                    self.audio.play_menu_select();
+                    self.watch_live();
+                }
+            }
+            4 if !broadcast.recordings.is_empty() && (left || right || confirm) => {
+                let count = broadcast.recordings.len();
+                if left {
+                    broadcast.recording_selection =
+                        (broadcast.recording_selection + count - 1) % count;
+                } else if right {
+                    broadcast.recording_selection = (broadcast.recording_selection + 1) % count;
+                }
+                self.audio.play_menu_select();
+                if confirm {
+                    self.watch_recording();
+                }
+            }
+            5 if confirm => {
+                self.audio.play_menu_select();
+                self.state = GameState::VersusSetup;
+            }
+            _ => {}
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.state = GameState::VersusSetup;
+        }
+    }
+
+    fn watch_live(&mut self) {
+        let address = if self.broadcast.watch_address.contains(':') {
+            self.broadcast.watch_address.clone()
+        } else {
+            format!("{}:{}", self.broadcast.watch_address, STREAM_DEFAULT_PORT)
+        };
+        match StreamClient::connect(&address) {
+            Ok(client) => self.start_spectating(Source::Live(client), address),
+            Err(e) => self.broadcast.status = Some(e),
+        }
+    }
+
+    fn watch_recording(&mut self) {
+        let Some(path) = self
+            .broadcast
+            .recordings
+            .get(self.broadcast.recording_selection)
+        else {
+            return;
+        };
+        let label = recording_name(path);
+        let messages = read_recording(path).and_then(|frames| {
+            frames
+                .iter()
+                .map(|frame| StreamMessage::decode(frame))
+                .collect::<Result<Vec<_>, _>>()
+        });
+        match messages {
+            Ok(messages) => {
+                let source = Source::Recording {
+                    messages,
+                    clock: 0.0,
+                    paused: false,
+                };
+                self.start_spectating(source, label);
+            }
+            Err(e) => self.broadcast.status = Some(e),
+        }
+    }
+
+    fn start_spectating(&mut self, source: Source, label: String) {
+        self.spectating = Some(Spectating {
+            source,
+            label,
+            watching: false,
+            ended: false,
+            notice: None,
+        });
+        self.broadcast.status = None;
+        self.state = GameState::Spectate;
+    }
+
+    pub fn update_spectate(&mut self, dt: f32) {
+        if is_key_pressed(KeyCode::Escape) {
+            self.spectating = None;
+            self.audio.play_menu_select();
+            self.open_broadcast_menu();
+            return;
+        }
+        let Some(mut spectating) = self.spectating.take() else {
+            return;
+        };
+        let mut arrived = Vec::new();
+        match &mut spectating.source {
+            Source::Live(client) => match client.poll() {
+                Ok(frames) => {
+                    for frame in frames {
+                        match StreamMessage::decode(&frame) {
+                            Ok(message) => arrived.push(message),
+                            Err(e) => spectating.notice = Some(e),
+                        }
+                    }
+                }
+                Err(e) => {
+                    if spectating.notice.is_none() {
+                        spectating.notice = Some(e);
+                    }
+                }
+            },
+            Source::Recording {
+                messages,
+                clock,
+                paused,
+            } => {
+                if is_key_pressed(KeyCode::Space) {
+                    *paused = !*paused;
+                }
+                if !*paused {
+                    *clock += dt;
+                }
+                // Everything up to the clock is due; only the last frame
+                // matters, but the match start has to be applied.
+                let due = messages
+                    .iter()
+                    .position(|m| matches!(m, StreamMessage::Frame(view) if view.time > *clock))
+                    .unwrap_or(messages.len());
+                let start = arrived_start(messages, due);
+                arrived.extend(messages.drain(..due).skip(start));
+                if messages.is_empty() {
+                    spectating.notice = Some("End of recording".to_string());
+                }
+            }
+        }
+        for message in arrived {
+            if let Err(e) = self.apply_stream_message(message, &mut spectating) {
+                spectating.notice = Some(e);
+            }
+        }
+        self.spectating = Some(spectating);
+    }
+
+    fn apply_stream_message(
+        &mut self,
+        message: StreamMessage,
+        spectating: &mut Spectating,
+    ) -> Result<(), String> {
+        match message {
+            StreamMessage::Match {
+                version,
+                arena,
+                rules,
+            } => {
+                if version != STREAM_VERSION {
+                    return Err("This broadcast is from a different version".to_string());
+                }
+                rules.validate().map_err(|_| UNREADABLE.to_string())?;
+                self.versus_arena = self
+                    .versus_arenas
+                    .iter()
+                    .position(|a| a.name == arena)
+                    .ok_or_else(|| format!("Arena '{}' is missing on this machine", arena))?;
+                self.versus_rules = rules;
+                self.load_versus_map(0);
+                spectating.watching = true;
+                spectating.ended = false;
+            }
+            StreamMessage::Frame(view) if spectating.watching => {
+                if !view.fits(&self.versus_rules) {
+                    return Err(UNREADABLE.to_string());
+                }
+                spectating.ended = view.ended;
+                self.apply_frame_view(*view);
+            }
+            StreamMessage::Frame(_) => {}
+        }
+        Ok(())
+    }
+
+    fn apply_frame_view(&mut self, view: FrameView) {
+        self.camera.x = view.camera.0;
+        self.camera.y = view.camera.1;
+        self.versus_fighters.truncate(view.fighters.len());
+        let cpu_level = self.versus_rules.cpu_level;
+        for (index, seen) in view.fighters.into_iter().enumerate() {
+            if index == self.versus_fighters.len() {
+                let player = self.new_player(0.0, 0.0);
+                let controls = extra_slot_controls(seen.slot);
+                let fighter = VersusFighter::new(player, seen.slot, seen.team, controls, None);
+                self.versus_fighters.push(fighter);
+            }
+            let fighter = &mut self.versus_fighters[index];
+            if fighter.cpu.is_some() != seen.cpu {
+                fighter.cpu = seen.cpu.then(|| VersusAi::new(cpu_level));
+            }
+            let player = &mut fighter.player;
+            player.x = seen.x;
+            player.y = seen.y;
+            player.vel_x = seen.vel_x;
+            player.vel_y = seen.vel_y;
+            player.on_ground = seen.on_ground;
+            player.facing_right = seen.facing_right;
+            player.animation_frame = seen.animation_frame;
+            player.walk_bounce_timer = seen.walk_bounce_timer;
+            player.invulnerable_timer = seen.invulnerable_timer;
+            fighter.slot = seen.slot;
+            fighter.team = seen.team;
+            fighter.kills = seen.kills;
+            fighter.round_kills = seen.round_kills;
+            fighter.points = seen.points;
+            fighter.stocks = seen.stocks;
+            fighter.respawn_timer = seen.respawn_timer;
+            fighter.out = seen.out;
+            fighter.objective = seen.objective;
+            fighter.objective_total = seen.objective_total;
+            fighter.item = seen.item;
+            fighter.shield = seen.shield;
+            fighter.speed_timer = seen.speed_timer;
+            fighter.frozen_timer = seen.frozen_timer;
+        }
+        self.versus_team_rounds = view.team_rounds;
+        self.versus_round = view.round;
+        self.versus_round_intro = view.round_intro;
+        self.versus_round_result = view.round_result;
+        self.versus_time_remaining = view.time_remaining;
+        self.versus_coins = view.coins;
+        self.versus_coin_rotation = view.coin_rotation;
+        self.versus_crab = view.crab;
+        self.versus_item_boxes = view.item_boxes;
+        self.versus_shells = view.shells;
+        self.versus_traps = view.traps;
+    }
+
+    fn frame_view(&self) -> FrameView {
+        FrameView {
+            time: self.broadcast.clock,
+            ended: self.state == GameState::VersusEnd,
+            camera: (self.camera.x, self.camera.y),
+            fighters: self
+                .versus_fighters
+                .iter()
+                .map(|fighter| {
+                    let player = &fighter.player;
+                    FighterView {
+                        x: player.x,
+                        y: player.y,
+                        vel_x: player.vel_x,
+                        vel_y: player.vel_y,
+                        on_ground: player.on_ground,
+                        facing_right: player.facing_right,
+                        animation_frame: player.animation_frame,
+                        walk_bounce_timer: player.walk_bounce_timer,
+                        invulnerable_timer: player.invulnerable_timer,
+                        slot: fighter.slot,
+                        team: fighter.team,
+                        cpu: fighter.cpu.is_some(),
+                        kills: fighter.kills,
+                        round_kills: fighter.round_kills,
+                        points: fighter.points,
+                        stocks: fighter.stocks,
+                        respawn_timer: fighter.respawn_timer,
+                        out: fighter.out,
+                        objective: fighter.objective,
+                        objective_total: fighter.objective_total,
+                        item: fighter.item,
+                        shield: fighter.shield,
+                        speed_timer: fighter.speed_timer,
+                        frozen_timer: fighter.frozen_timer,
+                    }
+                })
+                .collect(),
+            team_rounds: self.versus_team_rounds.clone(),
+            round: self.versus_round,
+            round_intro: self.versus_round_intro,
+            round_result: self.versus_round_result,
+            time_remaining: self.versus_time_remaining,
+            coins: self.versus_coins.clone(),
+            coin_rotation: self.versus_coin_rotation,
+            crab: self.versus_crab.clone(),
+            item_boxes: self.versus_item_boxes.clone(),
+            shells: self.versus_shells.clone(),
+            traps: self.versus_traps.clone(),
+        }
+    }
+
+    /// Called when a Versus match starts: tells spectators about it and
+    /// opens a recording file if recording is on.
+    pub fn begin_versus_stream(&mut self) {
+        if self.state == GameState::Spectate {
+            return;
+        }
+        self.broadcast.clock = 0.0;
+        self.broadcast.send_timer = 0.0;
+        self.broadcast.recorder = None;
+        let arena = self
+            .versus_arenas
+            .get(self.versus_arena)
+            .map(|a| a.name.clone())
+            .unwrap_or_default();
+        let intro = StreamMessage::Match {
+            version: STREAM_VERSION,
+            arena,
+            rules: self.versus_rules,
+        }
+        .encode();
+        if let Some(server) = self.broadcast.server.as_mut() {
+            server.set_intro(&intro);
+        }
+        if self.broadcast.record {
+            let recorder = recording_dir().and_then(|dir| {
+                fs::create_dir_all(&dir)
+                    .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+                let stamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let path = dir.join(format!("versus-{}.{}", stamp, RECORDING_EXTENSION));
+                let mut recorder = StreamRecorder::create(&path)?;
+                recorder.write(&intro)?;
+                Ok(recorder)
+            });
+            match recorder {
+                Ok(recorder) => self.broadcast.recorder = Some(recorder),
+                Err(e) => self.show_error(e),
+            }
+        }
+    }
+
+    /// Sends the running match to spectators and the recording. Runs every
+    /// frame; outside a match it only lets new spectators in.
+    pub fn update_versus_stream(&mut self, dt: f32) {
+        let in_match = matches!(self.state, GameState::Versus | GameState::VersusEnd)
+            || (self.state == GameState::Pause && self.pause_origin == GameState::Versus);
+        if !in_match {
+            // Leaving the match finishes its recording.
+            self.broadcast.recorder = None;
+            if let Some(server) = self.broadcast.server.as_mut() {
+                server.accept();
+            }
+            return;
+        }
+        if self.broadcast.server.is_none() && self.broadcast.recorder.is_none() {
+            return;
+        }
+        self.broadcast.clock += dt;
+        self.broadcast.send_timer -= dt;
+        if self.broadcast.send_timer > 0.0 {
+            return;
+        }
+        self.broadcast.send_timer = STREAM_INTERVAL;
+        let frame = StreamMessage::Frame(Box::new(self.frame_view())).encode();
+        if let Some(server) = self.broadcast.server.as_mut() {
+            server.broadcast(&frame);
+        }
+        if let Some(recorder) = self.broadcast.recorder.as_mut() {
+            if let Err(e) = recorder.write(&frame) {
+                self.broadcast.recorder = None;
+                self.show_error(e);
+            }
+        }
+    }
+}
+
+/// Index of the last match start before `end`, or of the last frame when
+/// there isn't one, so a catch-up only applies what's still visible.
+fn arrived_start(messages: &[StreamMessage], end: usize) -> usize {
+    let shown = &messages[..end];
+    match shown
+        .iter()
+        .rposition(|m| matches!(m, StreamMessage::Match { .. }))
+    {
+        Some(start) => start,
+        None => end.saturating_sub(1),
+    }
+}
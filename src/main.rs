@@ -8,10 +8,13 @@ pub mod constants;
 mod enemy;
 mod game;
 mod name_filter;
+mod netplay;
 mod platform;
 mod player;
 mod save;
 mod systems;
+mod trigger;
+mod versus_history;
 
 fn window_conf() -> Conf {
     Conf {
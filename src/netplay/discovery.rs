@@ -0,0 +1,206 @@
+use super::transport::Responder;
+use super::{NetMessage, PROTOCOL_VERSION};
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::Instant;
+
+/// Seconds between probes while browsing.
+const PROBE_INTERVAL: f32 = 1.0;
+/// Seconds a lobby stays listed after its last answer.
+const LOBBY_EXPIRY: f32 = 3.5;
+/// Probes kept around to time the answers against.
+const PROBES_KEPT: usize = 4;
+const MAX_NAME_LEN: usize = 20;
+const MAX_DETAILS_LEN: usize = 80;
+
+/// What a host tells joiners about its lobby.
+#[derive(Clone)]
+pub struct LobbyInfo {
+    pub name: String,
+    /// Arena and rules, already formatted for the lobby list.
+    pub details: String,
+}
+
+/// Responder for a hosting socket that answers LAN probes with `lobby`.
+pub fn answer_probes(lobby: LobbyInfo) -> Responder {
+    let token = rand::random();
+    Box::new(move |packet| match NetMessage::decode(packet) {
+        Some(NetMessage::Probe { probe }) => Some(
+            NetMessage::Announce {
+                probe,
+                token,
+                version: PROTOCOL_VERSION,
+                name: lobby.name.clone(),
+                details: lobby.details.clone(),
+            }
+            .encode(),
+        ),
+        _ => None,
+    })
+}
+
+/// An open lobby that answered a probe.
+pub struct LanLobby {
+    pub address: SocketAddr,
+    pub name: String,
+    pub details: String,
+    /// Round trip of the latest answer, in milliseconds.
+    pub ping_ms: u32,
+    token: u64,
+    /// Seconds since the last answer.
+    age: f32,
+}
+
+/// Finds lobbies on the local network. Every second it broadcasts a probe
+/// to each lobby port, and sends one to this machine too so several copies
+/// of the game can find each other without a network. Hosts answer from
+/// their game socket, so the reply address is where to connect.
+pub struct LanBrowser {
+    socket: UdpSocket,
+    ports: Vec<u16>,
+    next_probe: u32,
+    probes: Vec<(u32, Instant)>,
+    probe_timer: f32,
+    lobbies: Vec<LanLobby>,
+}
+
+impl LanBrowser {
+    pub fn new(ports: Vec<u16>) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("Socket error: {}", e))?;
+        socket
+            .set_broadcast(true)
+            .and_then(|_| socket.set_nonblocking(true))
+            .map_err(|e| format!("Socket error: {}", e))?;
+        Ok(Self {
+            socket,
+            ports,
+            next_probe: 0,
+            probes: Vec::new(),
+            probe_timer: 0.0,
+            lobbies: Vec::new(),
+        })
+    }
+
+    /// Lobbies heard from recently, in the order they were found.
+    pub fn lobbies(&self) -> &[LanLobby] {
+        &self.lobbies
+    }
+
+    pub fn update(&mut self, dt: f32) -> Result<(), String> {
+        self.probe_timer -= dt;
+        if self.probe_timer <= 0.0 {
+            self.probe_timer = PROBE_INTERVAL;
+            self.send_probes();
+        }
+        for lobby in &mut self.lobbies {
+            lobby.age += dt;
+        }
+        let mut buffer = [0u8; 2048];
+        loop {
+            let (size, from) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(format!("Receive failed: {}", e)),
+            };
+            if let Some(NetMessage::Announce {
+                probe,
+                token,
+                version,
+                name,
+                details,
+            }) = NetMessage::decode(&buffer[..size])
+            {
+                if version == PROTOCOL_VERSION {
+                    self.record(from, probe, token, name, details);
+                }
+            }
+        }
+        self.lobbies.retain(|lobby| lobby.age < LOBBY_EXPIRY);
+        Ok(())
+    }
+
+    fn send_probes(&mut self) {
+        let probe = self.next_probe;
+        self.next_probe = self.next_probe.wrapping_add(1);
+        self.probes.push((probe, Instant::now()));
+        if self.probes.len() > PROBES_KEPT {
+            self.probes.remove(0);
+        }
+        let packet = NetMessage::Probe { probe }.encode();
+        for &port in &self.ports {
+            for ip in [Ipv4Addr::BROADCAST, Ipv4Addr::LOCALHOST] {
+                // No network or no broadcast route just means there's
+                // nothing to find that way.
+                let _ = self.socket.send_to(&packet, (ip, port));
+            }
+        }
+    }
+
+    fn record(&mut self, from: SocketAddr, probe: u32, token: u64, name: String, details: String) {
+        let Some(&(_, sent)) = self.probes.iter().find(|(id, _)| *id == probe) else {
+            return;
+        };
+        let ping_ms = sent.elapsed().as_millis() as u32;
+        let name: String = name.chars().take(MAX_NAME_LEN).collect();
+        let details: String = details.chars().take(MAX_DETAILS_LEN).collect();
+        match self.lobbies.iter_mut().find(|lobby| lobby.token == token) {
+            // The same host heard over a second route keeps its first address.
+            Some(lobby) => {
+                lobby.name = name;
+                lobby.details = details;
+                lobby.ping_ms = ping_ms;
+                lobby.age = 0.0;
+            }
+            None => self.lobbies.push(LanLobby {
+                address: from,
+                name,
+                details,
+                ping_ms,
+                token,
+                age: 0.0,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Transport, UdpTransport};
+    use super::*;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    #[test]
+    fn browser_finds_host_on_loopback() {
+        let port = 47911;
+        let mut host = UdpTransport::host(port).unwrap();
+        host.set_responder(answer_probes(LobbyInfo {
+            name: "Ferris".to_string(),
+            details: "Classic | 3 stocks".to_string(),
+        }));
+        let mut browser = LanBrowser::new(vec![port]).unwrap();
+        for _ in 0..100 {
+            browser.update(0.05).unwrap();
+            // Probes are answered, never handed to the match.
+            assert!(host.poll().unwrap().is_empty());
+            if !browser.lobbies().is_empty() {
+                break;
+            }
+            sleep(Duration::from_millis(5));
+        }
+        sleep(Duration::from_millis(20));
+        browser.update(0.05).unwrap();
+        assert_eq!(browser.lobbies().len(), 1);
+        let lobby = &browser.lobbies()[0];
+        assert_eq!(lobby.name, "Ferris");
+        assert_eq!(lobby.details, "Classic | 3 stocks");
+        assert_eq!(lobby.address.port(), port);
+
+        // The seat is still free for a real guest.
+        let mut guest = UdpTransport::join(&format!("127.0.0.1:{}", port)).unwrap();
+        guest.send(b"hello").unwrap();
+        sleep(Duration::from_millis(20));
+        assert_eq!(host.poll().unwrap(), vec![b"hello".to_vec()]);
+    }
+}
@@ -0,0 +1,400 @@
+//! Networking for Versus: two-player online play (a packet transport,
+//! rollback on top of it, the handshake that starts a match and LAN
+//! discovery) and one-way match broadcasts for spectators. Nothing in here
+//! knows about Versus; the game hands over its data as bytes and implements
+//! `RollbackSim` for the simulation itself.
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+mod discovery;
+mod rollback;
+mod stream;
+mod transport;
+
+pub use discovery::{answer_probes, LanBrowser, LanLobby, LobbyInfo};
+pub use rollback::{RollbackSession, RollbackSim};
+pub use stream::{read_recording, StreamClient, StreamRecorder, StreamServer};
+pub use transport::{Transport, UdpTransport};
+
+/// Bumped whenever the packets or the simulation change, so different
+/// builds refuse to play each other instead of desyncing.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Online matches always step at this rate, whatever the frame rate.
+pub const TICK_RATE: f32 = 60.0;
+/// Seconds without a packet before the connection counts as lost.
+const TIMEOUT: f32 = 5.0;
+/// Seconds a guest waits for the host to answer.
+const JOIN_TIMEOUT: f32 = 10.0;
+/// Seconds between handshake resends.
+const HANDSHAKE_RESEND: f32 = 0.25;
+const MAX_MESSAGE_SIZE: u64 = 2048;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum NetMessage {
+    /// Guest looking for a host.
+    Hello,
+    /// Host's answer: the match to play, encoded by the game.
+    Welcome {
+        version: u32,
+        setup: Vec<u8>,
+    },
+    /// Guest has the setup and is starting.
+    Ready,
+    /// Sender's inputs from frame `start` on, and how many of the
+    /// receiver's inputs it has.
+    Inputs {
+        start: u32,
+        inputs: Vec<u8>,
+        ack: u32,
+    },
+    Checksum {
+        frame: u32,
+        value: u64,
+    },
+    Quit,
+    /// LAN search, answered by every open lobby that hears it.
+    Probe {
+        probe: u32,
+    },
+    /// A lobby's answer to `Probe`. `token` tells apart answers from the
+    /// same host arriving over different routes.
+    Announce {
+        probe: u32,
+        token: u64,
+        version: u32,
+        name: String,
+        details: String,
+    },
+}
+
+/// The encoding for everything sent over the network. Decoding gives up
+/// once `limit` bytes would be allocated, so a hostile length prefix can't
+/// exhaust memory.
+pub fn wire_options(limit: u64) -> impl Options {
+    bincode::DefaultOptions::new().with_limit(limit)
+}
+
+impl NetMessage {
+    fn options() -> impl Options {
+        wire_options(MAX_MESSAGE_SIZE)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        Self::options().serialize(self).unwrap_or_default()
+    }
+
+    /// `None` for anything that isn't one of our packets.
+    pub fn decode(packet: &[u8]) -> Option<Self> {
+        Self::options().deserialize(packet).ok()
+    }
+}
+
+fn send(transport: &mut dyn Transport, message: &NetMessage) -> Result<(), String> {
+    transport.send(&message.encode())
+}
+
+/// The connection and the match setup, once a handshake completes.
+pub type Agreed = (Box<dyn Transport>, Vec<u8>);
+
+/// Gets two instances to agree on a match before it starts. The guest says
+/// hello until the host answers with the setup; the host keeps offering it
+/// until the guest confirms.
+pub struct Handshake {
+    transport: Option<Box<dyn Transport>>,
+    /// The host's setup to offer; `None` on the guest.
+    setup: Option<Vec<u8>>,
+    guest_seen: bool,
+    resend_timer: f32,
+    waited: f32,
+}
+
+impl Handshake {
+    pub fn host(transport: Box<dyn Transport>, setup: Vec<u8>) -> Self {
+        Self {
+            transport: Some(transport),
+            setup: Some(setup),
+            guest_seen: false,
+            resend_timer: 0.0,
+            waited: 0.0,
+        }
+    }
+
+    pub fn join(transport: Box<dyn Transport>) -> Self {
+        Self {
+            transport: Some(transport),
+            setup: None,
+            guest_seen: false,
+            resend_timer: 0.0,
+            waited: 0.0,
+        }
+    }
+
+    /// A guest has turned up and is being sent the setup.
+    pub fn guest_seen(&self) -> bool {
+        self.guest_seen
+    }
+
+    /// Called every frame. Once both sides agree, hands back the transport
+    /// and the setup to start the match with.
+    pub fn update(&mut self, dt: f32) -> Result<Option<Agreed>, String> {
+        let Some(transport) = self.transport.as_mut() else {
+            return Err("Handshake already finished".to_string());
+        };
+        self.waited += dt;
+        self.resend_timer -= dt;
+        let mut agreed = None;
+        for packet in transport.poll()? {
+            match (NetMessage::decode(&packet), &self.setup) {
+                (Some(NetMessage::Hello), Some(_)) => {
+                    self.guest_seen = true;
+                    self.resend_timer = 0.0;
+                }
+                (Some(NetMessage::Ready | NetMessage::Inputs { .. }), Some(setup)) => {
+                    agreed = Some(setup.clone());
+                }
+                (Some(NetMessage::Welcome { version, setup }), None) => {
+                    if version != PROTOCOL_VERSION {
+                        return Err(format!(
+                            "The host is running a different version ({} vs {})",
+                            version, PROTOCOL_VERSION
+                        ));
+                    }
+                    send(transport.as_mut(), &NetMessage::Ready)?;
+                    agreed = Some(setup);
+                }
+                _ => {}
+            }
+        }
+        if let Some(setup) = agreed {
+            return Ok(self.transport.take().map(|transport| (transport, setup)));
+        }
+        if self.setup.is_none() && self.waited > JOIN_TIMEOUT {
+            return Err("No answer from the host".to_string());
+        }
+        if self.resend_timer <= 0.0 {
+            self.resend_timer = HANDSHAKE_RESEND;
+            match &self.setup {
+                Some(setup) if self.guest_seen => {
+                    let welcome = NetMessage::Welcome {
+                        version: PROTOCOL_VERSION,
+                        setup: setup.clone(),
+                    };
+                    send(transport.as_mut(), &welcome)?;
+                }
+                Some(_) => {}
+                None => send(transport.as_mut(), &NetMessage::Hello)?,
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// One side of a running match: the rollback session and the connection
+/// it talks over.
+pub struct NetPeer<S> {
+    session: RollbackSession<S>,
+    transport: Box<dyn Transport>,
+    silence: f32,
+}
+
+impl<S: Clone> NetPeer<S> {
+    pub fn new(transport: Box<dyn Transport>, local_seat: usize, input_delay: u32) -> Self {
+        Self {
+            session: RollbackSession::new(local_seat, input_delay),
+            transport,
+            silence: 0.0,
+        }
+    }
+
+    pub fn session(&self) -> &RollbackSession<S> {
+        &self.session
+    }
+
+    /// One fixed tick: trades packets with the peer, rolls back if a
+    /// prediction was wrong and simulates the next frame with
+    /// `local_input`. Fails once the peer leaves, goes quiet or desyncs.
+    pub fn tick<G: RollbackSim<State = S>>(
+        &mut self,
+        sim: &mut G,
+        local_input: u8,
+    ) -> Result<(), String> {
+        let packets = self.transport.poll()?;
+        if packets.is_empty() {
+            self.silence += 1.0 / TICK_RATE;
+            if self.silence > TIMEOUT {
+                return Err("Connection lost".to_string());
+            }
+        } else {
+            self.silence = 0.0;
+        }
+        for packet in packets {
+            match NetMessage::decode(&packet) {
+                Some(NetMessage::Inputs { start, inputs, ack }) => {
+                    self.session.receive_inputs(start, &inputs, ack)
+                }
+                Some(NetMessage::Checksum { frame, value }) => {
+                    self.session.receive_checksum(frame, value)
+                }
+                Some(NetMessage::Quit) => return Err("The other player left".to_string()),
+                // Our Ready got lost and the host is still offering the match.
+                Some(NetMessage::Welcome { .. }) => {
+                    send(self.transport.as_mut(), &NetMessage::Ready)?
+                }
+                _ => {}
+            }
+        }
+        self.session.add_local_input(local_input);
+        self.session.advance(sim);
+        let (start, inputs, ack) = self.session.outgoing_inputs();
+        send(
+            self.transport.as_mut(),
+            &NetMessage::Inputs { start, inputs, ack },
+        )?;
+        for (frame, value) in self.session.take_checksums() {
+            send(
+                self.transport.as_mut(),
+                &NetMessage::Checksum { frame, value },
+            )?;
+        }
+        match self.session.desync_frame() {
+            Some(frame) => Err(format!("Desync detected at frame {}", frame)),
+            None => Ok(()),
+        }
+    }
+
+    /// Tells the peer we're leaving. Sent a few times since any one packet
+    /// may be lost.
+    pub fn quit(&mut self) {
+        for _ in 0..3 {
+            let _ = send(self.transport.as_mut(), &NetMessage::Quit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transport::LoopbackTransport;
+    use super::*;
+
+    /// Two counters driven by the inputs, plus a record of the state after
+    /// every frame so both sides' histories can be compared.
+    #[derive(Clone, Default)]
+    struct Toy {
+        totals: [u64; 2],
+        history: Vec<u64>,
+        /// Makes this side's simulation differ, to provoke a desync.
+        drift: bool,
+    }
+
+    impl RollbackSim for Toy {
+        type State = ([u64; 2], usize);
+
+        fn save_state(&self) -> Self::State {
+            (self.totals, self.history.len())
+        }
+
+        fn load_state(&mut self, state: &Self::State) {
+            self.totals = state.0;
+            self.history.truncate(state.1);
+        }
+
+        fn advance(&mut self, inputs: [u8; 2], _replaying: bool) {
+            for (total, input) in self.totals.iter_mut().zip(inputs) {
+                *total = total.wrapping_mul(31).wrapping_add(input as u64 + 1);
+            }
+            if self.drift && self.history.len() == 40 {
+                self.totals[0] += 1;
+            }
+            self.history.push(self.checksum());
+        }
+
+        fn checksum(&self) -> u64 {
+            self.totals[0] ^ self.totals[1].rotate_left(17)
+        }
+    }
+
+    fn script(seat: usize, tick: u32) -> u8 {
+        if seat == 0 {
+            ((tick / 7) % 4) as u8
+        } else {
+            ((tick / 5) % 3) as u8
+        }
+    }
+
+    /// Runs both sides for `ticks`, then lets them finish confirming.
+    fn play(
+        latency: u32,
+        drop_every: u32,
+        drift: bool,
+        ticks: u32,
+    ) -> Result<(Toy, Toy, u32), String> {
+        let (a, b) = LoopbackTransport::pair(latency, drop_every);
+        let mut host: NetPeer<([u64; 2], usize)> = NetPeer::new(Box::new(a), 0, 2);
+        let mut guest = NetPeer::new(Box::new(b), 1, 2);
+        let mut host_sim = Toy::default();
+        let mut guest_sim = Toy {
+            drift,
+            ..Toy::default()
+        };
+        for tick in 0..ticks {
+            host.tick(&mut host_sim, script(0, tick))?;
+            guest.tick(&mut guest_sim, script(1, tick))?;
+        }
+        for _ in 0..60 {
+            host.tick(&mut host_sim, 0)?;
+            guest.tick(&mut guest_sim, 0)?;
+        }
+        let rollbacks = host.session().rollbacks() + guest.session().rollbacks();
+        Ok((host_sim, guest_sim, rollbacks))
+    }
+
+    #[test]
+    fn peers_agree_despite_latency() {
+        let (host, guest, rollbacks) = play(3, 0, false, 300).unwrap();
+        assert!(rollbacks > 0, "latency should force some rollbacks");
+        assert!(host.history.len() >= 300 && guest.history.len() >= 300);
+        assert_eq!(host.history[..300], guest.history[..300]);
+    }
+
+    #[test]
+    fn peers_agree_despite_packet_loss() {
+        let (host, guest, _) = play(2, 3, false, 300).unwrap();
+        assert_eq!(host.history[..300], guest.history[..300]);
+    }
+
+    #[test]
+    fn desync_is_detected() {
+        let error = play(1, 0, true, 300).err().unwrap();
+        assert!(error.contains("Desync"), "{}", error);
+    }
+
+    #[test]
+    fn handshake_hands_over_setup() {
+        let (a, b) = LoopbackTransport::pair(1, 0);
+        let mut host = Handshake::host(Box::new(a), vec![7, 8, 9]);
+        let mut guest = Handshake::join(Box::new(b));
+        let (mut hosted, mut joined) = (None, None);
+        for _ in 0..50 {
+            if hosted.is_none() {
+                hosted = host.update(0.1).unwrap();
+            }
+            if joined.is_none() {
+                joined = guest.update(0.1).unwrap();
+            }
+        }
+        assert_eq!(hosted.map(|(_, setup)| setup), Some(vec![7, 8, 9]));
+        assert_eq!(joined.map(|(_, setup)| setup), Some(vec![7, 8, 9]));
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let message = NetMessage::Inputs {
+            start: 120,
+            inputs: vec![1, 2, 3],
+            ack: 118,
+        };
+        assert_eq!(NetMessage::decode(&message.encode()), Some(message));
+        assert_eq!(NetMessage::decode(&[0xff, 0xff, 0xff]), None);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+
+/// Frames the simulation may run ahead of the last input heard from the
+/// peer. Past this it waits rather than predicting any further.
+pub const MAX_PREDICTION: u32 = 8;
+/// Peers compare state checksums on every frame that's a multiple of this.
+pub const CHECKSUM_INTERVAL: u32 = 30;
+
+/// A two-player simulation that can be snapshotted and stepped one fixed
+/// tick at a time. It must be deterministic: the same inputs from the same
+/// state always give the same result.
+pub trait RollbackSim {
+    type State: Clone;
+
+    fn save_state(&self) -> Self::State;
+    fn load_state(&mut self, state: &Self::State);
+    /// Runs one tick with each seat's packed input. `replaying` is set while
+    /// re-running frames after a misprediction, so sounds can be skipped.
+    fn advance(&mut self, inputs: [u8; 2], replaying: bool);
+    /// Hash of everything that affects the outcome of the match.
+    fn checksum(&self) -> u64;
+}
+
+struct SavedFrame<S> {
+    frame: u32,
+    state: S,
+    checksum: u64,
+}
+
+/// Rollback bookkeeping for one side of a two-player match. The remote
+/// input for a frame that hasn't arrived yet is predicted to repeat the
+/// last one that did; when the real one turns out different, the session
+/// loads the state from before that frame and simulates forward again.
+pub struct RollbackSession<S> {
+    local_seat: usize,
+    input_delay: u32,
+    /// Next frame to simulate.
+    frame: u32,
+    /// Inputs by seat, indexed by frame. Always contiguous from frame 0.
+    inputs: [Vec<u8>; 2],
+    /// Remote input each simulated frame was run with.
+    predicted: Vec<u8>,
+    /// State from just before each recent frame, oldest first.
+    saved: VecDeque<SavedFrame<S>>,
+    rollback_from: Option<u32>,
+    /// How many of our inputs the peer has confirmed receiving.
+    peer_acked: u32,
+    next_checksum_frame: u32,
+    local_checksums: VecDeque<(u32, u64)>,
+    remote_checksums: VecDeque<(u32, u64)>,
+    unsent_checksums: Vec<(u32, u64)>,
+    desync_frame: Option<u32>,
+    rollbacks: u32,
+}
+
+impl<S: Clone> RollbackSession<S> {
+    /// `local_seat` is 0 for the host and 1 for the guest. Local inputs are
+    /// scheduled `input_delay` frames ahead, which hides that much latency
+    /// without any rollback at all.
+    pub fn new(local_seat: usize, input_delay: u32) -> Self {
+        let idle = vec![0; input_delay as usize];
+        Self {
+            local_seat,
+            input_delay,
+            frame: 0,
+            inputs: [idle.clone(), idle],
+            predicted: Vec::new(),
+            saved: VecDeque::new(),
+            rollback_from: None,
+            peer_acked: 0,
+            next_checksum_frame: CHECKSUM_INTERVAL,
+            local_checksums: VecDeque::new(),
+            remote_checksums: VecDeque::new(),
+            unsent_checksums: Vec::new(),
+            desync_frame: None,
+            rollbacks: 0,
+        }
+    }
+
+    fn remote_seat(&self) -> usize {
+        1 - self.local_seat
+    }
+
+    pub fn input_delay(&self) -> u32 {
+        self.input_delay
+    }
+
+    /// Next frame to simulate.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Frames below this have the real input from both sides.
+    pub fn confirmed_frame(&self) -> u32 {
+        self.inputs[self.remote_seat()].len() as u32
+    }
+
+    /// Number of times a misprediction forced a resimulation.
+    pub fn rollbacks(&self) -> u32 {
+        self.rollbacks
+    }
+
+    /// Frame whose checksums didn't match, once that has happened.
+    pub fn desync_frame(&self) -> Option<u32> {
+        self.desync_frame
+    }
+
+    /// Schedules this tick's local input. Does nothing while waiting on the
+    /// peer, so the local side never gets more than the delay ahead.
+    pub fn add_local_input(&mut self, input: u8) {
+        let target = (self.frame + self.input_delay) as usize;
+        let local = &mut self.inputs[self.local_seat];
+        if local.len() == target {
+            local.push(input);
+        }
+    }
+
+    /// Takes in a run of the peer's inputs starting at frame `start`, and
+    /// how many of ours they have. Resent inputs are ignored; a gap means a
+    /// packet went missing, and everything after it waits for the resend.
+    pub fn receive_inputs(&mut self, start: u32, inputs: &[u8], ack: u32) {
+        self.peer_acked = self.peer_acked.max(ack);
+        let remote = self.remote_seat();
+        for (offset, &input) in inputs.iter().enumerate() {
+            let frame = start + offset as u32;
+            let known = self.inputs[remote].len() as u32;
+            if frame < known {
+                continue;
+            }
+            if frame > known {
+                break;
+            }
+            self.inputs[remote].push(input);
+            let mispredicted = self
+                .predicted
+                .get(frame as usize)
+                .is_some_and(|&guess| guess != input);
+            if frame < self.frame && mispredicted {
+                self.rollback_from = Some(self.rollback_from.map_or(frame, |f| f.min(frame)));
+            }
+        }
+    }
+
+    pub fn receive_checksum(&mut self, frame: u32, checksum: u64) {
+        self.remote_checksums.push_back((frame, checksum));
+        self.compare_checksums();
+    }
+
+    /// Our inputs the peer hasn't confirmed yet, as `(start, inputs, ack)`.
+    /// Sent every tick, so a lost packet only costs a frame or two.
+    pub fn outgoing_inputs(&self) -> (u32, Vec<u8>, u32) {
+        let local = &self.inputs[self.local_seat];
+        let start = (self.peer_acked as usize).min(local.len());
+        (
+            start as u32,
+            local[start..].to_vec(),
+            self.confirmed_frame(),
+        )
+    }
+
+    /// Checksums computed since the last call, for the peer to compare.
+    pub fn take_checksums(&mut self) -> Vec<(u32, u64)> {
+        std::mem::take(&mut self.unsent_checksums)
+    }
+
+    /// Rolls back if a prediction turned out wrong, then simulates the next
+    /// frame if both its local input is in and the peer isn't too far
+    /// behind. Returns whether a new frame was simulated.
+    pub fn advance<G: RollbackSim<State = S>>(&mut self, sim: &mut G) -> bool {
+        if let Some(from) = self.rollback_from.take() {
+            let snapshot = self.saved.iter().find(|s| s.frame == from);
+            if let Some(snapshot) = snapshot {
+                sim.load_state(&snapshot.state);
+                let target = self.frame;
+                self.saved.retain(|s| s.frame < from);
+                self.frame = from;
+                while self.frame < target {
+                    self.simulate_frame(sim, true);
+                }
+                self.rollbacks += 1;
+            }
+        }
+        let local_ready = self.inputs[self.local_seat].len() as u32 > self.frame;
+        let within_prediction = self.frame < self.confirmed_frame() + MAX_PREDICTION;
+        let advanced = local_ready && within_prediction;
+        if advanced {
+            self.simulate_frame(sim, false);
+        }
+        self.collect_checksums();
+        advanced
+    }
+
+    fn simulate_frame<G: RollbackSim<State = S>>(&mut self, sim: &mut G, replaying: bool) {
+        let frame = self.frame as usize;
+        self.saved.push_back(SavedFrame {
+            frame: self.frame,
+            state: sim.save_state(),
+            checksum: sim.checksum(),
+        });
+        while self.saved.len() > MAX_PREDICTION as usize + 2 {
+            self.saved.pop_front();
+        }
+        let remote = &self.inputs[self.remote_seat()];
+        let remote_input = remote.get(frame).or(remote.last()).copied().unwrap_or(0);
+        self.predicted.truncate(frame);
+        self.predicted.push(remote_input);
+        let mut inputs = [0; 2];
+        inputs[self.local_seat] = self.inputs[self.local_seat][frame];
+        inputs[self.remote_seat()] = remote_input;
+        sim.advance(inputs, replaying);
+        self.frame += 1;
+    }
+
+    /// Checksums the state before each checksum frame once every input
+    /// leading up to it is confirmed, so it can't be rolled back any more.
+    fn collect_checksums(&mut self) {
+        while self.next_checksum_frame <= self.confirmed_frame()
+            && self.next_checksum_frame < self.frame
+        {
+            let frame = self.next_checksum_frame;
+            if let Some(snapshot) = self.saved.iter().find(|s| s.frame == frame) {
+                self.local_checksums.push_back((frame, snapshot.checksum));
+                self.unsent_checksums.push((frame, snapshot.checksum));
+            }
+            self.next_checksum_frame += CHECKSUM_INTERVAL;
+        }
+        self.compare_checksums();
+    }
+
+    fn compare_checksums(&mut self) {
+        while let (Some(&(local_frame, local)), Some(&(remote_frame, remote))) =
+            (self.local_checksums.front(), self.remote_checksums.front())
+        {
+            if local_frame < remote_frame {
+                self.local_checksums.pop_front();
+            } else if remote_frame < local_frame {
+                self.remote_checksums.pop_front();
+            } else {
+                if local != remote && self.desync_frame.is_none() {
+                    self.desync_frame = Some(local_frame);
+                }
+                self.local_checksums.pop_front();
+                self.remote_checksums.pop_front();
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! One-way match broadcasts: length-prefixed frames sent to any number of
+//! TCP spectators, or written to a recording file in the same format.
+
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+/// Anything longer is taken as a corrupt stream rather than allocated.
+const MAX_FRAME_SIZE: usize = 1 << 20;
+/// A spectator this far behind is dropped instead of buffered forever.
+const MAX_BACKLOG: usize = 4 << 20;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Recordings start with this so other files are refused up front.
+const RECORDING_MAGIC: &[u8; 4] = b"JQRC";
+
+fn framed(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(payload.len() + 4);
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Collects bytes and splits complete frames back out.
+#[derive(Default)]
+struct FrameBuffer {
+    bytes: Vec<u8>,
+}
+
+impl FrameBuffer {
+    fn next_frame(&mut self) -> Result<Option<Vec<u8>>, String> {
+        let Some(header) = self.bytes.get(..4) else {
+            return Ok(None);
+        };
+        let size = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if size > MAX_FRAME_SIZE {
+            return Err("Broken stream".to_string());
+        }
+        if self.bytes.len() < 4 + size {
+            return Ok(None);
+        }
+        let frame = self.bytes[4..4 + size].to_vec();
+        self.bytes.drain(..4 + size);
+        Ok(Some(frame))
+    }
+}
+
+struct Spectator {
+    stream: TcpStream,
+    backlog: Vec<u8>,
+}
+
+impl Spectator {
+    /// Writes as much of the backlog as the socket takes. `false` once the
+    /// spectator is gone or hopelessly behind.
+    fn flush(&mut self) -> bool {
+        while !self.backlog.is_empty() {
+            match self.stream.write(&self.backlog) {
+                Ok(0) => return false,
+                Ok(written) => {
+                    self.backlog.drain(..written);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => return false,
+            }
+        }
+        self.backlog.len() <= MAX_BACKLOG
+    }
+}
+
+/// Sends every frame to whoever is connected. Spectators who tune in late
+/// are sent the intro frame, such as the match setup, before anything else.
+pub struct StreamServer {
+    listener: TcpListener,
+    spectators: Vec<Spectator>,
+    intro: Option<Vec<u8>>,
+}
+
+impl StreamServer {
+    pub fn bind(port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|e| format!("Could not listen on port {}: {}", port, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Socket error: {}", e))?;
+        Ok(Self {
+            listener,
+            spectators: Vec::new(),
+            intro: None,
+        })
+    }
+
+    pub fn spectator_count(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// Replaces the intro frame and sends it to everyone already watching.
+    pub fn set_intro(&mut self, payload: &[u8]) {
+        self.intro = Some(payload.to_vec());
+        self.broadcast(payload);
+    }
+
+    /// Takes in new spectators and sends them the intro.
+    pub fn accept(&mut self) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_err() {
+                continue;
+            }
+            let _ = stream.set_nodelay(true);
+            let backlog = self.intro.as_deref().map(framed).unwrap_or_default();
+            self.spectators.push(Spectator { stream, backlog });
+        }
+        self.spectators.retain_mut(Spectator::flush);
+    }
+
+    pub fn broadcast(&mut self, payload: &[u8]) {
+        self.accept();
+        let frame = framed(payload);
+        for spectator in &mut self.spectators {
+            spectator.backlog.extend_from_slice(&frame);
+        }
+        self.spectators.retain_mut(Spectator::flush);
+    }
+}
+
+/// The watching end of a `StreamServer`.
+pub struct StreamClient {
+    stream: TcpStream,
+    buffer: FrameBuffer,
+}
+
+impl StreamClient {
+    /// Connects to `address`, given as `host:port`.
+    pub fn connect(address: &str) -> Result<Self, String> {
+        let target = address
+            .to_socket_addrs()
+            .map_err(|e| format!("Bad address '{}': {}", address, e))?
+            .next()
+            .ok_or_else(|| format!("Bad address '{}'", address))?;
+        let stream = TcpStream::connect_timeout(&target, CONNECT_TIMEOUT)
+            .map_err(|e| format!("Could not connect to {}: {}", address, e))?;
+        stream
+            .set_nonblocking(true)
+            .map_err(|e| format!("Socket error: {}", e))?;
+        Ok(Self {
+            stream,
+            buffer: FrameBuffer::default(),
+        })
+    }
+
+    /// Every complete frame received since the last call. Fails once the
+    /// broadcaster hangs up.
+    pub fn poll(&mut self) -> Result<Vec<Vec<u8>>, String> {
+        let mut chunk = [0u8; 16 * 1024];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err("The broadcast ended".to_string()),
+                Ok(size) => self.buffer.bytes.extend_from_slice(&chunk[..size]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(format!("Connection lost: {}", e)),
+            }
+        }
+        let mut frames = Vec::new();
+        while let Some(frame) = self.buffer.next_frame()? {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+}
+
+/// Writes a broadcast to disk for watching later.
+pub struct StreamRecorder {
+    file: BufWriter<File>,
+}
+
+impl StreamRecorder {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let mut file = File::create(path)
+            .map(BufWriter::new)
+            .map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+        file.write_all(RECORDING_MAGIC)
+            .map_err(|e| format!("Could not write recording: {}", e))?;
+        Ok(Self { file })
+    }
+
+    pub fn write(&mut self, payload: &[u8]) -> Result<(), String> {
+        self.file
+            .write_all(&framed(payload))
+            .map_err(|e| format!("Could not write recording: {}", e))
+    }
+}
+
+/// Every frame of a recording, in order. A recording cut short by a crash
+/// plays up to its last complete frame.
+pub fn read_recording(path: &Path) -> Result<Vec<Vec<u8>>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Could not read recording: {}", e))?;
+    let Some(body) = bytes.strip_prefix(RECORDING_MAGIC) else {
+        return Err("Not a recording".to_string());
+    };
+    let mut buffer = FrameBuffer {
+        bytes: body.to_vec(),
+    };
+    let mut frames = Vec::new();
+    while let Some(frame) = buffer.next_frame()? {
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    #[test]
+    fn late_spectator_gets_intro_then_frames() {
+        let port = 47921;
+        let mut server = StreamServer::bind(port).unwrap();
+        server.set_intro(b"setup");
+        server.broadcast(b"early");
+        let mut client = StreamClient::connect(&format!("127.0.0.1:{}", port)).unwrap();
+        let mut received = Vec::new();
+        for frame in [b"one".as_slice(), b"two"] {
+            sleep(Duration::from_millis(20));
+            server.broadcast(frame);
+        }
+        for _ in 0..20 {
+            sleep(Duration::from_millis(10));
+            received.extend(client.poll().unwrap());
+        }
+        assert_eq!(
+            received,
+            vec![b"setup".to_vec(), b"one".to_vec(), b"two".to_vec()]
+        );
+        drop(server);
+        sleep(Duration::from_millis(20));
+        assert!(client.poll().is_err());
+    }
+
+    #[test]
+    fn recording_round_trips_and_tolerates_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("match.jqr");
+        let mut recorder = StreamRecorder::create(&path).unwrap();
+        recorder.write(b"setup").unwrap();
+        recorder.write(b"frame").unwrap();
+        drop(recorder);
+        assert_eq!(
+            read_recording(&path).unwrap(),
+            vec![b"setup".to_vec(), b"frame".to_vec()]
+        );
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 2]).unwrap();
+        assert_eq!(read_recording(&path).unwrap(), vec![b"setup".to_vec()]);
+        std::fs::write(&path, b"nope").unwrap();
+        assert!(read_recording(&path).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+use std::cell::RefCell;
+#[cfg(test)]
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+#[cfg(test)]
+use std::rc::Rc;
+
+/// Largest datagram we expect; anything bigger is cut off and fails to decode.
+const MAX_PACKET_SIZE: usize = 2048;
+
+/// Unreliable, unordered packet delivery to one peer. Lost packets are
+/// fine; the protocol resends everything that hasn't been acknowledged.
+pub trait Transport {
+    fn send(&mut self, packet: &[u8]) -> Result<(), String>;
+    /// Every packet that has arrived since the last call. Never blocks.
+    fn poll(&mut self) -> Result<Vec<Vec<u8>>, String>;
+}
+
+/// Gets a look at each packet that arrives before there's a peer, such as a
+/// LAN probe. Returning a reply sends it back and keeps the packet from
+/// claiming the peer slot.
+pub type Responder = Box<dyn FnMut(&[u8]) -> Option<Vec<u8>>>;
+
+pub struct UdpTransport {
+    socket: UdpSocket,
+    /// Unknown for a host until the first packet comes in.
+    peer: Option<SocketAddr>,
+    responder: Option<Responder>,
+}
+
+impl UdpTransport {
+    /// Listens on `port` on every interface; the first address to send a
+    /// packet becomes the peer.
+    pub fn host(port: u16) -> Result<Self, String> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))
+            .map_err(|e| format!("Could not listen on port {}: {}", port, e))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| format!("Socket error: {}", e))?;
+        Ok(Self {
+            socket,
+            peer: None,
+            responder: None,
+        })
+    }
+
+    /// Sends to `address`, given as `host:port`, from any free local port.
+    pub fn join(address: &str) -> Result<Self, String> {
+        let peer = address
+            .to_socket_addrs()
+            .map_err(|e| format!("Bad address '{}': {}", address, e))?
+            .next()
+            .ok_or_else(|| format!("Bad address '{}'", address))?;
+        let local = if peer.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(local).map_err(|e| format!("Socket error: {}", e))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| format!("Socket error: {}", e))?;
+        Ok(Self {
+            socket,
+            peer: Some(peer),
+            responder: None,
+        })
+    }
+
+    pub fn set_responder(&mut self, responder: Responder) {
+        self.responder = Some(responder);
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<(), String> {
+        let Some(peer) = self.peer else {
+            return Ok(());
+        };
+        match self.socket.send_to(packet, peer) {
+            Ok(_) => Ok(()),
+            // Full buffers just drop the packet, like the network would.
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(e) => Err(format!("Send failed: {}", e)),
+        }
+    }
+
+    fn poll(&mut self) -> Result<Vec<Vec<u8>>, String> {
+        let mut packets = Vec::new();
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, from)) => {
+                    if self.peer.is_none() {
+                        let responder = self.responder.as_mut();
+                        if let Some(reply) = responder.and_then(|answer| answer(&buffer[..size])) {
+                            let _ = self.socket.send_to(&reply, from);
+                            continue;
+                        }
+                    }
+                    let peer = *self.peer.get_or_insert(from);
+                    if from == peer {
+                        packets.push(buffer[..size].to_vec());
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // Windows reports an ICMP "port unreachable" from an earlier
+                // send this way; the peer just isn't listening yet.
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(format!("Receive failed: {}", e)),
+            }
+        }
+        Ok(packets)
+    }
+}
+
+#[cfg(test)]
+type Queue = Rc<RefCell<VecDeque<(u32, Vec<u8>)>>>;
+
+/// In-process transport for tests. Each packet is held back for `latency`
+/// polls, and every `drop_every`-th one is lost.
+#[cfg(test)]
+pub struct LoopbackTransport {
+    outbox: Queue,
+    inbox: Queue,
+    latency: u32,
+    drop_every: u32,
+    sent: u32,
+}
+
+#[cfg(test)]
+impl LoopbackTransport {
+    /// Two connected ends.
+    pub fn pair(latency: u32, drop_every: u32) -> (Self, Self) {
+        let a: Queue = Rc::default();
+        let b: Queue = Rc::default();
+        let end = |outbox: &Queue, inbox: &Queue| Self {
+            outbox: Rc::clone(outbox),
+            inbox: Rc::clone(inbox),
+            latency,
+            drop_every,
+            sent: 0,
+        };
+        (end(&a, &b), end(&b, &a))
+    }
+}
+
+#[cfg(test)]
+impl Transport for LoopbackTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<(), String> {
+        self.sent += 1;
+        if self.drop_every > 0 && self.sent.is_multiple_of(self.drop_every) {
+            return Ok(());
+        }
+        self.outbox
+            .borrow_mut()
+            .push_back((self.latency, packet.to_vec()));
+        Ok(())
+    }
+
+    fn poll(&mut self) -> Result<Vec<Vec<u8>>, String> {
+        let mut inbox = self.inbox.borrow_mut();
+        let mut arrived = Vec::new();
+        let mut waiting = VecDeque::new();
+        for (wait, packet) in inbox.drain(..) {
+            if wait == 0 {
+                arrived.push(packet);
+            } else {
+                waiting.push_back((wait - 1, packet));
+            }
+        }
+        *inbox = waiting;
+        Ok(arrived)
+    }
+}
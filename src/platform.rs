@@ -117,6 +117,17 @@ pub fn create_level_platforms(level: usize) -> Vec<Platform> {
             platforms.push(Platform::new(2100.0, 350.0, 150.0, 20.0));
             platforms.push(Platform::new(2300.0, 300.0, 150.0, 20.0));
             platforms.push(Platform::new(2500.0, 250.0, 200.0, 20.0));
+
+            platforms.push(Platform::new(2780.0, 170.0, 100.0, 20.0));
+            platforms.push(Platform::new(2920.0, 90.0, 100.0, 20.0));
+            platforms.push(Platform::new(2780.0, 10.0, 100.0, 20.0));
+            platforms.push(Platform::new(2920.0, -70.0, 100.0, 20.0));
+            platforms.push(Platform::new(2780.0, -150.0, 100.0, 20.0));
+            platforms.push(Platform::new(2950.0, -230.0, 220.0, 20.0));
+
+            platforms.push(Platform::new(3250.0, -150.0, 150.0, 20.0));
+            platforms.push(Platform::new(3500.0, -50.0, 150.0, 20.0));
+            platforms.push(Platform::new(3750.0, 60.0, 150.0, 20.0));
         }
         3 => {
             platforms.push(Platform::new(0.0, 550.0, 4200.0, 50.0));
@@ -202,3 +213,16 @@ pub fn create_level_platforms(level: usize) -> Vec<Platform> {
 
     platforms
 }
+
+/// Ledges placed out of reach of a single jump; only a partner boost gets
+/// there, so they're only added in co-op with partner physics on.
+pub fn create_level_coop_ledges(level: usize) -> Vec<Platform> {
+    match level {
+        1 => vec![Platform::new(2900.0, 300.0, 150.0, 20.0)],
+        2 => vec![Platform::new(880.0, -10.0, 140.0, 20.0)],
+        3 => vec![Platform::new(3000.0, 300.0, 150.0, 20.0)],
+        4 => vec![Platform::new(3050.0, 300.0, 150.0, 20.0)],
+        5 => vec![Platform::new(930.0, 70.0, 140.0, 20.0)],
+        _ => Vec::new(),
+    }
+}
@@ -2,6 +2,7 @@ use crate::constants::*;
 use macroquad::prelude::*;
 use std::rc::Rc;
 
+#[derive(Clone)]
 pub struct Player {
     pub x: f32,
     pub y: f32,
@@ -16,6 +17,14 @@ pub struct Player {
     pub animation_frame: usize,
     pub animation_timer: f32,
     pub walk_bounce_timer: f32,
+    pub health: u32,
+    pub invulnerable_timer: f32,
+    pub knockback_timer: f32,
+    pub standing_on_partner: bool,
+    /// Scales walking speed; Versus speed boosts raise it.
+    pub speed_multiplier: f32,
+    /// Scales jump force; set by Versus handicaps.
+    pub jump_multiplier: f32,
 }
 
 impl Player {
@@ -39,6 +48,12 @@ impl Player {
             animation_frame: 0,
             animation_timer: 0.0,
             walk_bounce_timer: 0.0,
+            health: PLAYER_MAX_HEALTH,
+            invulnerable_timer: 0.0,
+            knockback_timer: 0.0,
+            standing_on_partner: false,
+            speed_multiplier: 1.0,
+            jump_multiplier: 1.0,
         }
     }
 
@@ -65,6 +80,43 @@ impl Player {
         }
 
         self.on_ground = false;
+        self.standing_on_partner = false;
+
+        if self.invulnerable_timer > 0.0 {
+            self.invulnerable_timer = (self.invulnerable_timer - dt).max(0.0);
+        }
+        if self.knockback_timer > 0.0 {
+            self.knockback_timer = (self.knockback_timer - dt).max(0.0);
+        }
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_timer > 0.0
+    }
+
+    /// Applies one point of damage from a source centered at `source_x`.
+    /// Returns true when the hit drains the last point of health.
+    pub fn take_damage(&mut self, source_x: f32) -> bool {
+        if self.is_invulnerable() {
+            return false;
+        }
+        self.health = self.health.saturating_sub(1);
+        let away = if self.x + self.width / 2.0 < source_x {
+            -1.0
+        } else {
+            1.0
+        };
+        self.vel_x = KNOCKBACK_SPEED_X * away;
+        self.vel_y = KNOCKBACK_SPEED_Y;
+        self.on_ground = false;
+        self.invulnerable_timer = INVULNERABILITY_DURATION;
+        self.knockback_timer = KNOCKBACK_STUN;
+        self.health == 0
+    }
+
+    fn is_blink_hidden(&self) -> bool {
+        self.is_invulnerable()
+            && (self.invulnerable_timer * INVULNERABILITY_BLINK_RATE) as u32 % 2 == 1
     }
 
     pub fn update_animation(&mut self, dt: f32) {
@@ -94,11 +146,14 @@ impl Player {
     }
 
     pub fn handle_movement_custom(&mut self, left: bool, right: bool) {
+        if self.knockback_timer > 0.0 {
+            return;
+        }
         if left {
-            self.vel_x = -PLAYER_SPEED;
+            self.vel_x = -PLAYER_SPEED * self.speed_multiplier;
             self.facing_right = false;
         } else if right {
-            self.vel_x = PLAYER_SPEED;
+            self.vel_x = PLAYER_SPEED * self.speed_multiplier;
             self.facing_right = true;
         }
     }
@@ -118,7 +173,11 @@ impl Player {
     pub fn handle_jump_custom(&mut self, jump_pressed: bool) -> bool {
         if jump_pressed {
             if self.on_ground {
-                self.vel_y = JUMP_FORCE;
+                self.vel_y = if self.standing_on_partner {
+                    JUMP_FORCE * PARTNER_BOOST_MULTIPLIER
+                } else {
+                    JUMP_FORCE
+                } * self.jump_multiplier;
                 self.on_ground = false;
                 return true;
             }
@@ -126,6 +185,28 @@ impl Player {
         false
     }
 
+    /// Lands on a co-op partner's head when falling onto it and rides along
+    /// with their horizontal movement. Jumping from there gets a boost.
+    pub fn land_on_partner(&mut self, partner: &Player, dt: f32) -> bool {
+        if self.vel_y < 0.0 {
+            return false;
+        }
+        let overlap = (self.x + self.width).min(partner.x + partner.width) - self.x.max(partner.x);
+        if overlap < self.width * PARTNER_MIN_OVERLAP {
+            return false;
+        }
+        let feet = self.y + self.height;
+        if feet < partner.y || feet > partner.y + PARTNER_HEAD_TOLERANCE {
+            return false;
+        }
+        self.y = partner.y - self.height;
+        self.x += partner.vel_x * dt;
+        self.vel_y = 0.0;
+        self.on_ground = true;
+        self.standing_on_partner = true;
+        true
+    }
+
     pub fn check_platform_collision(&mut self, platform: &crate::platform::Platform) {
         if let Some((new_x, new_y, on_top)) =
             platform.get_collision_response(self.x, self.y, self.width, self.height, self.vel_y)
@@ -147,6 +228,9 @@ impl Player {
     }
 
     pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        if self.is_blink_hidden() {
+            return;
+        }
         let screen_x = self.x - camera_x;
         let mut screen_y = self.y - camera_y;
 
@@ -188,7 +272,10 @@ impl Player {
         }
     }
 
-    pub fn draw_vs(&self, camera_x: f32, camera_y: f32, is_player1: bool) {
+    pub fn draw_vs(&self, camera_x: f32, camera_y: f32, is_player1: bool, tint: Color) {
+        if self.is_blink_hidden() {
+            return;
+        }
         let screen_x = self.x - camera_x;
         let mut screen_y = self.y - camera_y;
 
@@ -221,9 +308,15 @@ impl Player {
                 pivot: None,
             };
 
-            draw_texture_ex(&**texture, screen_x, screen_y, WHITE, params);
+            draw_texture_ex(&**texture, screen_x, screen_y, tint, params);
         } else {
-            let color = if is_player1 { BLACK } else { DARKGRAY };
+            let color = if tint != WHITE {
+                Color::new(tint.r * 0.5, tint.g * 0.5, tint.b * 0.5, 1.0)
+            } else if is_player1 {
+                BLACK
+            } else {
+                DARKGRAY
+            };
             draw_rectangle(screen_x, screen_y, self.width, self.height, color);
             draw_circle(screen_x + 10.0, screen_y + 10.0, 3.0, WHITE);
             draw_circle(screen_x + 22.0, screen_y + 10.0, 3.0, WHITE);
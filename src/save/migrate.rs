@@ -0,0 +1,275 @@
+//! Every layout a save has been stored in, oldest first, and the step that
+//! upgrades each one to the next.
+//!
+//! To change `SaveData` or the blob header, freeze the current layout here
+//! as the next `…Vn` type, bump `SAVE_VERSION`, give the frozen type an
+//! `upgrade` to the new one and add a fixture for it under `fixtures/`.
+
+use super::{PassphraseHeader, SaveBlob, SaveData, NONCE_LEN, SAVE_VERSION};
+use serde::Deserialize;
+
+/// Version 0: the plain JSON `save_slotN.json` files from before saves were
+/// encrypted. Fields added after the first release may be missing.
+#[derive(Deserialize)]
+struct SaveDataV0 {
+    current_level: usize,
+    unlocked_levels: Vec<bool>,
+    lives: u32,
+    score: u32,
+    #[serde(default)]
+    coins_collected: u32,
+    #[serde(default)]
+    total_coins: u32,
+    #[serde(default)]
+    time_remaining: f32,
+    #[serde(default)]
+    time_taken: f32,
+    #[serde(default)]
+    timestamp: u64,
+    last_checkpoint_pos: Option<(f32, f32)>,
+    #[serde(default)]
+    player_name: String,
+    #[serde(default)]
+    tutorial_completed: bool,
+    #[serde(default)]
+    versus_played: bool,
+}
+
+impl SaveDataV0 {
+    fn upgrade(self) -> SaveDataV1 {
+        SaveDataV1 {
+            current_level: self.current_level,
+            unlocked_levels: self.unlocked_levels,
+            lives: self.lives,
+            score: self.score,
+            coins_collected: self.coins_collected,
+            total_coins: self.total_coins,
+            time_remaining: self.time_remaining,
+            time_taken: self.time_taken,
+            timestamp: self.timestamp,
+            last_checkpoint_pos: self.last_checkpoint_pos,
+            player_name: self.player_name,
+            tutorial_completed: self.tutorial_completed,
+            versus_played: self.versus_played,
+        }
+    }
+}
+
+/// The encrypted payload of versions 1 and 2. Bincode has no optional
+/// fields, so every field is always present.
+#[derive(Deserialize)]
+struct SaveDataV1 {
+    current_level: usize,
+    unlocked_levels: Vec<bool>,
+    lives: u32,
+    score: u32,
+    coins_collected: u32,
+    total_coins: u32,
+    time_remaining: f32,
+    time_taken: f32,
+    timestamp: u64,
+    last_checkpoint_pos: Option<(f32, f32)>,
+    player_name: String,
+    tutorial_completed: bool,
+    versus_played: bool,
+}
+
+impl SaveDataV1 {
+    /// Version 3 kept the payload as it was.
+    fn upgrade(self) -> SaveData {
+        SaveData {
+            current_level: self.current_level,
+            unlocked_levels: self.unlocked_levels,
+            lives: self.lives,
+            score: self.score,
+            coins_collected: self.coins_collected,
+            total_coins: self.total_coins,
+            time_remaining: self.time_remaining,
+            time_taken: self.time_taken,
+            timestamp: self.timestamp,
+            last_checkpoint_pos: self.last_checkpoint_pos,
+            player_name: self.player_name,
+            tutorial_completed: self.tutorial_completed,
+            versus_played: self.versus_played,
+        }
+    }
+}
+
+/// Version 1 blobs: the nonce and ciphertext.
+#[derive(Deserialize)]
+struct SaveBlobV1 {
+    version: u8,
+    nonce: [u8; NONCE_LEN],
+    ciphertext: Vec<u8>,
+}
+
+impl SaveBlobV1 {
+    fn upgrade(self) -> SaveBlobV2 {
+        SaveBlobV2 {
+            version: self.version,
+            passphrase: None,
+            nonce: self.nonce,
+            ciphertext: self.ciphertext,
+        }
+    }
+}
+
+/// Version 2 added passphrase locks.
+#[derive(Deserialize)]
+struct SaveBlobV2 {
+    version: u8,
+    passphrase: Option<PassphraseHeader>,
+    nonce: [u8; NONCE_LEN],
+    ciphertext: Vec<u8>,
+}
+
+impl SaveBlobV2 {
+    /// Version 3 bound saves to their slot. Older blobs have no app id or
+    /// slot to carry over; theirs are left blank and never checked.
+    fn upgrade(self) -> SaveBlob {
+        SaveBlob {
+            version: self.version,
+            app_id: String::new(),
+            slot: 0,
+            passphrase: self.passphrase,
+            nonce: self.nonce,
+            ciphertext: self.ciphertext,
+        }
+    }
+}
+
+/// Decodes a blob of any supported version into the current header layout.
+/// `version` keeps the version it was written at, which decides how the
+/// ciphertext is authenticated and which payload it holds.
+pub(super) fn read_blob(bytes: &[u8]) -> Result<SaveBlob, String> {
+    match bytes.first() {
+        Some(1) => Ok(decode_blob::<SaveBlobV1>(bytes)?.upgrade().upgrade()),
+        Some(2) => Ok(decode_blob::<SaveBlobV2>(bytes)?.upgrade()),
+        Some(&SAVE_VERSION) => decode_blob(bytes),
+        _ => Err("Save version not supported".to_string()),
+    }
+}
+
+/// Decodes a decrypted payload written at `version` into the current
+/// `SaveData`.
+pub(super) fn read_payload(version: u8, plaintext: &[u8]) -> Result<SaveData, String> {
+    match version {
+        1 | 2 => Ok(decode_payload::<SaveDataV1>(plaintext)?.upgrade()),
+        SAVE_VERSION => decode_payload(plaintext),
+        _ => Err("Save version not supported".to_string()),
+    }
+}
+
+/// Parses a version 0 JSON save.
+pub(super) fn read_legacy_json(json: &str) -> Result<SaveData, serde_json::Error> {
+    let legacy: SaveDataV0 = serde_json::from_str(json)?;
+    Ok(legacy.upgrade().upgrade())
+}
+
+fn decode_blob<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T, String> {
+    bincode::deserialize(bytes).map_err(|e| format!("Error decoding save blob: {e}"))
+}
+
+fn decode_payload<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T, String> {
+    bincode::deserialize(bytes).map_err(|e| format!("Error deserializing save: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save::{KEY_ENV, KEY_LEN, SAVE_DIR_ENV};
+    use base64::Engine;
+    use serial_test::serial;
+    use std::{env, fs};
+    use tempfile::tempdir;
+
+    /// A save from each version, all holding the same profile for slot 0
+    /// under the all-zero test key. Locked ones use the passphrase "fixture".
+    const FIXTURES: [(&str, &[u8], bool); 4] = [
+        ("v1", include_bytes!("fixtures/profile_v1.dat"), false),
+        ("v2", include_bytes!("fixtures/profile_v2.dat"), false),
+        (
+            "v2 locked",
+            include_bytes!("fixtures/profile_v2_locked.dat"),
+            true,
+        ),
+        ("v3", include_bytes!("fixtures/profile_v3.dat"), false),
+    ];
+
+    /// Checks `data` is the fixture profile. Version 0 predates the last
+    /// two fields.
+    fn assert_fixture_profile(data: &SaveData, name: &str, has_flags: bool) {
+        assert_eq!(data.current_level, 3, "{name}");
+        assert_eq!(
+            data.unlocked_levels,
+            [true, true, true, false, false],
+            "{name}"
+        );
+        assert_eq!((data.lives, data.score), (4, 12345), "{name}");
+        assert_eq!((data.coins_collected, data.total_coins), (5, 20), "{name}");
+        assert_eq!(
+            (data.time_remaining, data.time_taken),
+            (200.0, 100.0),
+            "{name}"
+        );
+        assert_eq!(data.timestamp, 1_700_000_000, "{name}");
+        assert_eq!(data.last_checkpoint_pos, Some((320.0, 480.0)), "{name}");
+        assert_eq!(data.player_name, "Fixture", "{name}");
+        assert_eq!(data.tutorial_completed, has_flags, "{name}");
+        assert_eq!(data.versus_played, has_flags, "{name}");
+    }
+
+    #[test]
+    fn every_version_has_a_fixture() {
+        for version in 1..=SAVE_VERSION {
+            assert!(
+                FIXTURES.iter().any(|(_, bytes, _)| bytes[0] == version),
+                "no fixture for save version {version}"
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn every_fixture_loads() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(
+            KEY_ENV,
+            base64::engine::general_purpose::STANDARD.encode([0u8; KEY_LEN]),
+        );
+        let path = SaveData::get_save_path(0).unwrap();
+        for (name, bytes, locked) in FIXTURES {
+            fs::write(&path, bytes).unwrap();
+            let data = if locked {
+                SaveData::load_protected(&path, 0, "fixture").unwrap().0
+            } else {
+                SaveData::load_slot(0).unwrap().0
+            };
+            assert_fixture_profile(&data, name, true);
+            assert_eq!(fs::read(&path).unwrap()[0], SAVE_VERSION, "{name}");
+        }
+
+        let legacy = read_legacy_json(include_str!("fixtures/save_slot_v0.json")).unwrap();
+        assert_fixture_profile(&legacy, "v0", false);
+    }
+
+    #[test]
+    fn v0_upgrade_fills_missing_fields() {
+        let json = r#"{"current_level":2,"unlocked_levels":[true,true],"lives":3,"score":50,"last_checkpoint_pos":null}"#;
+        let data = read_legacy_json(json).unwrap();
+        assert_eq!((data.current_level, data.lives, data.score), (2, 3, 50));
+        assert_eq!(
+            (data.coins_collected, data.total_coins, data.timestamp),
+            (0, 0, 0)
+        );
+        assert!(data.player_name.is_empty());
+        assert!(!data.tutorial_completed && !data.versus_played);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        assert!(read_blob(&[SAVE_VERSION + 1, 0, 0]).is_err());
+        assert!(read_payload(0, &[]).is_err());
+    }
+}
@@ -0,0 +1,988 @@
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use directories::ProjectDirs;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+mod migrate;
+
+const SAVE_VERSION: u8 = 3;
+/// Authenticated with every save so files from other programs are refused.
+const APP_ID: &str = "jump-quest";
+const APP_QUALIFIER: &str = "";
+const APP_ORG: &str = "JumpQuest";
+const APP_NAME: &str = "JumpQuest";
+const SAVE_PREFIX: &str = "profile";
+const SAVE_EXTENSION: &str = "dat";
+const KEY_ENV: &str = "JUMPQUEST_SAVE_KEY";
+const KEY_FILE: &str = "save.key";
+const TEMP_EXTENSION: &str = "tmp";
+const REKEY_EXTENSION: &str = "rekey";
+/// Earlier copies kept of each slot, as `profile_N.dat.bak1` (newest) on up.
+const SAVE_BACKUPS: usize = 3;
+const WRONG_PASSPHRASE: &str = "Wrong passphrase";
+const SAVE_DIR_ENV: &str = "JUMPQUEST_SAVE_DIR";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+const VERIFIER_LEN: usize = 32;
+const MAX_LIVES: u32 = 99;
+const LEGACY_PREFIX: &str = "save_slot";
+const LEGACY_EXTENSION: &str = "json";
+
+/// A profile as of the current `SAVE_VERSION`. Older layouts live in
+/// `migrate`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SaveData {
+    pub current_level: usize,
+    pub unlocked_levels: Vec<bool>,
+    pub lives: u32,
+    pub score: u32,
+    pub coins_collected: u32,
+    pub total_coins: u32,
+    pub time_remaining: f32,
+    pub time_taken: f32,
+    pub timestamp: u64,
+    pub last_checkpoint_pos: Option<(f32, f32)>,
+    pub player_name: String,
+    pub tutorial_completed: bool,
+    pub versus_played: bool,
+}
+
+/// Where a slot's profile was read from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SaveSource {
+    Primary,
+    /// The profile file couldn't be read, so this came from backup `n`, 1
+    /// being the newest.
+    Backup(usize),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveBlob {
+    version: u8,
+    app_id: String,
+    /// The slot the save was written for. Together with the version and
+    /// app id it's the ciphertext's associated data, so a blob copied over
+    /// another slot or edited to an older version fails to decrypt.
+    slot: u32,
+    /// Present when the profile is locked with a passphrase rather than the
+    /// keyfile.
+    passphrase: Option<PassphraseHeader>,
+    nonce: [u8; NONCE_LEN],
+    ciphertext: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct PassphraseHeader {
+    salt: [u8; SALT_LEN],
+    /// Derived alongside the key, so a wrong passphrase can be told apart
+    /// from a damaged file.
+    verifier: [u8; VERIFIER_LEN],
+}
+
+/// A save key derived from a passphrase with Argon2id, kept for the session
+/// so the profile can be re-saved without asking again.
+#[derive(Clone)]
+pub struct PassphraseKey {
+    key: Key,
+    header: PassphraseHeader,
+}
+
+impl PassphraseKey {
+    /// Derives a key under a fresh salt, for locking a profile.
+    pub fn new(passphrase: &str) -> Result<Self, String> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(passphrase, salt)
+    }
+
+    fn derive(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("Passphrase can't be empty".to_string());
+        }
+        let mut output = [0u8; KEY_LEN + VERIFIER_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut output)
+            .map_err(|e| format!("Error deriving save key: {e}"))?;
+        let mut verifier = [0u8; VERIFIER_LEN];
+        verifier.copy_from_slice(&output[KEY_LEN..]);
+        Ok(Self {
+            key: Key::from_slice(&output[..KEY_LEN]).to_owned(),
+            header: PassphraseHeader { salt, verifier },
+        })
+    }
+}
+
+impl SaveBlob {
+    fn read(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Error reading save: {e}"))?;
+        migrate::read_blob(&bytes)
+    }
+
+    fn is_current(&self) -> bool {
+        self.version == SAVE_VERSION
+    }
+
+    /// Refuses a current-version blob written by another program or for
+    /// another slot. Older blobs are taken as belonging to the slot they're
+    /// loaded from.
+    fn check_binding(&self, slot: usize) -> Result<(), String> {
+        if !self.is_current() {
+            return Ok(());
+        }
+        if self.app_id != APP_ID {
+            return Err("Not a JumpQuest save file".to_string());
+        }
+        if self.slot as usize != slot {
+            return Err(format!(
+                "Save was written for slot {}, not slot {}",
+                self.slot as usize + 1,
+                slot + 1
+            ));
+        }
+        Ok(())
+    }
+
+    fn decrypt(&self, key: &Key, slot: usize) -> Result<SaveData, String> {
+        let cipher = ChaCha20Poly1305::new(key);
+        let nonce = Nonce::from_slice(&self.nonce);
+        let aad = if self.is_current() {
+            associated_data(slot)
+        } else {
+            Vec::new()
+        };
+        let payload = Payload {
+            msg: &self.ciphertext,
+            aad: &aad,
+        };
+        let plaintext = cipher
+            .decrypt(nonce, payload)
+            .map_err(|e| format!("Error decrypting save: {e}"))?;
+        Ok(migrate::read_payload(self.version, &plaintext)?.validate())
+    }
+}
+
+impl SaveData {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        let mut unlocked_levels = vec![false; crate::constants::MAX_LEVELS];
+        unlocked_levels[0] = true;
+
+        Self {
+            current_level: 1,
+            unlocked_levels,
+            lives: crate::constants::DEFAULT_LIVES,
+            score: 0,
+            coins_collected: 0,
+            total_coins: 0,
+            time_remaining: crate::constants::TIME_LIMIT,
+            time_taken: 0.0,
+            timestamp: 0,
+            last_checkpoint_pos: None,
+            player_name: String::new(),
+            tutorial_completed: false,
+            versus_played: false,
+        }
+    }
+
+    fn validate(mut self) -> Self {
+        use crate::constants::{MAX_LEVELS, TIME_LIMIT};
+        if self.current_level == 0 {
+            self.current_level = 1;
+        }
+        if self.current_level > MAX_LEVELS {
+            self.current_level = MAX_LEVELS;
+        }
+        self.lives = self.lives.min(MAX_LIVES);
+        self.coins_collected = self.coins_collected.min(self.total_coins);
+        if self.time_remaining.is_sign_negative() || !self.time_remaining.is_finite() {
+            self.time_remaining = TIME_LIMIT;
+        }
+        if self.time_remaining > TIME_LIMIT {
+            self.time_remaining = TIME_LIMIT;
+        }
+        if self.time_taken.is_sign_negative() || !self.time_taken.is_finite() {
+            self.time_taken = 0.0;
+        }
+        self
+    }
+
+    /// Saves `slot`'s profile to `path`, which is normally that slot's save
+    /// path. The file it replaces becomes the newest backup.
+    pub fn save_to_file(&self, path: &Path, slot: usize) -> Result<(), String> {
+        let key = load_key()?;
+        keep_backup(path)?;
+        self.save_with_key(path, slot, &key, None)
+    }
+
+    /// Saves locked with `passphrase` instead of the keyfile. Locking a save
+    /// drops its backups, which would still open without the passphrase.
+    pub fn save_protected(
+        &self,
+        path: &Path,
+        slot: usize,
+        passphrase: &PassphraseKey,
+    ) -> Result<(), String> {
+        if Self::is_protected(path) {
+            keep_backup(path)?;
+        } else {
+            remove_backups(path)?;
+        }
+        self.save_with_key(path, slot, &passphrase.key, Some(passphrase.header))
+    }
+
+    fn save_with_key(
+        &self,
+        path: &Path,
+        slot: usize,
+        key: &Key,
+        passphrase: Option<PassphraseHeader>,
+    ) -> Result<(), String> {
+        let cipher = ChaCha20Poly1305::new(key);
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        let nonce = Nonce::from_slice(&nonce_bytes);
+
+        let payload =
+            bincode::serialize(self).map_err(|e| format!("Error serializing save: {e}"))?;
+        let aad = associated_data(slot);
+        let ciphertext = cipher
+            .encrypt(
+                nonce,
+                Payload {
+                    msg: &payload,
+                    aad: &aad,
+                },
+            )
+            .map_err(|e| format!("Error encrypting save: {e}"))?;
+
+        let blob = SaveBlob {
+            version: SAVE_VERSION,
+            app_id: APP_ID.to_string(),
+            slot: slot as u32,
+            passphrase,
+            nonce: nonce_bytes,
+            ciphertext,
+        };
+        let blob_bytes =
+            bincode::serialize(&blob).map_err(|e| format!("Error encoding save blob: {e}"))?;
+        write_atomic(path, &blob_bytes, false).map_err(|e| format!("Error writing save file: {e}"))
+    }
+
+    /// Loads `slot`'s profile from `path`. Saves from older versions are
+    /// rewritten in the current format once they've been read.
+    pub fn load_from_file(path: &Path, slot: usize) -> Result<Self, String> {
+        let key = load_key()?;
+        Self::load_with_key(path, slot, &key)
+    }
+
+    fn load_with_key(path: &Path, slot: usize, key: &Key) -> Result<Self, String> {
+        let blob = SaveBlob::read(path)?;
+        if blob.passphrase.is_some() {
+            return Err("Save is locked with a passphrase".to_string());
+        }
+        blob.check_binding(slot)?;
+        let save_data = blob.decrypt(key, slot)?;
+        if !blob.is_current() {
+            save_data.save_with_key(path, slot, key, None)?;
+        }
+        Ok(save_data)
+    }
+
+    /// Loads a passphrase-locked save, handing back the derived key so the
+    /// profile can be saved again under it.
+    pub fn load_protected(
+        path: &Path,
+        slot: usize,
+        passphrase: &str,
+    ) -> Result<(Self, PassphraseKey), String> {
+        let blob = SaveBlob::read(path)?;
+        let header = blob
+            .passphrase
+            .ok_or_else(|| "Save is not locked with a passphrase".to_string())?;
+        blob.check_binding(slot)?;
+        let derived = PassphraseKey::derive(passphrase, header.salt)?;
+        if derived.header.verifier != header.verifier {
+            return Err(WRONG_PASSPHRASE.to_string());
+        }
+        let save_data = blob
+            .decrypt(&derived.key, slot)
+            .map_err(|e| format!("Save is corrupted: {e}"))?;
+        if !blob.is_current() {
+            save_data.save_protected(path, slot, &derived)?;
+        }
+        Ok((save_data, derived))
+    }
+
+    /// Whether the save at `path` needs a passphrase to open.
+    pub fn is_protected(path: &Path) -> bool {
+        SaveBlob::read(path).is_ok_and(|blob| blob.passphrase.is_some())
+    }
+
+    /// Whether `slot` needs a passphrase, going by the first of its files
+    /// that has a readable header.
+    pub fn is_slot_protected(slot: usize) -> bool {
+        Self::get_save_path(slot).is_ok_and(|path| {
+            slot_files(&path)
+                .iter()
+                .find_map(|file| SaveBlob::read(file).ok())
+                .is_some_and(|blob| blob.passphrase.is_some())
+        })
+    }
+
+    /// Loads `slot`, falling back to its newest backup that opens when the
+    /// profile file can't be read.
+    pub fn load_slot(slot: usize) -> Result<(Self, SaveSource), String> {
+        if let Some(migrated) = ensure_migrated(slot)? {
+            return Ok((migrated, SaveSource::Primary));
+        }
+        Self::open_slot(slot, |path| Self::load_from_file(path, slot))
+    }
+
+    /// `load_slot` for a slot locked with a passphrase.
+    pub fn load_locked_slot(
+        slot: usize,
+        passphrase: &str,
+    ) -> Result<(Self, PassphraseKey, SaveSource), String> {
+        let ((save_data, key), source) =
+            Self::open_slot(slot, |path| Self::load_protected(path, slot, passphrase))?;
+        Ok((save_data, key, source))
+    }
+
+    fn open_slot<T>(
+        slot: usize,
+        open: impl Fn(&Path) -> Result<T, String>,
+    ) -> Result<(T, SaveSource), String> {
+        let path = Self::get_save_path(slot)?;
+        let primary_error = match open(&path) {
+            Ok(loaded) => return Ok((loaded, SaveSource::Primary)),
+            // The backups were written under the same passphrase.
+            Err(e) if e == WRONG_PASSPHRASE => return Err(e),
+            Err(e) => e,
+        };
+        for n in 1..=SAVE_BACKUPS {
+            let backup = backup_path(&path, n);
+            if !backup.exists() {
+                continue;
+            }
+            match open(&backup) {
+                Ok(loaded) => {
+                    eprintln!(
+                        "Save slot {} unreadable ({}), using backup {}",
+                        slot, primary_error, n
+                    );
+                    return Ok((loaded, SaveSource::Backup(n)));
+                }
+                Err(e) => eprintln!("Error reading backup {} of save slot {}: {}", n, slot, e),
+            }
+        }
+        Err(primary_error)
+    }
+
+    pub fn save_exists(path: &Path) -> bool {
+        path.exists()
+    }
+
+    /// Whether `slot` has a profile file or any backups.
+    pub fn slot_exists(slot: usize) -> bool {
+        Self::get_save_path(slot)
+            .is_ok_and(|path| slot_files(&path).iter().any(|file| file.exists()))
+    }
+
+    pub fn get_save_path(slot: usize) -> Result<PathBuf, String> {
+        let base = get_save_dir()?;
+        let filename = format!("{SAVE_PREFIX}_{slot}.{SAVE_EXTENSION}");
+        Ok(base.join(filename))
+    }
+
+    /// Every slot's profile, recovered from a backup where needed. Locked
+    /// and unreadable slots come back as `None`.
+    pub fn list_all_saves() -> Vec<(usize, Option<(SaveData, SaveSource)>)> {
+        let mut saves = Vec::with_capacity(crate::constants::MAX_SAVE_SLOTS);
+        for slot in 0..crate::constants::MAX_SAVE_SLOTS {
+            let readable = Self::legacy_exists(slot)
+                || (Self::slot_exists(slot) && !Self::is_slot_protected(slot));
+            if !readable {
+                saves.push((slot, None));
+                continue;
+            }
+            match Self::load_slot(slot) {
+                Ok(loaded) => saves.push((slot, Some(loaded))),
+                Err(e) => {
+                    eprintln!("Error reading save slot {}: {}", slot, e);
+                    saves.push((slot, None));
+                }
+            }
+        }
+        saves
+    }
+
+    pub fn delete_save(slot: usize) -> Result<(), String> {
+        let path = Self::get_save_path(slot)?;
+        if Self::save_exists(&path) {
+            fs::remove_file(&path).map_err(|e| format!("Error deleting file: {e}"))?;
+        }
+        remove_backups(&path)?;
+        let legacy = legacy_save_path(slot);
+        if legacy.exists() {
+            let _ = fs::remove_file(&legacy);
+        }
+        Ok(())
+    }
+
+    pub fn legacy_exists(slot: usize) -> bool {
+        legacy_save_path(slot).exists()
+    }
+}
+
+/// The save key: `JUMPQUEST_SAVE_KEY` when set, otherwise the keyfile in the
+/// save dir, which is generated on first use.
+fn load_key() -> Result<Key, String> {
+    if let Ok(encoded) = env::var(KEY_ENV) {
+        return decode_key(&encoded, KEY_ENV);
+    }
+    let path = key_file_path()?;
+    match fs::read_to_string(&path) {
+        Ok(encoded) => decode_key(encoded.trim(), "Save keyfile"),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let key = generate_key();
+            write_key_file(&path, &key)?;
+            Ok(key)
+        }
+        Err(e) => Err(format!("Error reading save keyfile: {e}")),
+    }
+}
+
+/// App id, save version and slot, authenticated alongside the ciphertext.
+fn associated_data(slot: usize) -> Vec<u8> {
+    let mut aad = APP_ID.as_bytes().to_vec();
+    aad.push(SAVE_VERSION);
+    aad.extend_from_slice(&(slot as u32).to_le_bytes());
+    aad
+}
+
+fn decode_key(encoded: &str, source: &str) -> Result<Key, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Invalid base64 in {source}: {e}"))?;
+    if bytes.len() != KEY_LEN {
+        return Err(format!(
+            "{source} must be {} bytes after base64 decoding",
+            KEY_LEN
+        ));
+    }
+    let mut key_bytes = [0u8; KEY_LEN];
+    key_bytes.copy_from_slice(&bytes);
+    Ok(Key::from_slice(&key_bytes).to_owned())
+}
+
+fn generate_key() -> Key {
+    let mut key_bytes = [0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut key_bytes);
+    Key::from_slice(&key_bytes).to_owned()
+}
+
+fn key_file_path() -> Result<PathBuf, String> {
+    Ok(get_save_dir()?.join(KEY_FILE))
+}
+
+/// Writes the keyfile readable by the owner only.
+fn write_key_file(path: &Path, key: &Key) -> Result<(), String> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(key);
+    write_atomic(path, encoded.as_bytes(), true)
+        .map_err(|e| format!("Error writing save keyfile: {e}"))
+}
+
+/// Writes `bytes` to a temp file beside `path`, syncs it to disk and
+/// renames it over `path`, so a crash leaves either the old file or the new
+/// one and never half of either.
+pub fn write_atomic(path: &Path, bytes: &[u8], owner_only: bool) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = sibling_path(path, TEMP_EXTENSION);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if owner_only {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = owner_only;
+    let mut file = options.open(&temp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    // The rename itself only survives a crash once the directory is synced.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// `path` with `.extension` added after its existing one.
+fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    sibling_path(path, &format!("bak{n}"))
+}
+
+/// A slot's profile file followed by its backups, newest first.
+fn slot_files(path: &Path) -> Vec<PathBuf> {
+    std::iter::once(path.to_path_buf())
+        .chain((1..=SAVE_BACKUPS).map(|n| backup_path(path, n)))
+        .collect()
+}
+
+/// Shifts `path`'s backups down one, dropping the oldest, and copies `path`
+/// in as the newest.
+fn keep_backup(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    for n in (1..SAVE_BACKUPS).rev() {
+        let older = backup_path(path, n);
+        if older.exists() {
+            fs::rename(&older, backup_path(path, n + 1))
+                .map_err(|e| format!("Error rotating save backups: {e}"))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1)).map_err(|e| format!("Error backing up save: {e}"))?;
+    Ok(())
+}
+
+fn remove_backups(path: &Path) -> Result<(), String> {
+    for n in 1..=SAVE_BACKUPS {
+        let backup = backup_path(path, n);
+        if backup.exists() {
+            fs::remove_file(&backup).map_err(|e| format!("Error deleting backup: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the keyfile with a fresh key and re-encrypts every save not
+/// locked with a passphrase under it, backups included, returning how many
+/// slots were rewritten. Everything is re-encrypted to temp files first, so
+/// a profile file that won't open aborts the rotation with nothing touched;
+/// the new keyfile only goes in once every save has been swapped over.
+/// Backups that won't open are left as they are.
+pub fn rotate_save_key() -> Result<usize, String> {
+    if env::var(KEY_ENV).is_ok() {
+        return Err(format!(
+            "The save key is set by {KEY_ENV}; change it there instead"
+        ));
+    }
+    let old_key = load_key()?;
+    let new_key = generate_key();
+    let mut rewritten = Vec::new();
+    let slots = match reencrypt_saves(&old_key, &new_key, &mut rewritten) {
+        Ok(slots) => slots,
+        Err(e) => {
+            discard_rekeyed(&rewritten);
+            return Err(e);
+        }
+    };
+    for (done, (temp, file)) in rewritten.iter().enumerate() {
+        if let Err(e) = fs::rename(temp, file) {
+            discard_rekeyed(&rewritten[done..]);
+            return Err(format!("Error writing save file: {e}"));
+        }
+    }
+    write_key_file(&key_file_path()?, &new_key)?;
+    Ok(slots)
+}
+
+/// Writes each save `rotate_save_key` covers to a temp file under
+/// `new_key`, listing `(temp, save)` pairs in `rewritten` as it goes.
+fn reencrypt_saves(
+    old_key: &Key,
+    new_key: &Key,
+    rewritten: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<usize, String> {
+    let mut slots = 0;
+    for slot in 0..crate::constants::MAX_SAVE_SLOTS {
+        ensure_migrated(slot)?;
+        let path = SaveData::get_save_path(slot)?;
+        let before = rewritten.len();
+        for file in slot_files(&path) {
+            if !SaveData::save_exists(&file) || SaveData::is_protected(&file) {
+                continue;
+            }
+            let data = match SaveData::load_with_key(&file, slot, old_key) {
+                Ok(data) => data,
+                Err(e) if file == path => return Err(e),
+                Err(e) => {
+                    eprintln!("Skipping unreadable backup {:?}: {}", file, e);
+                    continue;
+                }
+            };
+            let temp = sibling_path(&file, REKEY_EXTENSION);
+            // Listed before writing so a half-written temp is cleaned up too.
+            rewritten.push((temp.clone(), file));
+            data.save_with_key(&temp, slot, new_key, None)?;
+        }
+        if rewritten.len() > before {
+            slots += 1;
+        }
+    }
+    Ok(slots)
+}
+
+/// Deletes the temp files of a rotation that didn't go through.
+fn discard_rekeyed(rewritten: &[(PathBuf, PathBuf)]) {
+    for (temp, _) in rewritten {
+        if temp.exists() {
+            if let Err(e) = fs::remove_file(temp) {
+                eprintln!("Could not delete {:?}: {}", temp, e);
+            }
+        }
+    }
+}
+
+pub fn get_save_dir() -> Result<PathBuf, String> {
+    if let Ok(dir) = env::var(SAVE_DIR_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+    let dirs = ProjectDirs::from(APP_QUALIFIER, APP_ORG, APP_NAME)
+        .ok_or_else(|| "No valid save dir".to_string())?;
+    Ok(dirs.data_dir().to_path_buf())
+}
+
+fn legacy_save_path(slot: usize) -> PathBuf {
+    let filename = format!("{LEGACY_PREFIX}{}.{}", slot + 1, LEGACY_EXTENSION);
+    PathBuf::from(filename)
+}
+
+fn ensure_migrated(slot: usize) -> Result<Option<SaveData>, String> {
+    let new_path = SaveData::get_save_path(slot)?;
+    if SaveData::save_exists(&new_path) {
+        return Ok(None);
+    }
+    let legacy_path = legacy_save_path(slot);
+    if !legacy_path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&legacy_path)
+        .map_err(|e| format!("Error reading legacy save {:?}: {}", legacy_path, e))?;
+    let legacy = migrate::read_legacy_json(&content)
+        .map_err(|e| format!("Error parsing legacy save {:?}: {}", legacy_path, e))?;
+    let validated = legacy.validate();
+    validated.save_to_file(&new_path, slot)?;
+    let _ = fs::remove_file(&legacy_path);
+    Ok(Some(validated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::env;
+    use tempfile::tempdir;
+
+    fn set_test_key() -> String {
+        // 32 bytes of zero for deterministic tests
+        base64::engine::general_purpose::STANDARD.encode([0u8; KEY_LEN])
+    }
+
+    #[test]
+    fn validate_caps_values() {
+        let data = SaveData {
+            current_level: 999,
+            unlocked_levels: vec![true; crate::constants::MAX_LEVELS + 2],
+            lives: 500,
+            score: 0,
+            coins_collected: 100,
+            total_coins: 10,
+            time_remaining: f32::INFINITY,
+            time_taken: f32::NAN,
+            timestamp: 0,
+            last_checkpoint_pos: None,
+            player_name: String::new(),
+            tutorial_completed: false,
+            versus_played: false,
+        };
+        let v = data.validate();
+        assert!(v.current_level <= crate::constants::MAX_LEVELS);
+        assert!(v.lives <= MAX_LIVES);
+        assert!(v.coins_collected <= v.total_coins);
+        assert!(v.time_remaining <= crate::constants::TIME_LIMIT);
+        assert!(v.time_taken >= 0.0);
+    }
+
+    #[test]
+    #[serial]
+    fn save_round_trip_encrypted() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(KEY_ENV, set_test_key());
+        let data = SaveData::new();
+        let path = SaveData::get_save_path(0).unwrap();
+        data.save_to_file(&path, 0).unwrap();
+        let loaded = SaveData::load_from_file(&path, 0).unwrap();
+        assert_eq!(loaded.current_level, data.current_level);
+        assert_eq!(loaded.unlocked_levels, data.unlocked_levels);
+    }
+
+    #[test]
+    #[serial]
+    fn save_generates_keyfile_without_env() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::remove_var(KEY_ENV);
+        let data = SaveData::new();
+        let path = SaveData::get_save_path(0).unwrap();
+        data.save_to_file(&path, 0).unwrap();
+        let key_path = dir.path().join(KEY_FILE);
+        assert!(key_path.exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let loaded = SaveData::load_from_file(&path, 0).unwrap();
+        assert_eq!(loaded.current_level, data.current_level);
+    }
+
+    #[test]
+    #[serial]
+    fn rotating_key_reencrypts_saves() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::remove_var(KEY_ENV);
+        let mut data = SaveData::new();
+        data.score = 1234;
+        for slot in [0, 2] {
+            data.save_to_file(&SaveData::get_save_path(slot).unwrap(), slot)
+                .unwrap();
+        }
+        let key_path = dir.path().join(KEY_FILE);
+        let old_key = fs::read_to_string(&key_path).unwrap();
+
+        data.score = 5678;
+        let third = SaveData::get_save_path(2).unwrap();
+        data.save_to_file(&third, 2).unwrap();
+
+        assert_eq!(rotate_save_key().unwrap(), 2);
+        assert_ne!(fs::read_to_string(&key_path).unwrap(), old_key);
+        let (loaded, _) = SaveData::load_slot(2).unwrap();
+        assert_eq!(loaded.score, 5678);
+        // The backup was re-encrypted too.
+        fs::write(&third, b"junk").unwrap();
+        let (loaded, source) = SaveData::load_slot(2).unwrap();
+        assert_eq!(loaded.score, 1234);
+        assert!(source == SaveSource::Backup(1));
+
+        env::set_var(KEY_ENV, set_test_key());
+        assert!(rotate_save_key().is_err());
+        env::remove_var(KEY_ENV);
+    }
+
+    #[test]
+    #[serial]
+    fn failed_rotation_leaves_key_and_saves_alone() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::remove_var(KEY_ENV);
+        let mut data = SaveData::new();
+        data.score = 42;
+        let first = SaveData::get_save_path(0).unwrap();
+        data.save_to_file(&first, 0).unwrap();
+        let second = SaveData::get_save_path(1).unwrap();
+        data.save_to_file(&second, 1).unwrap();
+        fs::write(&second, b"junk").unwrap();
+        let key_path = dir.path().join(KEY_FILE);
+        let old_key = fs::read_to_string(&key_path).unwrap();
+
+        assert!(rotate_save_key().is_err());
+        assert_eq!(fs::read_to_string(&key_path).unwrap(), old_key);
+        assert!(!sibling_path(&first, REKEY_EXTENSION).exists());
+        let (loaded, _) = SaveData::load_slot(0).unwrap();
+        assert_eq!(loaded.score, 42);
+    }
+
+    #[test]
+    #[serial]
+    fn passphrase_locks_save() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(KEY_ENV, set_test_key());
+        let mut data = SaveData::new();
+        data.score = 777;
+        let path = SaveData::get_save_path(1).unwrap();
+        data.save_to_file(&path, 1).unwrap();
+        data.save_to_file(&path, 1).unwrap();
+        let key = PassphraseKey::new("correct horse").unwrap();
+        data.save_protected(&path, 1, &key).unwrap();
+        assert!(SaveData::is_protected(&path));
+        // Unlocked copies don't outlive the lock.
+        assert!(!backup_path(&path, 1).exists());
+        assert!(SaveData::load_from_file(&path, 1)
+            .err()
+            .unwrap()
+            .contains("passphrase"));
+
+        let err = SaveData::load_protected(&path, 1, "wrong horse")
+            .err()
+            .unwrap();
+        assert_eq!(err, "Wrong passphrase");
+        let (loaded, _) = SaveData::load_protected(&path, 1, "correct horse").unwrap();
+        assert_eq!(loaded.score, 777);
+
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+        let err = SaveData::load_protected(&path, 1, "correct horse")
+            .err()
+            .unwrap();
+        assert!(err.contains("corrupted"));
+    }
+
+    #[test]
+    #[serial]
+    fn refuses_save_from_another_slot() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(KEY_ENV, set_test_key());
+        let first = SaveData::get_save_path(0).unwrap();
+        let third = SaveData::get_save_path(2).unwrap();
+        SaveData::new().save_to_file(&first, 0).unwrap();
+
+        fs::copy(&first, &third).unwrap();
+        let err = SaveData::load_slot(2).err().unwrap();
+        assert_eq!(err, "Save was written for slot 1, not slot 3");
+
+        // Rewriting the header's slot is caught by the associated data.
+        let mut blob: SaveBlob = bincode::deserialize(&fs::read(&first).unwrap()).unwrap();
+        blob.slot = 2;
+        fs::write(&third, bincode::serialize(&blob).unwrap()).unwrap();
+        let err = SaveData::load_slot(2).err().unwrap();
+        assert!(err.contains("Error decrypting save"));
+    }
+
+    #[test]
+    #[serial]
+    fn migrates_version_1_blob() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(KEY_ENV, set_test_key());
+        let mut data = SaveData::new();
+        data.score = 4321;
+        let key = load_key().unwrap();
+        let nonce = [7u8; NONCE_LEN];
+        let ciphertext = ChaCha20Poly1305::new(&key)
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                bincode::serialize(&data).unwrap().as_ref(),
+            )
+            .unwrap();
+        let path = SaveData::get_save_path(1).unwrap();
+        fs::write(
+            &path,
+            bincode::serialize(&(1u8, nonce, ciphertext)).unwrap(),
+        )
+        .unwrap();
+
+        let (loaded, _) = SaveData::load_slot(1).unwrap();
+        assert_eq!(loaded.score, 4321);
+        assert_eq!(fs::read(&path).unwrap()[0], SAVE_VERSION);
+        assert_eq!(SaveData::load_slot(1).unwrap().0.score, 4321);
+    }
+
+    #[test]
+    #[serial]
+    fn saves_keep_rotating_backups() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(KEY_ENV, set_test_key());
+        let path = SaveData::get_save_path(0).unwrap();
+        let mut data = SaveData::new();
+        for score in 1..=5 {
+            data.score = score;
+            data.save_to_file(&path, 0).unwrap();
+        }
+        assert!(!backup_path(&path, SAVE_BACKUPS + 1).exists());
+        assert!(!sibling_path(&path, TEMP_EXTENSION).exists());
+        for n in 1..=SAVE_BACKUPS {
+            let backup = SaveData::load_from_file(&backup_path(&path, n), 0).unwrap();
+            assert_eq!(backup.score, 5 - n as u32);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn recovers_from_newest_readable_backup() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(KEY_ENV, set_test_key());
+        let path = SaveData::get_save_path(0).unwrap();
+        let mut data = SaveData::new();
+        for score in 1..=3 {
+            data.score = score;
+            data.save_to_file(&path, 0).unwrap();
+        }
+        fs::write(&path, b"half a save").unwrap();
+        fs::write(backup_path(&path, 1), b"").unwrap();
+
+        let (loaded, source) = SaveData::load_slot(0).unwrap();
+        assert_eq!(loaded.score, 1);
+        assert!(source == SaveSource::Backup(2));
+        let saves = SaveData::list_all_saves();
+        assert!(matches!(saves[0].1, Some((_, SaveSource::Backup(2)))));
+
+        SaveData::delete_save(0).unwrap();
+        assert!(!SaveData::slot_exists(0));
+        assert!(SaveData::load_slot(0).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn save_fails_with_bad_key() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(KEY_ENV, "dQ=="); // 1 byte
+        let data = SaveData::new();
+        let path = SaveData::get_save_path(0).unwrap();
+        let err = data.save_to_file(&path, 0).unwrap_err();
+        assert!(err.contains("must be 32 bytes"));
+    }
+
+    #[test]
+    #[serial]
+    fn migrates_legacy_json() {
+        let dir = tempdir().unwrap();
+        env::set_var(SAVE_DIR_ENV, dir.path());
+        env::set_var(KEY_ENV, set_test_key());
+        let cwd = env::current_dir().unwrap();
+        env::set_current_dir(dir.path()).unwrap();
+
+        let legacy_path = legacy_save_path(0);
+        let legacy = SaveData::new();
+        let json = serde_json::to_string(&legacy).unwrap();
+        fs::write(&legacy_path, json).unwrap();
+
+        let (loaded, _) = SaveData::load_slot(0).unwrap();
+        let new_path = SaveData::get_save_path(0).unwrap();
+        assert!(new_path.exists());
+        assert!(!legacy_path.exists());
+        assert_eq!(loaded.current_level, legacy.current_level);
+
+        env::set_current_dir(cwd).unwrap();
+    }
+}
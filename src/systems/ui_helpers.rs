@@ -37,3 +37,15 @@ impl MenuAnimation {
         }
     }
 }
+
+pub fn draw_heart(x: f32, y: f32, size: f32, color: Color) {
+    let lobe = size * 0.55;
+    draw_circle(x - size * 0.5, y - size * 0.25, lobe, color);
+    draw_circle(x + size * 0.5, y - size * 0.25, lobe, color);
+    draw_triangle(
+        vec2(x - size * 1.02, y - size * 0.05),
+        vec2(x + size * 1.02, y - size * 0.05),
+        vec2(x, y + size * 1.1),
+        color,
+    );
+}
@@ -0,0 +1,431 @@
+use crate::camera::level_world_bounds;
+use crate::constants::*;
+use crate::platform::Platform;
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TriggerKind {
+    /// Active only while something stands on it. With `enemy_weight` set,
+    /// walking enemies hold it down as well as players.
+    PressurePlate { enemy_weight: bool },
+    /// Flipped with the action key and stays where it was left.
+    Lever,
+    /// Pressed with the action key, then stays active for `duration` seconds.
+    TimedSwitch { duration: f32 },
+}
+
+/// A level object that powers every gate wired to the same `channel`.
+pub struct Trigger {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub kind: TriggerKind,
+    pub channel: u8,
+    pub active: bool,
+    timer: f32,
+}
+
+impl Trigger {
+    /// Places the trigger with its base resting on `floor_y`.
+    pub fn new(x: f32, floor_y: f32, kind: TriggerKind, channel: u8) -> Self {
+        let (width, height) = match kind {
+            TriggerKind::PressurePlate { .. } => (PRESSURE_PLATE_WIDTH, PRESSURE_PLATE_HEIGHT),
+            TriggerKind::Lever => (LEVER_WIDTH, LEVER_HEIGHT),
+            TriggerKind::TimedSwitch { .. } => (TIMED_SWITCH_SIZE, TIMED_SWITCH_SIZE),
+        };
+        Self {
+            x,
+            y: floor_y - height,
+            width,
+            height,
+            kind,
+            channel,
+            active: false,
+            timer: 0.0,
+        }
+    }
+
+    fn overlaps(&self, rect: (f32, f32, f32, f32)) -> bool {
+        let (rx, ry, rw, rh) = rect;
+        rx < self.x + self.width
+            && rx + rw > self.x
+            && ry < self.y + self.height
+            && ry + rh > self.y
+    }
+
+    /// Levers keep their state across checkpoint respawns; plates and timers
+    /// always come back released.
+    pub fn is_latching(&self) -> bool {
+        matches!(self.kind, TriggerKind::Lever)
+    }
+
+    pub fn update(
+        &mut self,
+        dt: f32,
+        players: &[(f32, f32, f32, f32)],
+        enemies: &[(f32, f32, f32, f32)],
+    ) {
+        match self.kind {
+            TriggerKind::PressurePlate { enemy_weight } => {
+                self.active = players.iter().any(|&rect| self.overlaps(rect))
+                    || (enemy_weight && enemies.iter().any(|&rect| self.overlaps(rect)));
+            }
+            TriggerKind::Lever => {}
+            TriggerKind::TimedSwitch { .. } => {
+                if self.timer > 0.0 {
+                    self.timer -= dt;
+                    if self.timer <= 0.0 {
+                        self.timer = 0.0;
+                        self.active = false;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Action-key use by a player standing at `rect`. Returns true when the
+    /// trigger reacted, so the key press isn't also used for something else.
+    pub fn interact(&mut self, rect: (f32, f32, f32, f32)) -> bool {
+        if !self.overlaps(rect) {
+            return false;
+        }
+        match self.kind {
+            TriggerKind::PressurePlate { .. } => false,
+            TriggerKind::Lever => {
+                self.active = !self.active;
+                true
+            }
+            TriggerKind::TimedSwitch { duration } => {
+                self.active = true;
+                self.timer = duration;
+                true
+            }
+        }
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y;
+        let color = channel_color(self.channel);
+        match self.kind {
+            TriggerKind::PressurePlate { .. } => {
+                let pressed = if self.active { self.height / 2.0 } else { 0.0 };
+                draw_rectangle(
+                    screen_x,
+                    screen_y + pressed,
+                    self.width,
+                    self.height - pressed,
+                    color,
+                );
+                draw_rectangle_lines(screen_x, screen_y, self.width, self.height, 1.0, DARKGRAY);
+            }
+            TriggerKind::Lever => {
+                let base_y = screen_y + self.height;
+                draw_rectangle(screen_x, base_y - 8.0, self.width, 8.0, DARKGRAY);
+                let tilt = if self.active { 12.0 } else { -12.0 };
+                let pivot_x = screen_x + self.width / 2.0;
+                draw_line(pivot_x, base_y - 8.0, pivot_x + tilt, screen_y, 4.0, color);
+                draw_circle(pivot_x + tilt, screen_y, 5.0, color);
+            }
+            TriggerKind::TimedSwitch { duration } => {
+                draw_rectangle(screen_x, screen_y, self.width, self.height, DARKGRAY);
+                let inset = if self.active { 6.0 } else { 4.0 };
+                draw_rectangle(
+                    screen_x + inset,
+                    screen_y + inset,
+                    self.width - inset * 2.0,
+                    self.height - inset * 2.0,
+                    color,
+                );
+                if self.active && duration > 0.0 {
+                    let remaining = self.timer / duration;
+                    draw_rectangle(screen_x, screen_y - 8.0, self.width * remaining, 4.0, color);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GateKind {
+    /// Solid while unpowered, opens while its channel is powered.
+    Door,
+    /// Only there while its channel is powered.
+    Bridge,
+}
+
+pub struct Gate {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub kind: GateKind,
+    pub channel: u8,
+    pub powered: bool,
+}
+
+impl Gate {
+    /// A door filling the column from `top` down to the ground.
+    pub fn door(x: f32, top: f32, channel: u8) -> Self {
+        Self {
+            x,
+            y: top,
+            width: DOOR_WIDTH,
+            height: GROUND_Y - top,
+            kind: GateKind::Door,
+            channel,
+            powered: false,
+        }
+    }
+
+    pub fn bridge(x: f32, y: f32, width: f32, channel: u8) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height: BRIDGE_HEIGHT,
+            kind: GateKind::Bridge,
+            channel,
+            powered: false,
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        match self.kind {
+            GateKind::Door => !self.powered,
+            GateKind::Bridge => self.powered,
+        }
+    }
+
+    pub fn as_platform(&self) -> Platform {
+        Platform::new(self.x, self.y, self.width, self.height)
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y;
+        let color = channel_color(self.channel);
+        if self.is_solid() {
+            draw_rectangle(
+                screen_x,
+                screen_y,
+                self.width,
+                self.height,
+                Color::new(0.35, 0.3, 0.3, 1.0),
+            );
+            draw_rectangle_lines(screen_x, screen_y, self.width, self.height, 3.0, color);
+        } else {
+            draw_rectangle_lines(
+                screen_x,
+                screen_y,
+                self.width,
+                self.height,
+                1.0,
+                Color::new(color.r, color.g, color.b, 0.4),
+            );
+        }
+    }
+}
+
+/// Colour shared by a channel's triggers and gates so players can see the wiring.
+fn channel_color(channel: u8) -> Color {
+    match channel % 4 {
+        0 => ORANGE,
+        1 => SKYBLUE,
+        2 => PINK,
+        _ => LIME,
+    }
+}
+
+/// A channel is powered while any of its triggers is active.
+pub fn channel_powered(triggers: &[Trigger], channel: u8) -> bool {
+    triggers.iter().any(|t| t.channel == channel && t.active)
+}
+
+pub fn update_gates(gates: &mut [Gate], triggers: &[Trigger]) {
+    for gate in gates {
+        gate.powered = channel_powered(triggers, gate.channel);
+    }
+}
+
+/// Snapshot of the triggers that should survive a checkpoint respawn, one
+/// entry per trigger in level order.
+pub fn latched_states(triggers: &[Trigger]) -> Vec<bool> {
+    triggers
+        .iter()
+        .map(|t| t.is_latching() && t.active)
+        .collect()
+}
+
+pub fn restore_latched_states(triggers: &mut [Trigger], states: &[bool]) {
+    for (trigger, &active) in triggers.iter_mut().zip(states) {
+        if trigger.is_latching() {
+            trigger.active = active;
+        }
+    }
+}
+
+/// Co-op puzzle sections: each blocks the way to the goal with a door that
+/// one player has to hold open for the other.
+pub fn create_level_coop_triggers(level: usize) -> Vec<Trigger> {
+    let plate = TriggerKind::PressurePlate {
+        enemy_weight: false,
+    };
+    match level {
+        1 => vec![
+            Trigger::new(3180.0, GROUND_Y, plate, 1),
+            Trigger::new(3400.0, GROUND_Y, plate, 1),
+        ],
+        2 => vec![
+            Trigger::new(1930.0, GROUND_Y, plate, 1),
+            Trigger::new(2150.0, GROUND_Y, plate, 1),
+        ],
+        3 => vec![
+            Trigger::new(3180.0, GROUND_Y, plate, 1),
+            Trigger::new(3420.0, GROUND_Y, TriggerKind::Lever, 1),
+        ],
+        4 => vec![
+            Trigger::new(
+                2760.0,
+                GROUND_Y,
+                TriggerKind::TimedSwitch { duration: 1.5 },
+                1,
+            ),
+            Trigger::new(3420.0, GROUND_Y, plate, 1),
+            Trigger::new(2880.0, GROUND_Y, plate, 2),
+        ],
+        5 => vec![
+            Trigger::new(2790.0, GROUND_Y, plate, 1),
+            Trigger::new(
+                3040.0,
+                500.0,
+                TriggerKind::PressurePlate { enemy_weight: true },
+                1,
+            ),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+pub fn create_level_coop_gates(level: usize) -> Vec<Gate> {
+    let top = level_world_bounds(level).top;
+    match level {
+        1 => vec![Gate::door(3300.0, top, 1)],
+        2 => vec![Gate::door(2050.0, top, 1)],
+        3 => vec![Gate::door(3300.0, top, 1)],
+        4 => vec![
+            Gate::door(3300.0, top, 1),
+            Gate::bridge(2960.0, 420.0, 80.0, 2),
+        ],
+        5 => vec![Gate::door(2900.0, top, 1)],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLATE: TriggerKind = TriggerKind::PressurePlate {
+        enemy_weight: false,
+    };
+
+    /// A player-sized box standing on the trigger.
+    fn standing_on(trigger: &Trigger) -> (f32, f32, f32, f32) {
+        (trigger.x, trigger.y - 20.0, 20.0, 30.0)
+    }
+
+    const AWAY: (f32, f32, f32, f32) = (0.0, 0.0, 20.0, 30.0);
+
+    #[test]
+    fn lever_stays_open_after_the_player_leaves() {
+        let mut triggers = vec![Trigger::new(500.0, GROUND_Y, TriggerKind::Lever, 1)];
+        let mut gates = vec![Gate::door(600.0, 0.0, 1)];
+        let rect = standing_on(&triggers[0]);
+        assert!(triggers[0].interact(rect));
+        triggers[0].update(0.1, &[rect], &[]);
+        update_gates(&mut gates, &triggers);
+        assert!(!gates[0].is_solid());
+
+        triggers[0].update(0.1, &[AWAY], &[]);
+        update_gates(&mut gates, &triggers);
+        assert!(triggers[0].active);
+        assert!(!gates[0].is_solid());
+    }
+
+    #[test]
+    fn pressure_plate_closes_again() {
+        let mut triggers = vec![Trigger::new(500.0, GROUND_Y, PLATE, 1)];
+        let mut gates = vec![Gate::door(600.0, 0.0, 1)];
+        let rect = standing_on(&triggers[0]);
+        assert!(!triggers[0].interact(rect));
+        triggers[0].update(0.1, &[rect], &[]);
+        update_gates(&mut gates, &triggers);
+        assert!(!gates[0].is_solid());
+
+        triggers[0].update(0.1, &[AWAY], &[]);
+        update_gates(&mut gates, &triggers);
+        assert!(!triggers[0].active);
+        assert!(gates[0].is_solid());
+    }
+
+    #[test]
+    fn timed_switch_runs_out() {
+        let mut trigger = Trigger::new(
+            500.0,
+            GROUND_Y,
+            TriggerKind::TimedSwitch { duration: 1.0 },
+            1,
+        );
+        assert!(trigger.interact(standing_on(&trigger)));
+        trigger.update(0.6, &[], &[]);
+        assert!(trigger.active);
+        trigger.update(0.6, &[], &[]);
+        assert!(!trigger.active);
+    }
+
+    #[test]
+    fn enemies_only_hold_weighted_plates() {
+        let mut plain = Trigger::new(500.0, GROUND_Y, PLATE, 1);
+        let mut weighted = Trigger::new(
+            500.0,
+            GROUND_Y,
+            TriggerKind::PressurePlate { enemy_weight: true },
+            1,
+        );
+        let enemy = standing_on(&plain);
+        plain.update(0.1, &[], &[enemy]);
+        weighted.update(0.1, &[], &[enemy]);
+        assert!(!plain.active);
+        assert!(weighted.active);
+    }
+
+    #[test]
+    fn latched_state_survives_a_checkpoint_respawn() {
+        let mut triggers = vec![
+            Trigger::new(100.0, GROUND_Y, TriggerKind::Lever, 1),
+            Trigger::new(300.0, GROUND_Y, PLATE, 2),
+            Trigger::new(500.0, GROUND_Y, TriggerKind::Lever, 3),
+        ];
+        let lever = standing_on(&triggers[0]);
+        triggers[0].interact(lever);
+        let plate = standing_on(&triggers[1]);
+        triggers[1].update(0.1, &[plate], &[]);
+        let saved = latched_states(&triggers);
+        assert_eq!(saved, [true, false, false]);
+
+        // The level is rebuilt on respawn, with every trigger released.
+        let mut respawned = vec![
+            Trigger::new(100.0, GROUND_Y, TriggerKind::Lever, 1),
+            Trigger::new(300.0, GROUND_Y, PLATE, 2),
+            Trigger::new(500.0, GROUND_Y, TriggerKind::Lever, 3),
+        ];
+        restore_latched_states(&mut respawned, &saved);
+        assert!(respawned[0].active);
+        assert!(!respawned[1].active);
+        assert!(!respawned[2].active);
+        assert!(channel_powered(&respawned, 1));
+        assert!(!channel_powered(&respawned, 2));
+    }
+}
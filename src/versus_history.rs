@@ -0,0 +1,325 @@
+use crate::save::{get_save_dir, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HISTORY_FILE: &str = "versus_history.json";
+/// Oldest matches are dropped past this many.
+const MAX_MATCHES: usize = 500;
+
+/// One side of a finished match.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MatchPlayer {
+    pub name: String,
+    pub team: usize,
+    pub cpu: bool,
+    /// Knockouts over the whole match, the score on the end screen.
+    pub kills: u32,
+    pub points: u32,
+    pub best_streak: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MatchRecord {
+    /// Unix seconds when the match finished.
+    pub timestamp: u64,
+    pub arena: String,
+    /// The ruleset as shown on the end screen.
+    pub rules: String,
+    pub players: Vec<MatchPlayer>,
+    /// Winning team, `None` for a draw.
+    pub winner: Option<usize>,
+}
+
+impl MatchRecord {
+    pub fn won(&self, player: &MatchPlayer) -> bool {
+        self.winner == Some(player.team)
+    }
+
+    /// Kills per player in slot order, e.g. "3-1" or "2-0-4-1".
+    pub fn scoreline(&self) -> String {
+        self.players
+            .iter()
+            .map(|p| p.kills.to_string())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
+
+/// Lifetime totals for one name.
+pub struct PlayerRecord {
+    pub name: String,
+    pub played: u32,
+    pub wins: u32,
+    pub most_kills: u32,
+    pub most_points: u32,
+    pub best_streak: u32,
+}
+
+impl PlayerRecord {
+    pub fn win_rate(&self) -> f32 {
+        if self.played == 0 {
+            0.0
+        } else {
+            self.wins as f32 / self.played as f32
+        }
+    }
+}
+
+/// How two names have done against each other. A match counts when they
+/// were on opposing teams; one of them has to have won for it to count as
+/// a win, so a third player taking a free-for-all leaves it undecided.
+pub struct HeadToHead {
+    pub names: (String, String),
+    pub played: u32,
+    pub wins: (u32, u32),
+    pub draws: u32,
+}
+
+impl HeadToHead {
+    /// Share of the decided matches won by the first name.
+    pub fn win_rate(&self) -> f32 {
+        let decided = self.wins.0 + self.wins.1;
+        if decided == 0 {
+            0.5
+        } else {
+            self.wins.0 as f32 / decided as f32
+        }
+    }
+}
+
+/// Every finished Versus match, kept as JSON beside the save files.
+#[derive(Serialize, Deserialize, Default)]
+pub struct VersusHistory {
+    pub matches: Vec<MatchRecord>,
+}
+
+impl VersusHistory {
+    pub fn get_path() -> Result<PathBuf, String> {
+        Ok(get_save_dir()?.join(HISTORY_FILE))
+    }
+
+    /// The stored history; empty when there's none yet.
+    pub fn load() -> Result<Self, String> {
+        Self::load_from_file(&Self::get_path()?)
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = fs::read_to_string(path).map_err(|e| format!("Error reading history: {e}"))?;
+        serde_json::from_str(&json).map_err(|e| format!("Error parsing history: {e}"))
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<(), String> {
+        let json =
+            serde_json::to_string(self).map_err(|e| format!("Error serializing history: {e}"))?;
+        write_atomic(path, json.as_bytes(), false)
+            .map_err(|e| format!("Error writing history: {e}"))
+    }
+
+    /// Adds `record` to the stored history.
+    pub fn append(record: MatchRecord) -> Result<(), String> {
+        let path = Self::get_path()?;
+        let mut history = Self::load_from_file(&path)?;
+        history.push(record);
+        history.save_to_file(&path)
+    }
+
+    pub fn push(&mut self, record: MatchRecord) {
+        self.matches.push(record);
+        if self.matches.len() > MAX_MATCHES {
+            let excess = self.matches.len() - MAX_MATCHES;
+            self.matches.drain(..excess);
+        }
+    }
+
+    /// Totals per name, most matches played first.
+    pub fn player_records(&self) -> Vec<PlayerRecord> {
+        let mut records: Vec<PlayerRecord> = Vec::new();
+        for record in &self.matches {
+            for player in &record.players {
+                let index = match records.iter().position(|r| r.name == player.name) {
+                    Some(index) => index,
+                    None => {
+                        records.push(PlayerRecord {
+                            name: player.name.clone(),
+                            played: 0,
+                            wins: 0,
+                            most_kills: 0,
+                            most_points: 0,
+                            best_streak: 0,
+                        });
+                        records.len() - 1
+                    }
+                };
+                let entry = &mut records[index];
+                entry.played += 1;
+                entry.wins += record.won(player) as u32;
+                entry.most_kills = entry.most_kills.max(player.kills);
+                entry.most_points = entry.most_points.max(player.points);
+                entry.best_streak = entry.best_streak.max(player.best_streak);
+            }
+        }
+        records.sort_by(|a, b| b.played.cmp(&a.played).then(b.wins.cmp(&a.wins)));
+        records
+    }
+
+    /// Every pairing of names that has met, most matches first.
+    pub fn head_to_head(&self) -> Vec<HeadToHead> {
+        let mut pairs: Vec<HeadToHead> = Vec::new();
+        for record in &self.matches {
+            for (i, a) in record.players.iter().enumerate() {
+                for b in &record.players[i + 1..] {
+                    if a.team == b.team || a.name == b.name {
+                        continue;
+                    }
+                    // Stored in name order so either way round finds it.
+                    let (first, second) = if a.name <= b.name { (a, b) } else { (b, a) };
+                    let index = match pairs
+                        .iter()
+                        .position(|p| p.names.0 == first.name && p.names.1 == second.name)
+                    {
+                        Some(index) => index,
+                        None => {
+                            pairs.push(HeadToHead {
+                                names: (first.name.clone(), second.name.clone()),
+                                played: 0,
+                                wins: (0, 0),
+                                draws: 0,
+                            });
+                            pairs.len() - 1
+                        }
+                    };
+                    let pair = &mut pairs[index];
+                    pair.played += 1;
+                    if record.won(first) {
+                        pair.wins.0 += 1;
+                    } else if record.won(second) {
+                        pair.wins.1 += 1;
+                    } else if record.winner.is_none() {
+                        pair.draws += 1;
+                    }
+                }
+            }
+        }
+        pairs.sort_by_key(|p| Reverse(p.played));
+        pairs
+    }
+}
+
+/// `YYYY-MM-DD` for a unix timestamp, in UTC.
+pub fn format_date(timestamp: u64) -> String {
+    // Days to civil date, after Howard Hinnant's `civil_from_days`.
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn player(name: &str, team: usize, kills: u32) -> MatchPlayer {
+        MatchPlayer {
+            name: name.to_string(),
+            team,
+            cpu: false,
+            kills,
+            points: kills * 100,
+            best_streak: kills,
+        }
+    }
+
+    fn record(players: Vec<MatchPlayer>, winner: Option<usize>) -> MatchRecord {
+        MatchRecord {
+            timestamp: 0,
+            arena: "Classic".to_string(),
+            rules: "Deathmatch".to_string(),
+            players,
+            winner,
+        }
+    }
+
+    #[test]
+    fn head_to_head_counts_either_order() {
+        let mut history = VersusHistory::default();
+        history.push(record(
+            vec![player("Ana", 0, 3), player("Bo", 1, 1)],
+            Some(0),
+        ));
+        history.push(record(
+            vec![player("Bo", 0, 2), player("Ana", 1, 0)],
+            Some(0),
+        ));
+        history.push(record(vec![player("Ana", 0, 1), player("Bo", 1, 1)], None));
+        let pairs = history.head_to_head();
+        assert_eq!(pairs.len(), 1);
+        let pair = &pairs[0];
+        assert_eq!(pair.names, ("Ana".to_string(), "Bo".to_string()));
+        assert_eq!((pair.played, pair.wins, pair.draws), (3, (1, 1), 1));
+
+        let records = history.player_records();
+        let ana = records.iter().find(|r| r.name == "Ana").unwrap();
+        assert_eq!((ana.played, ana.wins, ana.most_kills), (3, 1, 3));
+    }
+
+    #[test]
+    fn teammates_are_not_opponents() {
+        let mut history = VersusHistory::default();
+        history.push(record(
+            vec![
+                player("Ana", 0, 1),
+                player("Bo", 1, 0),
+                player("Cy", 0, 2),
+                player("Di", 1, 0),
+            ],
+            Some(0),
+        ));
+        let pairs = history.head_to_head();
+        assert_eq!(pairs.len(), 4);
+        assert!(pairs
+            .iter()
+            .all(|p| p.names != ("Ana".to_string(), "Cy".to_string())));
+    }
+
+    #[test]
+    fn history_round_trips_and_is_capped() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        assert!(VersusHistory::load_from_file(&path)
+            .unwrap()
+            .matches
+            .is_empty());
+        let mut history = VersusHistory::default();
+        for kills in 0..MAX_MATCHES as u32 + 5 {
+            history.push(record(vec![player("Ana", 0, kills)], Some(0)));
+        }
+        history.save_to_file(&path).unwrap();
+        let loaded = VersusHistory::load_from_file(&path).unwrap();
+        assert_eq!(loaded.matches.len(), MAX_MATCHES);
+        assert_eq!(loaded.matches[0].players[0].kills, 5);
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_767_225_599), "2025-12-31");
+    }
+}